@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+/// CSVに書き出す1件のノートの記録
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityRow {
+    pub timestamp: String, // ノートの作成日時（ISO 8601、不明なら空）
+    pub account: String,
+    pub username: String,
+    pub host: String,
+    pub note_type: String, // `NoteType`を`;`区切りで並べたもの（通常のノートは`note`）
+    pub text_length: usize,
+}
+
+const CSV_HEADER: &str = "timestamp,account,username,host,type,text_length";
+
+/// セッション中に流れたノートの記録
+///
+/// メモリを使いすぎないよう、`capacity`件を超えたら古いものから捨てる。
+#[derive(Debug)]
+pub struct ActivityLog {
+    capacity: usize,
+    rows: VecDeque<ActivityRow>,
+}
+
+impl ActivityLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            rows: VecDeque::new(),
+        }
+    }
+
+    pub fn record(&mut self, row: ActivityRow) {
+        if self.rows.len() >= self.capacity {
+            self.rows.pop_front();
+        }
+        self.rows.push_back(row);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// 表計算ソフトで開けるCSV（見出し行付き）にする
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(CSV_HEADER);
+        csv.push('\n');
+        for row in &self.rows {
+            let fields = [
+                csv_field(&row.timestamp),
+                csv_field(&row.account),
+                csv_field(&row.username),
+                csv_field(&row.host),
+                csv_field(&row.note_type),
+                row.text_length.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+
+    /// CSVをファイルに書き出す（Excelで文字化けしないようBOM付きUTF-8）
+    pub fn write_csv(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("\u{feff}{}", self.to_csv()))
+    }
+}
+
+// カンマ・引用符・改行を含む値は引用符で囲む
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,33 @@
+//! Windowsへのログイン時に自動で起動するための登録
+//!
+//! `HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Run` に実行ファイルのパスを書き込む。
+//! 登録の有無は設定ファイルには持たず、毎回レジストリから読む。
+
+use std::io;
+use winreg::enums::{HKEY_CURRENT_USER, KEY_READ, KEY_WRITE};
+use winreg::RegKey;
+
+const RUN_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+const VALUE_NAME: &str = "MisskeyPostViewer";
+
+/// スタートアップに登録されているか
+pub fn is_enabled() -> bool {
+    RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey_with_flags(RUN_KEY, KEY_READ)
+        .and_then(|key| key.get_value::<String, _>(VALUE_NAME))
+        .is_ok()
+}
+
+/// スタートアップへ現在の実行ファイルを登録する、または登録を取り消す
+pub fn set_enabled(enabled: bool) -> io::Result<()> {
+    let key = RegKey::predef(HKEY_CURRENT_USER).open_subkey_with_flags(RUN_KEY, KEY_READ | KEY_WRITE)?;
+    if enabled {
+        let exe = std::env::current_exe()?;
+        key.set_value(VALUE_NAME, &format!("\"{}\"", exe.display()))
+    } else {
+        match key.delete_value(VALUE_NAME) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::borrow::Cow;
+
+use unicode_bidi::BidiInfo;
+
+/// 1行のテキストを描画する順（左から右）に並べ替える
+///
+/// アラビア文字・ヘブライ文字などの右から左に書く部分を反転し、
+/// 行全体の向きは最初の強い方向を持つ文字で決める。
+/// 右から左に書く文字を含まなければそのまま返す。
+///
+/// eguiは文字の結合（アラビア文字の語頭形・語末形など）をしないため、並び順だけを直す。
+pub fn visual_order(line: &str) -> Cow<'_, str> {
+    if !line.chars().any(is_rtl_char) {
+        return Cow::Borrowed(line);
+    }
+    
+    let info = BidiInfo::new(line, None);
+    if !info.has_rtl() {
+        return Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len());
+    for paragraph in &info.paragraphs {
+        out.push_str(&info.reorder_line(paragraph, paragraph.range.clone()));
+    }
+    Cow::Owned(out)
+}
+
+/// 右から左に書く文字か（並べ替えが必要かどうかの大まかな判定）
+fn is_rtl_char(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0590..=0x08FF       // ヘブライ文字・アラビア文字・シリア文字・ターナ文字など
+            | 0xFB1D..=0xFDFF // ヘブライ文字・アラビア文字の表示形
+            | 0xFE70..=0xFEFF // アラビア文字の表示形B
+            | 0x10800..=0x10FFF
+            | 0x1E800..=0x1EFFF
+            | 0x200F // RIGHT-TO-LEFT MARK
+            | 0x202B // RIGHT-TO-LEFT EMBEDDING
+            | 0x202E // RIGHT-TO-LEFT OVERRIDE
+            | 0x2067 // RIGHT-TO-LEFT ISOLATE
+    )
+}
@@ -0,0 +1,271 @@
+use crate::misskey::{ConnectOptions, MiUser};
+use crate::note::{classify, clean_text, ParseError, ParseOptions, ParsedNote, TimelineEvent};
+use futures::StreamExt;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+use tokio_tungstenite::{connect_async_tls_with_config, Connector, tungstenite::client::IntoClientRequest, tungstenite::http::HeaderValue, tungstenite::protocol::Message};
+use url::Url;
+
+/// 既定のJetstreamのサーバー（Blueskyの投稿をJSONで流すfirehose）
+pub const DEFAULT_JETSTREAM_HOST: &str = "jetstream2.us-east.bsky.network";
+
+/// 投稿者のハンドル・表示名・アバターを調べる公開API
+const PUBLIC_APPVIEW: &str = "https://public.api.bsky.app";
+
+/// 投稿者の情報をキャッシュしておく最大の人数（超えたら古い順に忘れる）
+const PROFILE_CACHE_CAPACITY: usize = 10_000;
+
+/// `app.bsky.actor.getProfiles`で一度に調べられる人数
+const PROFILES_PER_REQUEST: usize = 25;
+
+/// firehoseで投稿者をまとめて調べるまでに待つ最長の時間
+const PROFILE_BATCH_WAIT: Duration = Duration::from_secs(1);
+
+/// 投稿者の情報を調べるリクエストのタイムアウト
+const PROFILE_LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+const POST_COLLECTION: &str = "app.bsky.feed.post";
+
+/// 投稿者の表示用の情報
+#[derive(Debug, Clone)]
+struct Profile {
+    handle: String,
+    display_name: Option<String>,
+    avatar: Option<String>,
+}
+
+/// BlueskyのJetstream (`/subscribe`) のクライアント
+///
+/// 流れてくるのは投稿者のDIDだけなので、表示する直前に`resolve_authors`で
+/// ハンドルと表示名を調べる。ログインには対応しておらず、`wanted_dids`を
+/// 指定しなければfirehose全体を受け取る。
+pub struct BlueskyClient {
+    read: futures::stream::SplitStream<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>,
+    profiles: HashMap<String, Option<Profile>>, // DID→投稿者の情報（見つからなかったらNone）
+    profile_order: VecDeque<String>, // キャッシュしたDID（古い順）
+    pending: Vec<ParsedNote>, // 投稿者を調べるのを待っているノート
+    pending_since: Option<Instant>,
+    batch_lookups: bool, // firehose全体を受け取っているので投稿者をまとめて調べる
+    http: reqwest::Client,
+}
+
+impl BlueskyClient {
+    /// `wanted_dids`が空でなければ、その投稿者の投稿だけをサーバー側で絞って受け取る
+    pub async fn connect_with(host: &str, wanted_dids: &[String], options: ConnectOptions) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let protocol = if options.secure { "wss" } else { "ws" };
+        let mut url = Url::parse(&format!("{}://{}/subscribe", protocol, host))?;
+        url.query_pairs_mut().append_pair("wantedCollections", POST_COLLECTION);
+        for did in wanted_dids {
+            url.query_pairs_mut().append_pair("wantedDids", did);
+        }
+
+        println!("Connecting to {}...", url);
+        let mut request = url.as_str().into_client_request()?;
+        request.headers_mut().insert("User-Agent", HeaderValue::from_str(crate::http::user_agent())?);
+        let connector = crate::http::tls_connector(options.accept_invalid_certs)?.map(Connector::NativeTls);
+
+        let (ws_stream, _) = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            connect_async_tls_with_config(request, None, false, connector)
+        ).await??;
+        println!("Connected!");
+
+        // 購読は接続時のクエリで指定するので、書き込み側は使わない
+        let (_write_stream, read_stream) = ws_stream.split();
+
+        // 投稿者を調べる先は公開APIなので、接続先の証明書の設定は関係ない
+        Ok(BlueskyClient {
+            read: read_stream,
+            profiles: HashMap::new(),
+            profile_order: VecDeque::new(),
+            pending: Vec::new(),
+            pending_since: None,
+            batch_lookups: wanted_dids.is_empty(),
+            http: crate::http::client_with_timeout(PROFILE_LOOKUP_TIMEOUT),
+        })
+    }
+
+    pub async fn whoami(&mut self) -> Result<&MiUser, Box<dyn std::error::Error + Send + Sync>> {
+        Err("Blueskyではログインに対応していません".into())
+    }
+
+    /// ブロック・ミュートの同期には対応していない（常に空）
+    pub async fn fetch_hidden_user_ids(&self) -> Result<HashSet<String>, Box<dyn std::error::Error + Send + Sync>> {
+        Ok(HashSet::new())
+    }
+
+    pub fn api_client(&self) -> reqwest::Client {
+        self.http.clone()
+    }
+
+    pub async fn next_message(&mut self) -> Option<Result<Message, tokio_tungstenite::tungstenite::Error>> {
+        self.read.next().await
+    }
+
+    /// 投稿者のハンドル・表示名・アバターを埋め、表示してよくなったノートを返す
+    ///
+    /// 調べていない投稿者は`app.bsky.actor.getProfiles`でまとめて問い合わせる。firehoseでは
+    /// 調べていない投稿者が`PROFILES_PER_REQUEST`人たまるか`PROFILE_BATCH_WAIT`経つまでノートを預かる。
+    /// 一度調べたDIDはキャッシュし、調べられなかった場合はDIDのまま表示する。
+    pub async fn resolve_authors(&mut self, note: ParsedNote) -> Vec<ParsedNote> {
+        self.pending_since.get_or_insert_with(Instant::now);
+        self.pending.push(note);
+
+        let mut unknown: Vec<String> = Vec::new();
+        for did in self.pending.iter().filter_map(|n| n.user_id.as_ref()) {
+            if !self.profiles.contains_key(did) && !unknown.contains(did) {
+                unknown.push(did.clone());
+            }
+        }
+        let waited = self.pending_since.is_some_and(|since| since.elapsed() >= PROFILE_BATCH_WAIT);
+        if self.batch_lookups && !unknown.is_empty() && unknown.len() < PROFILES_PER_REQUEST && !waited {
+            return Vec::new();
+        }
+
+        for dids in unknown.chunks(PROFILES_PER_REQUEST) {
+            // 問い合わせ自体に失敗したときはキャッシュせず、次に見かけたときに調べ直す
+            let Some(mut found) = self.fetch_profiles(dids).await else { continue };
+            for did in dids {
+                let profile = found.remove(did);
+                self.remember(did.clone(), profile);
+            }
+        }
+
+        self.pending_since = None;
+        let mut notes = std::mem::take(&mut self.pending);
+        for note in &mut notes {
+            let Some(Some(profile)) = note.user_id.as_ref().and_then(|did| self.profiles.get(did)) else { continue };
+            note.username = profile.handle.clone();
+            note.name = profile.display_name.clone().filter(|n| !n.is_empty()).unwrap_or_else(|| profile.handle.clone());
+            note.avatar_url = profile.avatar.clone();
+        }
+        notes
+    }
+
+    fn remember(&mut self, did: String, profile: Option<Profile>) {
+        if self.profile_order.len() >= PROFILE_CACHE_CAPACITY {
+            if let Some(oldest) = self.profile_order.pop_front() {
+                self.profiles.remove(&oldest);
+            }
+        }
+        self.profile_order.push_back(did.clone());
+        self.profiles.insert(did, profile);
+    }
+
+    // DID→投稿者の情報（問い合わせに失敗したらNone、見つからなかったDIDは含まれない）
+    async fn fetch_profiles(&self, dids: &[String]) -> Option<HashMap<String, Profile>> {
+        let query: Vec<(&str, &str)> = dids.iter().map(|did| ("actors", did.as_str())).collect();
+        let response = self.http.get(format!("{}/xrpc/app.bsky.actor.getProfiles", PUBLIC_APPVIEW))
+            .query(&query)
+            .send()
+            .await
+            .ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let body: Value = response.json().await.ok()?;
+        Some(body.get("profiles")?
+            .as_array()?
+            .iter()
+            .filter_map(|profile| {
+                let get_str = |key: &str| profile.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+                Some((get_str("did")?, Profile {
+                    handle: get_str("handle")?,
+                    display_name: get_str("displayName"),
+                    avatar: get_str("avatar"),
+                }))
+            })
+            .collect())
+    }
+}
+
+/// Jetstreamのメッセージを`TimelineEvent`として解析する
+///
+/// 投稿の作成はノート、削除は削除、更新は編集として扱う。
+/// ノートIDは`{DID}/{rkey}`で、投稿者の名前は`resolve_authors`で埋めるまでDIDになる。
+pub fn parse_event(text: &str, options: &ParseOptions) -> Result<TimelineEvent, ParseError> {
+    let parsed: Value = serde_json::from_str(text)
+        .map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+    let kind = parsed.get("kind").and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
+    if kind != "commit" {
+        return Ok(TimelineEvent::Other { event_type: kind });
+    }
+
+    let commit = parsed.get("commit")
+        .ok_or_else(|| ParseError::MissingField(kind.clone(), "commit"))?;
+    let operation = required_str(commit, "operation", &kind)?;
+    let collection = required_str(commit, "collection", &kind)?;
+    if collection != POST_COLLECTION {
+        return Ok(TimelineEvent::Other { event_type: format!("{}/{}", collection, operation) });
+    }
+    let did = required_str(&parsed, "did", &kind)?;
+    let note_id = format!("{}/{}", did, required_str(commit, "rkey", &kind)?);
+
+    match operation {
+        "create" => {
+            let record = commit.get("record")
+                .ok_or_else(|| ParseError::MissingField(kind.clone(), "commit.record"))?;
+            Ok(TimelineEvent::Note(Box::new(parse_post(did, note_id, record, options))))
+        }
+        "update" => {
+            let text = commit.get("record")
+                .and_then(|r| r.get("text"))
+                .and_then(|v| v.as_str())
+                .map(|t| clean_text(t, options));
+            Ok(TimelineEvent::Updated { note_id, text, cw: None })
+        }
+        "delete" => Ok(TimelineEvent::Deleted { note_id }),
+        _ => Ok(TimelineEvent::Other { event_type: format!("{}/{}", collection, operation) }),
+    }
+}
+
+fn required_str<'a>(v: &'a Value, key: &'static str, kind: &str) -> Result<&'a str, ParseError> {
+    v.get(key)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ParseError::MissingField(kind.to_string(), key))
+}
+
+// 投稿のレコード (`app.bsky.feed.post`) を`ParsedNote`に変換する
+fn parse_post(did: &str, note_id: String, record: &Value, options: &ParseOptions) -> ParsedNote {
+    let get_str = |v: Option<&Value>, key: &str| {
+        v.and_then(|v| v.get(key)).and_then(|v| v.as_str()).map(|s| s.to_string())
+    };
+    let embed = record.get("embed");
+    let embed_type = get_str(embed, "$type").unwrap_or_default();
+    let has_files = embed_type.starts_with("app.bsky.embed.images") || embed_type.starts_with("app.bsky.embed.video");
+
+    // メンションは本文の装飾 (facets) にDIDで入っている
+    let mentions = record.get("facets")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|facet| facet.get("features")?.as_array())
+        .flatten()
+        .filter(|feature| feature.get("$type").and_then(|v| v.as_str()) == Some("app.bsky.richtext.facet#mention"))
+        .filter_map(|feature| get_str(Some(feature), "did"))
+        .collect();
+
+    let mut parsed = ParsedNote {
+        id: note_id,
+        created_at: get_str(Some(record), "createdAt"),
+        user_id: Some(did.to_string()),
+        name: did.to_string(),
+        username: did.to_string(),
+        user_host: None,
+        avatar_url: None,
+        text: get_str(Some(record), "text")
+            .filter(|t| !t.is_empty())
+            .map(|t| clean_text(&t, options)),
+        cw: None,
+        emojis: Vec::new(),
+        mentions,
+        renote: None,
+        reply_id: get_str(record.get("reply").and_then(|r| r.get("parent")), "uri"),
+        reply: None,
+        note_types: Vec::new(),
+        counts: Default::default(),
+    };
+    parsed.note_types = classify(&parsed, false, has_files);
+    parsed
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::note::NoteType;
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 
 // トークンを難読化するためのシンプルなXOR暗号化 + Base64
@@ -27,6 +29,102 @@ fn deobfuscate_token(encoded: &str) -> Option<String> {
     String::from_utf8(original).ok()
 }
 
+// `--config`で指定された設定ファイル
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// 設定ファイルの場所を`--config`の値で上書きする（起動時に一度だけ）
+pub fn set_config_path(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
+}
+
+/// 設定ファイルの場所
+///
+/// 優先順は `--config` > 環境変数 `MISSKEY_CONFIG` > ユーザーごとの設定フォルダ
+/// (`%APPDATA%\MisskeyPostViewer\config.toml` など)。
+/// 設定フォルダが取得できない環境では従来どおりexeと同じフォルダかカレントディレクトリを使う。
+pub fn config_path() -> PathBuf {
+    if let Some(path) = override_config_path() {
+        return path;
+    }
+    platform_config_path().unwrap_or_else(legacy_config_path)
+}
+
+fn override_config_path() -> Option<PathBuf> {
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return Some(path.clone());
+    }
+    std::env::var_os("MISSKEY_CONFIG")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+}
+
+fn platform_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("MisskeyPostViewer").join("config.toml"))
+}
+
+/// 以前のバージョンの設定ファイルの場所（exeと同じフォルダ、なければカレントディレクトリ）
+fn legacy_config_path() -> PathBuf {
+    let current_dir_config = PathBuf::from("config.toml");
+    let exe_config = std::env::current_exe()
+        .ok()
+        .and_then(|exe_path| exe_path.parent().map(|dir| dir.join("config.toml")));
+    match exe_config {
+        Some(exe_config) if exe_config.exists() || !current_dir_config.exists() => exe_config,
+        _ => current_dir_config,
+    }
+}
+
+/// プロファイルから切り替えたことがないときに、切り替える前の設定を書き出すプロファイル名
+pub const DEFAULT_PROFILE_NAME: &str = "default";
+
+/// プロファイル（名前付きの設定ファイル）を置くフォルダ（設定ファイルと同じ場所の`profiles`）
+pub fn profiles_dir() -> PathBuf {
+    config_path()
+        .parent()
+        .map(|dir| dir.join("profiles"))
+        .unwrap_or_else(|| PathBuf::from("profiles"))
+}
+
+/// `profiles`フォルダにあるプロファイル名（`.toml`を除いたファイル名）を名前順に返す
+pub fn list_profiles() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(profiles_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    names.sort();
+    names
+}
+
+/// exeと同じフォルダにある以前の設定ファイルを、ユーザーごとの設定フォルダへコピーする
+///
+/// 移行先にすでに設定ファイルがある場合や、`--config`などで場所を指定している場合は何もしない。
+pub fn migrate_legacy_config() {
+    if override_config_path().is_some() {
+        return;
+    }
+    let Some(new_path) = platform_config_path() else {
+        return;
+    };
+    let old_path = legacy_config_path();
+    if new_path.exists() || !old_path.exists() {
+        return;
+    }
+    
+    let result = new_path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|_| std::fs::copy(&old_path, &new_path));
+    match result {
+        Ok(_) => println!("[CONFIG] Migrated {:?} -> {:?}", old_path, new_path),
+        Err(e) => eprintln!("[CONFIG] Failed to migrate {:?}: {}", old_path, e),
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub enum TimelineType {
     #[serde(rename = "hybrid")]
@@ -65,6 +163,293 @@ impl TimelineType {
     }
 }
 
+/// アカウントの接続先の種類
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum SourceType {
+    #[default]
+    #[serde(rename = "misskey")]
+    Misskey, // Misskeyとそのフォーク（/streaming）
+    #[serde(rename = "mastodon")]
+    Mastodon, // Mastodon（/api/v1/streaming、トークンは手動で設定）
+    #[serde(rename = "bluesky")]
+    Bluesky, // BlueskyのJetstream（hostはJetstreamのサーバー、ログインなし）
+}
+
+impl SourceType {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            SourceType::Misskey => "misskey",
+            SourceType::Mastodon => "mastodon",
+            SourceType::Bluesky => "bluesky",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            SourceType::Misskey => "Misskey",
+            SourceType::Mastodon => "Mastodon",
+            SourceType::Bluesky => "Bluesky",
+        }
+    }
+}
+
+/// コメント中のユーザー名の表示方法
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum UsernameMode {
+    #[default]
+    #[serde(rename = "inline")]
+    Inline, // 本文と同じ行に表示（comment_formatに従う）
+    #[serde(rename = "above")]
+    Above, // 本文の上に小さく表示
+    #[serde(rename = "hidden")]
+    Hidden, // 表示しない
+}
+
+impl UsernameMode {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            UsernameMode::Inline => "inline",
+            UsernameMode::Above => "above",
+            UsernameMode::Hidden => "hidden",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            UsernameMode::Inline => "本文と同じ行",
+            UsernameMode::Above => "本文の上",
+            UsernameMode::Hidden => "表示しない",
+        }
+    }
+}
+
+/// 投稿時刻を本文のどちら側に付けるか
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum TimestampPosition {
+    #[default]
+    #[serde(rename = "prefix")]
+    Prefix, // 本文の前
+    #[serde(rename = "suffix")]
+    Suffix, // 本文の後ろ
+}
+
+impl TimestampPosition {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            TimestampPosition::Prefix => "prefix",
+            TimestampPosition::Suffix => "suffix",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            TimestampPosition::Prefix => "本文の前",
+            TimestampPosition::Suffix => "本文の後ろ",
+        }
+    }
+}
+
+/// MFM（`$[shake ...]`や`**太字**`など）の扱い
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum MfmMode {
+    #[serde(rename = "raw")]
+    Raw, // 記法をそのまま表示
+    #[default]
+    #[serde(rename = "strip")]
+    Strip, // 記法を取り除いて中身だけ表示
+    #[serde(rename = "render")]
+    Render, // shake/spin などの効果を再現し、それ以外は取り除く
+}
+
+impl MfmMode {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            MfmMode::Raw => "raw",
+            MfmMode::Strip => "strip",
+            MfmMode::Render => "render",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            MfmMode::Raw => "そのまま表示",
+            MfmMode::Strip => "記法を取り除く",
+            MfmMode::Render => "効果を再現する",
+        }
+    }
+}
+
+/// CW（注意書き）付きのノートの表示方法
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum CwMode {
+    #[default]
+    #[serde(rename = "cw_only")]
+    CwOnly, // 注意書きだけを表示し、本文は隠す
+    #[serde(rename = "both")]
+    Both, // 注意書きと本文を続けて表示
+    #[serde(rename = "body_only")]
+    BodyOnly, // 注意書きを無視して本文だけを表示
+}
+
+impl CwMode {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            CwMode::CwOnly => "cw_only",
+            CwMode::Both => "both",
+            CwMode::BodyOnly => "body_only",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            CwMode::CwOnly => "注意書きだけ表示",
+            CwMode::Both => "注意書きと本文を表示",
+            CwMode::BodyOnly => "本文だけ表示",
+        }
+    }
+}
+
+/// 重なったコメントの描く順番
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum ZOrderMode {
+    #[default]
+    #[serde(rename = "newest_on_top")]
+    NewestOnTop, // 新しいコメントを上に描く
+    #[serde(rename = "oldest_on_top")]
+    OldestOnTop, // 古いコメントを上に描く（後から来たものは下にくぐる）
+}
+
+impl ZOrderMode {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            ZOrderMode::NewestOnTop => "newest_on_top",
+            ZOrderMode::OldestOnTop => "oldest_on_top",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            ZOrderMode::NewestOnTop => "新しいコメントを上に",
+            ZOrderMode::OldestOnTop => "古いコメントを上に",
+        }
+    }
+}
+
+/// カスタム絵文字の表示方法
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum EmojiMode {
+    #[default]
+    #[serde(rename = "image")]
+    Image, // 画像で表示
+    #[serde(rename = "shortcode")]
+    Shortcode, // `:name:` のまま表示し、画像は取得しない
+    #[serde(rename = "hidden")]
+    Hidden, // 表示しない
+}
+
+impl EmojiMode {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            EmojiMode::Image => "image",
+            EmojiMode::Shortcode => "shortcode",
+            EmojiMode::Hidden => "hidden",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            EmojiMode::Image => "画像で表示",
+            EmojiMode::Shortcode => "ショートコードで表示",
+            EmojiMode::Hidden => "表示しない",
+        }
+    }
+}
+
+/// 複数アカウントのコメントの流し方
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum StreamLayout {
+    #[default]
+    #[serde(rename = "merged")]
+    Merged, // 全アカウントで画面全体を共有する
+    #[serde(rename = "split_by_account")]
+    SplitByAccount, // アカウントごとに上下の帯に分けて流す
+}
+
+impl StreamLayout {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            StreamLayout::Merged => "merged",
+            StreamLayout::SplitByAccount => "split_by_account",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            StreamLayout::Merged => "まとめて流す",
+            StreamLayout::SplitByAccount => "アカウントごとに段を分ける",
+        }
+    }
+}
+
+/// 見た目の設定をまとめて変えるプリセット
+///
+/// 選ぶと文字色・影・帯などの各項目に書き込むだけなので、そのあと個別に調整できる。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    Classic, // ニコニコ風の白い文字と黒い影
+    Neon, // 鮮やかな色と紫の影
+    Pastel, // 淡い色と柔らかい影
+    ChatBar, // 半透明の帯の上に白い文字
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] = [Theme::Classic, Theme::Neon, Theme::Pastel, Theme::ChatBar];
+
+    pub fn display_name(&self) -> &str {
+        match self {
+            Theme::Classic => "クラシック（白）",
+            Theme::Neon => "ネオン",
+            Theme::Pastel => "パステル",
+            Theme::ChatBar => "チャット欄風",
+        }
+    }
+
+    // アカウントの文字色（アカウントが多ければ繰り返す）
+    fn palette(&self) -> &'static [[u8; 3]] {
+        match self {
+            Theme::Classic | Theme::ChatBar => &[[255, 255, 255]],
+            Theme::Neon => &[[0, 255, 255], [255, 64, 255], [180, 255, 0], [255, 220, 0]],
+            Theme::Pastel => &[[255, 200, 220], [200, 230, 255], [210, 255, 210], [255, 240, 200]],
+        }
+    }
+}
+
+/// ウィンドウの描画に使うバックエンド
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub enum RendererChoice {
+    #[default]
+    #[serde(rename = "glow")]
+    Glow, // OpenGL
+    #[serde(rename = "wgpu")]
+    Wgpu, // Direct3D / Vulkan など（GPUやドライバーによってはこちらの方が安定する）
+}
+
+impl RendererChoice {
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            RendererChoice::Glow => "glow",
+            RendererChoice::Wgpu => "wgpu",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            RendererChoice::Glow => "OpenGL (glow)",
+            RendererChoice::Wgpu => "wgpu",
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Account {
     pub name: String,
@@ -81,6 +466,26 @@ pub struct Account {
     pub enabled: bool, // アカウントの有効/無効
     #[serde(default = "default_text_color")]
     pub text_color: [u8; 3], // RGB色 (デフォルト: 白 [255, 255, 255])
+    #[serde(default = "default_true")]
+    pub show_replies: bool, // リプライも流す
+    #[serde(default)]
+    pub mark_replies: bool, // リプライの先頭に ↩ を付ける
+    #[serde(default)]
+    pub hide_pure_renotes: bool, // コメントなしのリノートを流さない（引用は流す）
+    #[serde(default)]
+    pub server_hide_renotes: bool, // リノートを購読の時点で断る（Misskeyの`withRenotes: false`、サーバーから送られてこなくなる）
+    #[serde(default)]
+    pub skip_backfill: bool, // 購読直後に届く、購読より前の投稿（再接続時に流れてくる分）を捨てる
+    #[serde(default)]
+    pub source: SourceType, // 接続先の種類（Misskey/Mastodon）
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f32, // 流すノートの割合（0.0〜1.0、流量の多いタイムラインを間引く）
+    #[serde(default = "default_true")]
+    pub secure: bool, // wss:// / https:// で接続する（falseならローカル開発用に ws:// / http://）
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool, // サーバー証明書を検証しない（ローカル開発用、危険）
+    #[serde(default)]
+    pub channel_params: serde_json::Value, // 購読時にチャンネルへ渡す追加のパラメータ（Misskeyは例: withReplies・withFiles、Blueskyは`wantedDids`で投稿者を絞る）
 }
 
 impl Account {
@@ -95,6 +500,80 @@ impl Account {
             timeline,
             enabled,
             text_color,
+            show_replies: true,
+            mark_replies: false,
+            hide_pure_renotes: false,
+            server_hide_renotes: false,
+            skip_backfill: false,
+            source: SourceType::default(),
+            sample_rate: default_sample_rate(),
+            secure: true,
+            danger_accept_invalid_certs: false,
+            channel_params: serde_json::Value::Null,
+        }
+    }
+    
+    /// サーバーのURLの起点（`https://{host}`、`secure`でなければ`http://{host}`）
+    pub fn base_url(&self) -> String {
+        crate::http::base_url(&self.host, self.secure)
+    }
+    
+    /// APIのURLの起点（`https://{host}/api`）
+    pub fn api_base(&self) -> String {
+        format!("{}/api", self.base_url())
+    }
+    
+    /// 購読時にチャンネルへ渡すパラメータ
+    ///
+    /// `channel_params`（オブジェクトでなければ空）に、`server_hide_renotes`なら`withRenotes: false`を上書きする。
+    pub fn subscribe_params(&self) -> serde_json::Value {
+        let mut params = match &self.channel_params {
+            serde_json::Value::Object(params) => params.clone(),
+            _ => serde_json::Map::new(),
+        };
+        if self.server_hide_renotes {
+            params.insert("withRenotes".to_string(), serde_json::Value::Bool(false));
+        }
+        serde_json::Value::Object(params)
+    }
+    
+    /// Blueskyで受け取る投稿者のDID（`channel_params`の`wantedDids`、空ならfirehose全体）
+    pub fn wanted_dids(&self) -> Vec<String> {
+        self.channel_params.get("wantedDids")
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+            .filter(|did| !did.is_empty())
+            .map(|did| did.to_string())
+            .collect()
+    }
+    
+    /// 実際に流すノートの割合
+    ///
+    /// Blueskyで投稿者を絞らずにfirehose全体を受け取るときは、`FIREHOSE_SAMPLE_RATE_LIMIT`までに抑える。
+    pub fn effective_sample_rate(&self) -> f32 {
+        if self.source == SourceType::Bluesky && self.wanted_dids().is_empty() {
+            self.sample_rate.min(FIREHOSE_SAMPLE_RATE_LIMIT)
+        } else {
+            self.sample_rate
+        }
+    }
+    
+    /// `channel_params`の真偽値のパラメータ（指定がなければNone）
+    pub fn channel_param(&self, key: &str) -> Option<bool> {
+        self.channel_params.get(key).and_then(|v| v.as_bool())
+    }
+    
+    /// `channel_params`の真偽値のパラメータを設定する（Noneなら取り除いてサーバーの既定に任せる）
+    pub fn set_channel_param(&mut self, key: &str, value: Option<bool>) {
+        if !self.channel_params.is_object() {
+            self.channel_params = serde_json::json!({});
+        }
+        let Some(params) = self.channel_params.as_object_mut() else { return };
+        match value {
+            Some(value) => { params.insert(key.to_string(), serde_json::Value::Bool(value)); }
+            None => { params.remove(key); }
         }
     }
     
@@ -135,8 +614,129 @@ impl Default for Account {
             timeline: TimelineType::default(),
             enabled: true,
             text_color: default_text_color(),
+            show_replies: true,
+            mark_replies: false,
+            hide_pure_renotes: false,
+            server_hide_renotes: false,
+            skip_backfill: false,
+            source: SourceType::default(),
+            sample_rate: default_sample_rate(),
+            secure: true,
+            danger_accept_invalid_certs: false,
+            channel_params: serde_json::Value::Null,
+        }
+    }
+}
+
+/// キーワードに一致したコメントの見た目を変えるルール
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HighlightRule {
+    pub pattern: String,
+    #[serde(default)]
+    pub color: Option<[u8; 3]>, // 指定があればアカウントの文字色を上書き
+    #[serde(default = "default_scale")]
+    pub scale: f32, // 文字サイズの倍率
+    #[serde(default = "default_scale")]
+    pub speed_multiplier: f32, // 速度の倍率（1.0未満で遅くなる）
+}
+
+impl HighlightRule {
+    /// テキストがこのルールのキーワードを含むか（大文字小文字を区別しない）
+    pub fn matches(&self, text: &str) -> bool {
+        keyword_matches(&self.pattern, text)
+    }
+}
+
+/// テキストに最初に一致したハイライトルールを返す
+pub fn find_highlight<'a>(rules: &'a [HighlightRule], text: &str) -> Option<&'a HighlightRule> {
+    rules.iter().find(|rule| rule.matches(text))
+}
+
+impl Default for HighlightRule {
+    fn default() -> Self {
+        Self {
+            pattern: String::new(),
+            color: None,
+            scale: default_scale(),
+            speed_multiplier: default_scale(),
+        }
+    }
+}
+
+/// キーワードに一致したコメントが届いたときに鳴らす効果音
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct SoundTrigger {
+    pub pattern: String,
+    pub sound: PathBuf, // 効果音のファイル（wav / mp3 / ogg / flac）
+}
+
+impl SoundTrigger {
+    /// テキストがこのトリガーのキーワードを含むか（大文字小文字を区別しない）
+    pub fn matches(&self, text: &str) -> bool {
+        keyword_matches(&self.pattern, text)
+    }
+}
+
+/// 大文字小文字を区別せずにキーワードが含まれるか（空のキーワードはどれにも一致しない）
+fn keyword_matches(pattern: &str, text: &str) -> bool {
+    if pattern.is_empty() {
+        return false;
+    }
+    text.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+/// Blueskyのfirehose全体を受け取るときに流すノートの割合の上限（毎秒数十件届くため）
+pub const FIREHOSE_SAMPLE_RATE_LIMIT: f32 = 0.05;
+
+fn default_sample_rate() -> f32 {
+    1.0
+}
+
+fn default_scale() -> f32 {
+    1.0
+}
+
+/// JSONの値をTOMLの値（オブジェクトはインラインテーブル）として書き出す
+///
+/// TOMLにnullはないので、オブジェクト・配列の中のnullは書き出さない。
+fn toml_value(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+    match value {
+        Value::Null => "\"\"".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => toml_string(s),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().filter(|v| !v.is_null()).map(toml_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        Value::Object(map) => {
+            let entries: Vec<String> = map.iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| format!("{} = {}", toml_string(k), toml_value(v)))
+                .collect();
+            if entries.is_empty() { "{}".to_string() } else { format!("{{ {} }}", entries.join(", ")) }
+        }
+    }
+}
+
+/// TOMLの文字列リテラルとしてエスケープ
+fn toml_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
         }
     }
+    out.push('"');
+    out
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -149,24 +749,287 @@ pub struct AppConfig {
     pub debug: bool,
     #[serde(default)]
     pub fallback_font: Option<String>,
+    #[serde(default)]
+    pub highlight_rules: Vec<HighlightRule>,
+    #[serde(default)]
+    pub highlight_mentions: bool, // 自分宛てのメンションを強調表示
+    #[serde(default = "default_comment_format")]
+    pub comment_format: String, // コメントの表示テンプレート（format::render_comment）
+    #[serde(default)]
+    pub show_username: UsernameMode,
+    #[serde(default)]
+    pub color_jitter: u8, // コメントごとに文字色を±この値だけランダムにずらす（0で無効）
+    #[serde(default)]
+    pub length_speed_factor: f32, // 文字数による速度補正の強さ（0で無効、長いほど遅く短いほど速く）
+    #[serde(default)]
+    pub max_lines: usize, // 複数行コメントの最大行数（0で無制限）
+    #[serde(default = "default_true")]
+    pub normalize_whitespace: bool, // 空行や前後の空白を取り除く
+    #[serde(default)]
+    pub render_mfm: MfmMode,
+    #[serde(default)]
+    pub remove_deleted: bool, // 削除されたノートのコメントを流れている途中でも消す
+    #[serde(default)]
+    pub apply_edits: bool, // 編集されたノートのコメントを流れている途中でも書き換える
+    #[serde(default = "default_min_gap_px")]
+    pub min_gap_px: f32, // 同じレーンで前のコメントとの間に空ける最小の間隔（px）
+    #[serde(default = "default_max_concurrent_downloads")]
+    pub max_concurrent_downloads: usize, // 絵文字・プレビュー画像の同時ダウンロード数
+    #[serde(default = "default_download_timeout_secs")]
+    pub download_timeout_secs: u64, // 絵文字・画像・OGPの取得のタイムアウト（秒）
+    #[serde(default)]
+    pub emoji_mode: EmojiMode,
+    #[serde(default)]
+    pub high_contrast: bool, // アクセシビリティ用: 文字色を統一し、太い縁取りと大きめの文字で表示する
+    #[serde(default = "default_text_color")]
+    pub high_contrast_color: [u8; 3], // ハイコントラスト表示の文字色
+    #[serde(default)]
+    pub max_lifetime_secs: Option<f32>, // 画面に出てからこの秒数が経ったコメントは位置に関係なく消す（Noneで無制限）
+    #[serde(default)]
+    pub user_agent: Option<String>, // HTTP・WebSocketで送るUser-Agent（Noneで既定値）
+    #[serde(default)]
+    pub extra_root_cert: Option<String>, // 追加で信頼するルート証明書（PEMファイルのパス）
+    #[serde(default)]
+    pub show_instance_icon: bool, // コメントの先頭に投稿者のサーバーのアイコン（favicon）を表示する
+    #[serde(default)]
+    pub show_avatars: bool, // コメントの先頭に投稿者のアバターを丸く切り抜いて表示する
+    #[serde(default)]
+    pub hidden_note_types: Vec<NoteType>, // 表示しないノートの種類
+    #[serde(default = "default_stats_window_mins")]
+    pub stats_window_mins: u64, // 統計ウィンドウで集計する直近の分数
+    #[serde(default)]
+    pub startup_delay_secs: u64, // 起動直後、最初に接続するまで待つ秒数（ログイン時の自動起動でネットワークの準備を待つ）
+    #[serde(default)]
+    pub renderer: RendererChoice,
+    #[serde(default = "default_target_fps")]
+    pub target_fps: u32, // コメントが流れている間の最大フレームレート（0で無制限）
+    #[serde(default)]
+    pub max_reconnect_attempts: Option<u32>, // 続けて接続に失敗したらこの回数で再接続をやめる（Noneで無制限）
+    #[serde(default)]
+    pub layout: StreamLayout,
+    #[serde(default)]
+    pub width_based_speed: bool, // 描画幅に反比例して速度を決める（長いコメントほど遅く、短いほど速く）
+    #[serde(default)]
+    pub show_counts: bool, // コメントの後ろにリノート・リアクション・リプライの数を表示する
+    #[serde(default)]
+    pub sound_triggers: Vec<SoundTrigger>, // キーワードで鳴らす効果音（最初に一致したもの）
+    #[serde(default = "default_sound_cooldown_secs")]
+    pub sound_cooldown_secs: f32, // 同じ効果音を続けて鳴らさない秒数
+    #[serde(default)]
+    pub sync_server_blocks: bool, // アカウントでブロック・ミュートしているユーザーのノートを流さない（一覧はサーバーから取得）
+    #[serde(default = "default_emoji_anim_speed")]
+    pub emoji_anim_speed: f32, // アニメーション絵文字の再生速度の倍率（0.1〜4.0）
+    #[serde(default)]
+    pub persist_state: bool, // 流れているコメントと表示済みIDを定期的に保存し、異常終了から再起動したときに戻す
+    #[serde(default)]
+    pub show_timestamp: bool, // 各コメントに投稿時刻（ローカル時刻）を付ける
+    #[serde(default = "default_timestamp_format")]
+    pub timestamp_format: String, // 投稿時刻の書式（chronoのstrftime形式）
+    #[serde(default)]
+    pub timestamp_position: TimestampPosition, // 投稿時刻を本文の前後どちらに付けるか
+    #[serde(default)]
+    pub max_emoji_width: Option<f32>, // 絵文字の最大幅（px）。横長の絵文字は高さを縮めて収める（Noneで無制限）
+    #[serde(default)]
+    pub enable_http_source: Option<u16>, // 外部ツールからコメントを受け付けるHTTPサーバーのポート（127.0.0.1のみ、Noneで無効）
+    #[serde(default)]
+    pub http_source_secret: Option<String>, // HTTPで受け付けるときに`Authorization: Bearer`で求める合言葉（必須、空ならサーバーを起動しない）
+    #[serde(default)]
+    pub max_emoji_per_comment: usize, // 1つのコメントで画像として表示する絵文字の最大数（超えた分はショートコードのまま、0で無制限）
+    #[serde(default)]
+    pub focus_host: Option<String>, // このサーバーの投稿者以外のコメントを薄く表示する（Noneで無効）
+    #[serde(default)]
+    pub clear_hotkey: Option<String>, // 画面のコメントをすべて消すグローバルホットキー（例: "ctrl+shift+KeyX"、Noneで無効）
+    #[serde(default = "default_shadow_color")]
+    pub shadow_color: [u8; 4], // 文字の影の色（RGBA）
+    #[serde(default = "default_shadow_offset")]
+    pub shadow_offset: [f32; 2], // 文字の影をずらす量（px、右と下が正）
+    #[serde(default)]
+    pub text_background: Option<[u8; 4]>, // コメントの後ろに敷く角丸の帯の色（RGBA、Noneで無し）
+    #[serde(default = "default_emoji_scale")]
+    pub emoji_scale: f32, // 文字の大きさに対する絵文字の高さの倍率
+    #[serde(default = "default_true")]
+    pub resolve_renote_emojis: bool, // リノート元の本文・投稿者名の絵文字も画像にする（falseでリノート元の絵文字は取得しない）
+    #[serde(default)]
+    pub quote_chain_depth: usize, // 引用の引用…を引用元の下に何段まで表示するか（0で引用元だけ）
+    #[serde(default)]
+    pub cw_mode: CwMode, // CW付きのノートで注意書きと本文のどちらを表示するか
+    #[serde(default)]
+    pub hide_idle_overlay: bool, // 有効なアカウントがないとき、案内を出さずに何も表示しない
+    #[serde(default)]
+    pub anonymize: bool, // 画面に出す名前とユーザー名を、ユーザーごとに決まる仮名（User#1a2b3c4d）に置き換える
+    #[serde(default)]
+    pub anonymize_salt: String, // 仮名のハッシュに混ぜるインストールごとのランダムな値（空なら起動時に作って保存する）
+    #[serde(default)]
+    pub z_order_mode: ZOrderMode, // 重なったコメントのどちらを上に描くか
+    #[serde(default)]
+    pub active_profile: Option<String>, // トレイで最後に切り替えたプロファイル（次に切り替えるとき、今の設定をここに書き戻す）
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_gap_px() -> f32 {
+    20.0
+}
+
+fn default_max_concurrent_downloads() -> usize {
+    4
+}
+
+fn default_download_timeout_secs() -> u64 {
+    10
+}
+
+fn default_stats_window_mins() -> u64 {
+    5
+}
+
+fn default_sound_cooldown_secs() -> f32 {
+    3.0
+}
+
+fn default_emoji_anim_speed() -> f32 {
+    1.0
+}
+
+fn default_timestamp_format() -> String {
+    "%H:%M".to_string()
+}
+
+fn default_target_fps() -> u32 {
+    60
+}
+
+fn default_comment_format() -> String {
+    crate::format::DEFAULT_COMMENT_FORMAT.to_string()
+}
+
+fn default_emoji_scale() -> f32 {
+    1.0
+}
+
+fn default_shadow_color() -> [u8; 4] {
+    [0, 0, 0, 255]
+}
+
+fn default_shadow_offset() -> [f32; 2] {
+    [2.0, 2.0]
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            active_account_index: 0,
+            debug: false,
+            fallback_font: None,
+            highlight_rules: Vec::new(),
+            highlight_mentions: false,
+            comment_format: default_comment_format(),
+            show_username: UsernameMode::default(),
+            color_jitter: 0,
+            length_speed_factor: 0.0,
+            max_lines: 0,
+            normalize_whitespace: true,
+            render_mfm: MfmMode::default(),
+            remove_deleted: false,
+            apply_edits: false,
+            min_gap_px: default_min_gap_px(),
+            max_concurrent_downloads: default_max_concurrent_downloads(),
+            download_timeout_secs: default_download_timeout_secs(),
+            emoji_mode: EmojiMode::default(),
+            high_contrast: false,
+            high_contrast_color: default_text_color(),
+            max_lifetime_secs: None,
+            user_agent: None,
+            extra_root_cert: None,
+            show_instance_icon: false,
+            show_avatars: false,
+            hidden_note_types: Vec::new(),
+            stats_window_mins: default_stats_window_mins(),
+            startup_delay_secs: 0,
+            renderer: RendererChoice::default(),
+            target_fps: default_target_fps(),
+            max_reconnect_attempts: None,
+            layout: StreamLayout::default(),
+            width_based_speed: false,
+            show_counts: false,
+            sound_triggers: Vec::new(),
+            sound_cooldown_secs: default_sound_cooldown_secs(),
+            sync_server_blocks: false,
+            emoji_anim_speed: default_emoji_anim_speed(),
+            persist_state: false,
+            show_timestamp: false,
+            timestamp_format: default_timestamp_format(),
+            timestamp_position: TimestampPosition::default(),
+            max_emoji_width: None,
+            enable_http_source: None,
+            http_source_secret: None,
+            max_emoji_per_comment: 0,
+            focus_host: None,
+            clear_hotkey: None,
+            shadow_color: default_shadow_color(),
+            shadow_offset: default_shadow_offset(),
+            text_background: None,
+            emoji_scale: default_emoji_scale(),
+            resolve_renote_emojis: true,
+            quote_chain_depth: 0,
+            cw_mode: CwMode::default(),
+            hide_idle_overlay: false,
+            anonymize: false,
+            anonymize_salt: String::new(),
+            z_order_mode: ZOrderMode::default(),
+            active_profile: None,
+        }
+    }
 }
 
 impl AppConfig {
     pub fn new() -> Result<Self, config::ConfigError> {
-        let mut builder = config::Config::builder();
-
-        if let Ok(exe_path) = std::env::current_exe() {
-            if let Some(exe_dir) = exe_path.parent() {
-                let exe_config_path = exe_dir.join("config.toml");
-                if exe_config_path.exists() {
-                    builder = builder.add_source(config::File::from(exe_config_path));
-                }
-            }
+        Self::load_from(&config_path())
+    }
+    
+    /// プリセットの文字色・影・帯・絵文字の大きさを書き込む
+    pub fn apply_theme(&mut self, theme: Theme) {
+        let palette = theme.palette();
+        for (i, account) in self.accounts.iter_mut().enumerate() {
+            account.text_color = palette[i % palette.len()];
         }
+        self.high_contrast = false;
+        let (shadow_color, shadow_offset, text_background) = match theme {
+            Theme::Classic => ([0, 0, 0, 255], [2.0, 2.0], None),
+            Theme::Neon => ([60, 0, 120, 255], [2.0, 2.0], None),
+            Theme::Pastel => ([90, 70, 110, 140], [1.5, 1.5], None),
+            Theme::ChatBar => ([0, 0, 0, 0], [0.0, 0.0], Some([0, 0, 0, 150])),
+        };
+        self.shadow_color = shadow_color;
+        self.shadow_offset = shadow_offset;
+        self.text_background = text_background;
+        self.emoji_scale = 1.0;
+    }
+    
+    /// `profiles`フォルダにある名前付きの設定を読み込む
+    pub fn load_profile(name: &str) -> Result<Self, config::ConfigError> {
+        let path = profiles_dir().join(format!("{}.toml", name));
+        if !path.exists() {
+            return Err(config::ConfigError::NotFound(format!("profile {:?}", path)));
+        }
+        Self::load_from(&path)
+    }
+    
+    /// 今の設定を`profiles`フォルダに名前付きで書き出す（同じ名前があれば上書き）
+    pub fn save_profile(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let dir = profiles_dir();
+        std::fs::create_dir_all(&dir)?;
+        self.save_to(&dir.join(format!("{}.toml", name)))
+    }
+    
+    fn load_from(path: &Path) -> Result<Self, config::ConfigError> {
+        let mut builder = config::Config::builder();
 
-        let current_dir_config = PathBuf::from("config.toml");
-        if current_dir_config.exists() {
-            builder = builder.add_source(config::File::from(current_dir_config));
+        if path.exists() {
+            builder = builder.add_source(config::File::from(path));
         }
 
         builder = builder.add_source(config::Environment::with_prefix("MISSKEY"));
@@ -190,32 +1053,136 @@ impl AppConfig {
                 timeline: TimelineType::default(),
                 enabled: true,
                 text_color: default_text_color(),
+                show_replies: true,
+                mark_replies: false,
+                hide_pure_renotes: false,
+                server_hide_renotes: false,
+                skip_backfill: false,
+                source: SourceType::default(),
+                sample_rate: default_sample_rate(),
+                secure: true,
+                danger_accept_invalid_certs: false,
+                channel_params: serde_json::Value::Null,
             });
         }
         
         Ok(config)
     }
     
+    /// 仮名の塩がまだなければ作る（作ったら`true`を返すので、呼び出し側で保存する）
+    pub fn ensure_anonymize_salt(&mut self) -> bool {
+        if !self.anonymize_salt.is_empty() {
+            return false;
+        }
+        self.anonymize_salt = format!("{:016x}", rand::random::<u64>());
+        true
+    }
+    
     pub fn get_active_account(&self) -> Option<&Account> {
         self.accounts.get(self.active_account_index)
     }
     
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.save_to(&config_path())?;
+        println!("保存完了!");
+        Ok(())
+    }
+    
+    /// 指定したパスにTOMLとして書き出す
+    pub fn save_to(&self, config_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         use std::io::Write;
         
         let mut content = String::new();
         content.push_str("# Misskey Post Viewer Configuration\n\n");
         content.push_str(&format!("active_account_index = {}\n", self.active_account_index));
         content.push_str(&format!("debug = {}\n", self.debug));
+        content.push_str(&format!("highlight_mentions = {}\n", self.highlight_mentions));
+        content.push_str(&format!("comment_format = {}\n", toml_string(&self.comment_format)));
+        content.push_str(&format!("show_username = \"{}\"\n", self.show_username.to_config_str()));
+        content.push_str(&format!("color_jitter = {}\n", self.color_jitter));
+        content.push_str(&format!("length_speed_factor = {}\n", self.length_speed_factor));
+        content.push_str(&format!("max_lines = {}\n", self.max_lines));
+        content.push_str(&format!("normalize_whitespace = {}\n", self.normalize_whitespace));
+        content.push_str(&format!("render_mfm = \"{}\"\n", self.render_mfm.to_config_str()));
+        content.push_str(&format!("remove_deleted = {}\n", self.remove_deleted));
+        content.push_str(&format!("apply_edits = {}\n", self.apply_edits));
+        content.push_str(&format!("min_gap_px = {}\n", self.min_gap_px));
+        content.push_str(&format!("max_concurrent_downloads = {}\n", self.max_concurrent_downloads));
+        content.push_str(&format!("download_timeout_secs = {}\n", self.download_timeout_secs));
+        content.push_str(&format!("emoji_mode = \"{}\"\n", self.emoji_mode.to_config_str()));
+        content.push_str(&format!("high_contrast = {}\n", self.high_contrast));
+        content.push_str(&format!("high_contrast_color = [{}, {}, {}]\n", self.high_contrast_color[0], self.high_contrast_color[1], self.high_contrast_color[2]));
+        if let Some(secs) = self.max_lifetime_secs {
+            content.push_str(&format!("max_lifetime_secs = {}\n", secs));
+        }
+        if let Some(user_agent) = &self.user_agent {
+            content.push_str(&format!("user_agent = {}\n", toml_string(user_agent)));
+        }
+        if let Some(path) = &self.extra_root_cert {
+            content.push_str(&format!("extra_root_cert = {}\n", toml_string(path)));
+        }
+        content.push_str(&format!("show_instance_icon = {}\n", self.show_instance_icon));
+        content.push_str(&format!("show_avatars = {}\n", self.show_avatars));
+        let hidden_note_types: Vec<String> = self.hidden_note_types.iter().map(|t| format!("\"{}\"", t.to_config_str())).collect();
+        content.push_str(&format!("hidden_note_types = [{}]\n", hidden_note_types.join(", ")));
+        content.push_str(&format!("stats_window_mins = {}\n", self.stats_window_mins));
+        content.push_str(&format!("startup_delay_secs = {}\n", self.startup_delay_secs));
+        content.push_str(&format!("renderer = \"{}\"\n", self.renderer.to_config_str()));
+        content.push_str(&format!("target_fps = {}\n", self.target_fps));
+        if let Some(attempts) = self.max_reconnect_attempts {
+            content.push_str(&format!("max_reconnect_attempts = {}\n", attempts));
+        }
+        content.push_str(&format!("layout = \"{}\"\n", self.layout.to_config_str()));
+        content.push_str(&format!("width_based_speed = {}\n", self.width_based_speed));
+        content.push_str(&format!("show_counts = {}\n", self.show_counts));
+        content.push_str(&format!("sound_cooldown_secs = {}\n", self.sound_cooldown_secs));
+        content.push_str(&format!("sync_server_blocks = {}\n", self.sync_server_blocks));
+        content.push_str(&format!("emoji_anim_speed = {}\n", self.emoji_anim_speed));
+        content.push_str(&format!("persist_state = {}\n", self.persist_state));
+        content.push_str(&format!("show_timestamp = {}\n", self.show_timestamp));
+        content.push_str(&format!("timestamp_format = {}\n", toml_string(&self.timestamp_format)));
+        content.push_str(&format!("timestamp_position = \"{}\"\n", self.timestamp_position.to_config_str()));
+        if let Some(width) = self.max_emoji_width {
+            content.push_str(&format!("max_emoji_width = {}\n", width));
+        }
+        if let Some(port) = self.enable_http_source {
+            content.push_str(&format!("enable_http_source = {}\n", port));
+        }
+        if let Some(secret) = &self.http_source_secret {
+            content.push_str(&format!("http_source_secret = {}\n", toml_string(secret)));
+        }
+        content.push_str(&format!("max_emoji_per_comment = {}\n", self.max_emoji_per_comment));
+        if let Some(host) = &self.focus_host {
+            content.push_str(&format!("focus_host = {}\n", toml_string(host)));
+        }
+        if let Some(keys) = &self.clear_hotkey {
+            content.push_str(&format!("clear_hotkey = {}\n", toml_string(keys)));
+        }
+        content.push_str(&format!("shadow_color = [{}, {}, {}, {}]\n", self.shadow_color[0], self.shadow_color[1], self.shadow_color[2], self.shadow_color[3]));
+        content.push_str(&format!("shadow_offset = [{}, {}]\n", self.shadow_offset[0], self.shadow_offset[1]));
+        if let Some([r, g, b, a]) = self.text_background {
+            content.push_str(&format!("text_background = [{}, {}, {}, {}]\n", r, g, b, a));
+        }
+        content.push_str(&format!("emoji_scale = {}\n", self.emoji_scale));
+        content.push_str(&format!("resolve_renote_emojis = {}\n", self.resolve_renote_emojis));
+        content.push_str(&format!("quote_chain_depth = {}\n", self.quote_chain_depth));
+        content.push_str(&format!("cw_mode = \"{}\"\n", self.cw_mode.to_config_str()));
+        content.push_str(&format!("hide_idle_overlay = {}\n", self.hide_idle_overlay));
+        content.push_str(&format!("anonymize = {}\n", self.anonymize));
+        content.push_str(&format!("anonymize_salt = {}\n", toml_string(&self.anonymize_salt)));
+        content.push_str(&format!("z_order_mode = \"{}\"\n", self.z_order_mode.to_config_str()));
         if let Some(font) = &self.fallback_font {
-            content.push_str(&format!("fallback_font = \"{}\"\n", font));
+            content.push_str(&format!("fallback_font = {}\n", toml_string(font)));
+        }
+        if let Some(profile) = &self.active_profile {
+            content.push_str(&format!("active_profile = {}\n", toml_string(profile)));
         }
         content.push_str("\n");
         
         for account in &self.accounts {
             content.push_str("[[accounts]]\n");
-            content.push_str(&format!("name = \"{}\"\n", account.name));
-            content.push_str(&format!("host = \"{}\"\n", account.host));
+            content.push_str(&format!("name = {}\n", toml_string(&account.name)));
+            content.push_str(&format!("host = {}\n", toml_string(&account.host)));
             // トークンは難読化して保存
             if let Some(token) = &account.token {
                 let obfuscated = obfuscate_token(token);
@@ -232,24 +1199,45 @@ impl AppConfig {
             content.push_str(&format!("enabled = {}\n", account.enabled));
             content.push_str(&format!("text_color = [{}, {}, {}]\n", 
                 account.text_color[0], account.text_color[1], account.text_color[2]));
+            content.push_str(&format!("show_replies = {}\n", account.show_replies));
+            content.push_str(&format!("mark_replies = {}\n", account.mark_replies));
+            content.push_str(&format!("hide_pure_renotes = {}\n", account.hide_pure_renotes));
+            content.push_str(&format!("server_hide_renotes = {}\n", account.server_hide_renotes));
+            content.push_str(&format!("skip_backfill = {}\n", account.skip_backfill));
+            content.push_str(&format!("source = \"{}\"\n", account.source.to_config_str()));
+            content.push_str(&format!("sample_rate = {}\n", account.sample_rate));
+            content.push_str(&format!("secure = {}\n", account.secure));
+            content.push_str(&format!("danger_accept_invalid_certs = {}\n", account.danger_accept_invalid_certs));
+            if account.channel_params.as_object().is_some_and(|params| !params.is_empty()) {
+                content.push_str(&format!("channel_params = {}\n", toml_value(&account.channel_params)));
+            }
             content.push_str("\n");
         }
         
-        if let Ok(exe_path) = std::env::current_exe() {
-            if let Some(exe_dir) = exe_path.parent() {
-                let config_path = exe_dir.join("config.toml");
-                println!("設定ファイルを保存: {:?}", config_path);
-                println!("保存するアカウント数: {}", self.accounts.len());
-                let mut file = std::fs::File::create(&config_path)?;
-                file.write_all(content.as_bytes())?;
-                println!("保存完了!");
-                return Ok(());
+        for rule in &self.highlight_rules {
+            content.push_str("[[highlight_rules]]\n");
+            content.push_str(&format!("pattern = {}\n", toml_string(&rule.pattern)));
+            if let Some(color) = rule.color {
+                content.push_str(&format!("color = [{}, {}, {}]\n", color[0], color[1], color[2]));
             }
+            content.push_str(&format!("scale = {}\n", rule.scale));
+            content.push_str(&format!("speed_multiplier = {}\n", rule.speed_multiplier));
+            content.push('\n');
+        }
+        
+        for trigger in &self.sound_triggers {
+            content.push_str("[[sound_triggers]]\n");
+            content.push_str(&format!("pattern = {}\n", toml_string(&trigger.pattern)));
+            content.push_str(&format!("sound = {}\n", toml_string(&trigger.sound.to_string_lossy())));
+            content.push('\n');
         }
         
-        let current_dir_config = PathBuf::from("config.toml");
-        println!("設定ファイルを保存: {:?}", current_dir_config);
-        let mut file = std::fs::File::create(current_dir_config)?;
+        println!("設定ファイルを保存: {:?}", config_path);
+        if let Some(parent) = config_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        println!("保存するアカウント数: {}", self.accounts.len());
+        let mut file = std::fs::File::create(config_path)?;
         file.write_all(content.as_bytes())?;
         Ok(())
     }
@@ -0,0 +1,51 @@
+use std::collections::{HashSet, VecDeque};
+
+/// 最近表示したノートIDを覚えておき、同じノートを二度流さないようにする
+///
+/// アプリの起動中ずっと共有して使う。設定の再適用などで接続をやり直しても
+/// 中身は消さないので、再接続直後に同じノートが届いても表示しない。
+/// 古いものから順に忘れるため、メモリ使用量は`capacity`件分で頭打ちになる。
+#[derive(Debug)]
+pub struct RecentIds {
+    capacity: usize,
+    order: VecDeque<String>,
+    ids: HashSet<String>,
+}
+
+impl RecentIds {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            ids: HashSet::with_capacity(capacity),
+        }
+    }
+
+    /// IDを記録し、初めて見たものなら`true`を返す
+    pub fn insert(&mut self, id: &str) -> bool {
+        if self.ids.contains(id) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.ids.remove(&oldest);
+            }
+        }
+        self.order.push_back(id.to_string());
+        self.ids.insert(id.to_string());
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// 覚えているIDを古い順に返す（状態の保存用）
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.order.iter().map(|id| id.as_str())
+    }
+}
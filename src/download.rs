@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::sync::Semaphore;
+
+/// ダウンロード結果（失敗時はエラー内容）
+pub type DownloadResult = Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>>;
+
+/// 一時的なエラーのときに試す最大回数（初回を含む）
+const MAX_ATTEMPTS: u32 = 3;
+
+/// 再試行までの待ち時間（回数ごとに倍にする）
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// 失敗の種類
+enum Failure {
+    /// 通信エラーや5xxなど、待てば成功しうるもの
+    Retryable(Box<dyn std::error::Error + Send + Sync>),
+    /// 404など、再試行しても変わらないもの
+    Permanent(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// 絵文字やプレビュー画像のダウンロードをアプリのTokioランタイム上で行う
+///
+/// HTTPクライアントを共有して接続を使い回し、同時に実行するダウンロードは最大`max_concurrent`件に制限する。
+/// OGPの取得なども`client()`で同じクライアントを使う。
+pub struct DownloadPool {
+    handle: Handle,
+    client: reqwest::Client,
+    permits: Arc<Semaphore>,
+}
+
+impl DownloadPool {
+    pub fn new(handle: Handle, max_concurrent: usize, timeout: Duration) -> Self {
+        let client = crate::http::client_builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_else(|e| {
+                eprintln!("[DOWNLOAD] Failed to create HTTP client: {}", e);
+                reqwest::Client::new()
+            });
+        Self {
+            handle,
+            client,
+            permits: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+    
+    /// 共有のHTTPクライアント（中身は参照カウントなので複製は軽い）
+    pub fn client(&self) -> reqwest::Client {
+        self.client.clone()
+    }
+    
+    /// URLをダウンロードし、結果を`on_done`に渡す
+    ///
+    /// 一時的なエラーなら待ち時間を倍にしながら`MAX_ATTEMPTS`回まで試す。
+    /// `on_done`は画像のデコードなど重い処理をしてもよいように、ブロッキング用のスレッドで呼ばれる。
+    pub fn fetch(&self, url: String, on_done: impl FnOnce(DownloadResult) + Send + 'static) {
+        let client = self.client.clone();
+        let permits = self.permits.clone();
+        self.handle.spawn(async move {
+            let mut attempt = 1;
+            let result = loop {
+                let outcome = match permits.clone().acquire_owned().await {
+                    // 枠はダウンロードが終わるまで保持し、再試行の待ち時間中は手放す
+                    Ok(_permit) => download(&client, &url).await,
+                    Err(e) => Err(Failure::Permanent(e.into())),
+                };
+                match outcome {
+                    Ok(bytes) => break Ok(bytes),
+                    Err(Failure::Retryable(_)) if attempt < MAX_ATTEMPTS => {
+                        tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+                        attempt += 1;
+                    }
+                    Err(Failure::Retryable(e)) | Err(Failure::Permanent(e)) => break Err(e),
+                }
+            };
+            let _ = tokio::task::spawn_blocking(move || on_done(result)).await;
+        });
+    }
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>, Failure> {
+    let response = client.get(url).send().await.map_err(|e| Failure::Retryable(e.into()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = format!("HTTP {}", status).into();
+        return Err(if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Failure::Retryable(error)
+        } else {
+            Failure::Permanent(error)
+        });
+    }
+    let bytes = response.bytes().await.map_err(|e| Failure::Retryable(e.into()))?;
+    Ok(bytes.to_vec())
+}
@@ -1,12 +1,73 @@
+use crate::download::DownloadPool;
 use egui::{ColorImage, TextureHandle, Context};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Clone)]
+/// ダウンロードに失敗した絵文字・画像を、この時間が経ったら取得し直す
+pub const FAILURE_TTL: Duration = Duration::from_secs(300);
+
+/// 1つのノートから読み込む絵文字の最大数（これを超える分は無視する）
+pub const MAX_NOTE_EMOJIS: usize = 256;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EmojiInfo {
     pub name: String,
     pub url: String,
 }
 
+/// ノートの`emojis`フィールドを`EmojiInfo`の一覧に正規化する
+///
+/// 名前→URLのマップ形式（従来）と`{name, url}`の配列形式（新しいMisskeyや一部のフォーク）の両方に対応。
+/// 名前が`:name:`のようにコロン付きで来ても取り除く。
+/// 極端に多い場合は先頭の`MAX_NOTE_EMOJIS`個だけを使う。
+pub fn parse_emoji_list(value: &serde_json::Value) -> Vec<EmojiInfo> {
+    use serde_json::Value;
+    
+    match value {
+        Value::Object(emoji_map) => emoji_map
+            .iter()
+            .filter_map(|(name, url)| normalize(name, url.as_str()?))
+            .take(MAX_NOTE_EMOJIS)
+            .collect(),
+        Value::Array(emoji_list) => emoji_list
+            .iter()
+            .filter_map(|emoji| {
+                let name = emoji.get("name")?.as_str()?;
+                let url = emoji.get("url")?.as_str()?;
+                normalize(name, url)
+            })
+            .take(MAX_NOTE_EMOJIS)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// `/api/emojis`の応答（`{"emojis": [{name, url, ...}]}`）からサーバーの絵文字の一覧を取り出す
+///
+/// サーバー全体の一覧なので、ノート用の`MAX_NOTE_EMOJIS`の上限はかけない。
+pub fn parse_emoji_catalog(value: &serde_json::Value) -> Vec<EmojiInfo> {
+    value.get("emojis")
+        .and_then(|v| v.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|emoji| normalize(emoji.get("name")?.as_str()?, emoji.get("url")?.as_str()?))
+        .collect()
+}
+
+// 名前のコロンを取り除き、名前かURLが空なら捨てる
+fn normalize(name: &str, url: &str) -> Option<EmojiInfo> {
+    let name = name.trim_matches(':');
+    if name.is_empty() || url.is_empty() {
+        return None;
+    }
+    Some(EmojiInfo {
+        name: name.to_string(),
+        url: url.to_string(),
+    })
+}
+
 pub struct AnimatedEmoji {
     pub frames: Vec<ColorImage>,
     pub frame_durations: Vec<u32>, // ミリ秒
@@ -15,16 +76,43 @@ pub struct AnimatedEmoji {
     pub elapsed_ms: u32,
 }
 
+impl AnimatedEmoji {
+    /// 経過時間だけフレームを進める
+    ///
+    /// 描画が遅れて`dt_ms`が複数フレーム分になっても、その分まとめて進めて実時間に合わせる。
+    pub fn advance(&mut self, dt_ms: u32) {
+        let frame_count = self.frame_durations.len();
+        let total_ms: u32 = self.frame_durations.iter().sum();
+        if frame_count == 0 || total_ms == 0 {
+            return;
+        }
+        self.current_frame %= frame_count;
+        // 1周以上進む分は同じフレームに戻るだけなので飛ばす
+        self.elapsed_ms = (self.elapsed_ms + dt_ms) % total_ms;
+        while self.elapsed_ms >= self.frame_durations[self.current_frame] {
+            self.elapsed_ms -= self.frame_durations[self.current_frame];
+            self.current_frame = (self.current_frame + 1) % frame_count;
+        }
+    }
+}
+
 pub struct EmojiCache {
     pub static_cache: HashMap<String, Option<TextureHandle>>,
     pub animated_cache: HashMap<String, AnimatedEmoji>,
     pub downloading: HashMap<String, bool>,
     pub rx: std::sync::mpsc::Receiver<(String, Vec<u8>)>,
     pub tx: std::sync::mpsc::Sender<(String, Vec<u8>)>,
+    pool: Arc<DownloadPool>, // 同時ダウンロード数の制限
+    failed_at: HashMap<String, Instant>, // ダウンロードに失敗した時刻（FAILURE_TTL後に再取得）
+    animation_speed: f32, // アニメーション絵文字の再生速度の倍率
 }
 
+// アニメーション絵文字の再生速度の倍率の範囲
+const MIN_ANIMATION_SPEED: f32 = 0.1;
+const MAX_ANIMATION_SPEED: f32 = 4.0;
+
 impl EmojiCache {
-    pub fn new() -> Self {
+    pub fn new(pool: Arc<DownloadPool>) -> Self {
         let (tx, rx) = std::sync::mpsc::channel::<(String, Vec<u8>)>();
         Self {
             static_cache: HashMap::new(),
@@ -32,9 +120,21 @@ impl EmojiCache {
             downloading: HashMap::new(),
             rx,
             tx,
+            pool,
+            failed_at: HashMap::new(),
+            animation_speed: 1.0,
         }
     }
 
+    /// すべてのアニメーション絵文字の再生速度の倍率を設定する（0.1〜4.0に収める）
+    pub fn set_animation_speed(&mut self, speed: f32) {
+        self.animation_speed = if speed.is_finite() {
+            speed.clamp(MIN_ANIMATION_SPEED, MAX_ANIMATION_SPEED)
+        } else {
+            1.0
+        };
+    }
+
     pub fn load_emoji(&mut self, _ctx: &Context, url: &str, debug_mode: bool) -> Option<TextureHandle> {
         // アニメーションキャッシュをチェック
         if self.animated_cache.contains_key(url) {
@@ -43,9 +143,15 @@ impl EmojiCache {
             }
         }
         
-        // 静止画キャッシュをチェック
+        // 静止画キャッシュをチェック（ダウンロード失敗はしばらくしたら取得し直す）
         if let Some(cached) = self.static_cache.get(url) {
-            return cached.clone();
+            let expired = cached.is_none()
+                && self.failed_at.get(url).is_some_and(|at| at.elapsed() >= FAILURE_TTL);
+            if !expired {
+                return cached.clone();
+            }
+            self.static_cache.remove(url);
+            self.failed_at.remove(url);
         }
         
         // ダウンロード中かチェック
@@ -58,119 +164,43 @@ impl EmojiCache {
         let url_clone = url.to_string();
         let emoji_tx = self.tx.clone();
         
-        // 別スレッドでダウンロード
-        std::thread::spawn(move || {
-            use std::time::Duration;
-            
-            let client = reqwest::blocking::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build();
-            
-            match client {
-                Ok(client) => {
-                    match client.get(&url_clone).send() {
-                        Ok(response) => {
-                            if response.status().is_success() {
-                                match response.bytes() {
-                                    Ok(bytes) => {
-                                        let _ = emoji_tx.send((url_clone.clone(), bytes.to_vec()));
-                                    }
-                                    Err(e) => {
-                                        if debug_mode { eprintln!("Failed to read emoji bytes from {}: {}", url_clone, e); }
-                                        // 失敗をキャッシュに記録（空のVecで）
-                                        let _ = emoji_tx.send((url_clone.clone(), Vec::new()));
-                                    }
-                                }
-                            } else {
-                                if debug_mode { eprintln!("Failed to download emoji (HTTP {}): {}", response.status(), url_clone); }
-                                let _ = emoji_tx.send((url_clone.clone(), Vec::new()));
-                            }
-                        }
-                        Err(e) => {
-                            if debug_mode { eprintln!("Failed to download emoji from {}: {}", url_clone, e); }
-                            let _ = emoji_tx.send((url_clone.clone(), Vec::new()));
-                        }
-                    }
-                }
+        // 共有のダウンロードプールで取得（同時実行数を超えた分は順番待ち）
+        self.pool.fetch(url_clone.clone(), move |result| {
+            let bytes = match result {
+                Ok(bytes) => bytes,
                 Err(e) => {
-                    if debug_mode { eprintln!("Failed to create HTTP client: {}", e); }
-                    let _ = emoji_tx.send((url_clone.clone(), Vec::new()));
+                    if debug_mode { eprintln!("Failed to download emoji from {}: {}", url_clone, e); }
+                    // 失敗をキャッシュに記録（空のVecで）
+                    Vec::new()
                 }
-            }
+            };
+            let _ = emoji_tx.send((url_clone, bytes));
         });
         
         None
     }
 
     pub fn load_gif_frames(&mut self, ctx: &Context, url: &str, bytes: &[u8]) -> Result<Vec<TextureHandle>, Box<dyn std::error::Error>> {
-        use image::AnimationDecoder;
-        use image::codecs::gif::GifDecoder;
-        use std::io::Cursor;
-        
-        let cursor = Cursor::new(bytes);
-        let decoder = GifDecoder::new(cursor)?;
-        let frames = decoder.into_frames().collect_frames()?;
-        
-        let mut textures = Vec::new();
-        let mut frame_durations = Vec::new();
-        
-        for frame in frames {
-            let delay = frame.delay();
-            let duration_ms = (delay.numer_denom_ms().0 as f32 / delay.numer_denom_ms().1 as f32) as u32;
-            frame_durations.push(duration_ms);
-            
-            let img = frame.into_buffer();
-            let size = [img.width() as usize, img.height() as usize];
-            let pixels = img.into_raw();
-            let color_image = ColorImage::from_rgba_unmultiplied(size, &pixels);
-            
-            let texture = ctx.load_texture(
-                &format!("{}_frame_{}", url, textures.len()),
-                color_image.clone(),
-                egui::TextureOptions::LINEAR,
-            );
-            textures.push(texture);
-        }
-        
-        if !textures.is_empty() {
-            self.animated_cache.insert(url.to_string(), AnimatedEmoji {
-                frames: Vec::new(),
-                frame_durations,
-                textures: textures.clone(),
-                current_frame: 0,
-                elapsed_ms: 0,
-            });
-        }
-        
-        Ok(textures)
+        let frames = decode_gif_frames(bytes)?;
+        Ok(self.cache_animation(ctx, url, frames))
     }
 
     pub fn load_apng_frames(&mut self, ctx: &Context, url: &str, bytes: &[u8]) -> Result<Vec<TextureHandle>, Box<dyn std::error::Error>> {
-        use image::codecs::png::PngDecoder;
-        use image::AnimationDecoder;
-        use std::io::Cursor;
-        
-        let cursor = Cursor::new(bytes);
-        let decoder = PngDecoder::new(cursor)?;
-        
-        // APNGかどうかをチェック
-        if !decoder.is_apng()? {
-            return Err("Not an APNG file".into());
-        }
-        
-        let frames = decoder.apng()?
-            .into_frames()
-            .collect_frames()?;
-        
+        let frames = decode_apng_frames(bytes)?
+            .into_iter()
+            .map(|(img, duration_ms)| (img, duration_ms.max(10))) // 最小10ms
+            .collect();
+        Ok(self.cache_animation(ctx, url, frames))
+    }
+
+    // 合成済みのフレームをテクスチャにしてアニメーションとしてキャッシュする
+    fn cache_animation(&mut self, ctx: &Context, url: &str, frames: Vec<(image::RgbaImage, u32)>) -> Vec<TextureHandle> {
         let mut textures = Vec::new();
         let mut frame_durations = Vec::new();
         
-        for frame in frames {
-            let delay = frame.delay();
-            let duration_ms = (delay.numer_denom_ms().0 as f32 / delay.numer_denom_ms().1 as f32) as u32;
-            frame_durations.push(duration_ms.max(10)); // 最小10ms
+        for (img, duration_ms) in frames {
+            frame_durations.push(duration_ms);
             
-            let img = frame.into_buffer();
             let size = [img.width() as usize, img.height() as usize];
             let pixels = img.into_raw();
             let color_image = ColorImage::from_rgba_unmultiplied(size, &pixels);
@@ -193,20 +223,19 @@ impl EmojiCache {
             });
         }
         
-        Ok(textures)
+        textures
     }
 
-    pub fn update_animations(&mut self, dt_ms: u32) {
-        for anim in self.animated_cache.values_mut() {
-            anim.elapsed_ms += dt_ms;
-            
-            if anim.current_frame < anim.frame_durations.len() {
-                let current_duration = anim.frame_durations[anim.current_frame];
-                if anim.elapsed_ms >= current_duration {
-                    anim.elapsed_ms -= current_duration;
-                    anim.current_frame = (anim.current_frame + 1) % anim.textures.len();
-                }
+    /// `active_urls`に含まれるアニメーション絵文字だけフレームを進める
+    ///
+    /// 画面に出ていない絵文字は止めておき、次に表示されたときに続きから再生する。
+    pub fn update_animations(&mut self, dt_ms: u32, active_urls: &HashSet<String>) {
+        let dt_ms = (dt_ms as f32 * self.animation_speed).round() as u32;
+        for (url, anim) in self.animated_cache.iter_mut() {
+            if !active_urls.contains(url) {
+                continue;
             }
+            anim.advance(dt_ms);
         }
     }
 
@@ -217,6 +246,7 @@ impl EmojiCache {
             // 空のbytes配列は失敗を意味する
             if bytes.is_empty() {
                 if debug_mode { eprintln!("Emoji download failed (empty bytes): {}", url); }
+                self.failed_at.insert(url.clone(), Instant::now());
                 self.static_cache.insert(url, None);
                 continue;
             }
@@ -278,3 +308,51 @@ impl EmojiCache {
         }
     }
 }
+
+/// GIFを、それぞれ画像全体の大きさに合成したフレームと表示時間（ミリ秒）に分解する
+///
+/// 画像の一部だけを書き換えるフレームも、前のフレームに重ねた状態で返す
+/// （処分方法 (disposal) は`image`のデコーダーが反映する）。
+pub fn decode_gif_frames(bytes: &[u8]) -> Result<Vec<(image::RgbaImage, u32)>, Box<dyn std::error::Error>> {
+    use image::codecs::gif::GifDecoder;
+    use image::{AnimationDecoder, ImageDecoder};
+    
+    let decoder = GifDecoder::new(std::io::Cursor::new(bytes))?;
+    let (width, height) = decoder.dimensions();
+    Ok(coalesce_frames(decoder.into_frames().collect_frames()?, width, height))
+}
+
+/// APNGを、それぞれ画像全体の大きさに合成したフレームと表示時間（ミリ秒）に分解する
+pub fn decode_apng_frames(bytes: &[u8]) -> Result<Vec<(image::RgbaImage, u32)>, Box<dyn std::error::Error>> {
+    use image::codecs::png::PngDecoder;
+    use image::{AnimationDecoder, ImageDecoder};
+    
+    let decoder = PngDecoder::new(std::io::Cursor::new(bytes))?;
+    
+    // APNGかどうかをチェック
+    if !decoder.is_apng()? {
+        return Err("Not an APNG file".into());
+    }
+    let (width, height) = decoder.dimensions();
+    Ok(coalesce_frames(decoder.apng()?.into_frames().collect_frames()?, width, height))
+}
+
+// フレームを画像全体の大きさにそろえる
+//
+// 全体を覆うフレームはそのまま使い、位置がずれていたり小さかったりするフレームは
+// 直前のフレームの上に重ねる（そのまま貼ると残りの部分が抜けて表示が崩れる）。
+fn coalesce_frames(frames: Vec<image::Frame>, width: u32, height: u32) -> Vec<(image::RgbaImage, u32)> {
+    let mut canvas = image::RgbaImage::new(width, height);
+    frames.into_iter().map(|frame| {
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let duration_ms = (numer as f32 / denom as f32) as u32;
+        let (left, top) = (frame.left(), frame.top());
+        let buffer = frame.into_buffer();
+        if (left, top) == (0, 0) && buffer.dimensions() == (width, height) {
+            canvas = buffer;
+        } else {
+            image::imageops::overlay(&mut canvas, &buffer, left as i64, top as i64);
+        }
+        (canvas.clone(), duration_ms)
+    }).collect()
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Local};
+
+use crate::config::{CwMode, EmojiMode};
+use crate::note::ParsedNote;
+use crate::segment::strip_emoji_shortcodes;
+
+/// コメント表示用テンプレートのデフォルト（従来の `[アカウント] 名前(@id@host): 本文` 形式）
+pub const DEFAULT_COMMENT_FORMAT: &str = "[{account}] {name}(@{user}{host}): {renote}{text}";
+
+/// 名前を本文の上に表示するときのヘッダー行
+pub const USERNAME_HEADER_FORMAT: &str = "{name}(@{user}{host})";
+
+/// 名前を本文と別に表示する（または表示しない）ときの本文
+pub const BODY_ONLY_FORMAT: &str = "{renote}{text}";
+
+/// コメント本文の最大文字数（超えた分は`...`で省略）
+pub const MAX_TEXT_CHARS: usize = 100;
+
+/// リノート元の本文の最大文字数
+pub const MAX_RENOTE_TEXT_CHARS: usize = 80;
+
+/// `mark_replies`でリプライの本文の先頭に付ける印
+pub const REPLY_MARKER: &str = "↩ ";
+
+/// テンプレートに埋め込む値
+pub struct CommentFields<'a> {
+    pub account: &'a str,
+    pub name: &'a str,
+    pub user: &'a str,
+    pub host: Option<&'a str>,
+    pub text: &'a str,
+    /// リノート元の (名前, username, host)
+    pub renote: Option<(&'a str, &'a str, &'a str)>,
+}
+
+/// コメントのテンプレートを展開する
+///
+/// 使えるプレースホルダー:
+/// - `{account}`: アカウント名
+/// - `{name}`: 表示名
+/// - `{user}`: username
+/// - `{host}`: リモートユーザーなら `@host`、ローカルなら空
+/// - `{text}`: 本文
+/// - `{renote}`: リノートなら `RT: 名前(@id@host): `、それ以外は空
+///
+/// 値の中に `{text}` などが含まれていても再展開はしない。未知のプレースホルダーはそのまま残す。
+pub fn render_comment(template: &str, fields: &CommentFields) -> String {
+    let mut out = String::with_capacity(template.len() + fields.text.len() + 32);
+    let mut rest = template;
+    
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start..];
+        let Some(end) = after.find('}') else {
+            out.push_str(after);
+            return out;
+        };
+        
+        match &after[1..end] {
+            "account" => out.push_str(fields.account),
+            "name" => out.push_str(fields.name),
+            "user" => out.push_str(fields.user),
+            "host" => {
+                if let Some(host) = fields.host {
+                    out.push('@');
+                    out.push_str(host);
+                }
+            }
+            "text" => out.push_str(fields.text),
+            "renote" => {
+                if let Some((name, user, host)) = fields.renote {
+                    if host.is_empty() {
+                        out.push_str(&format!("RT: {}(@{}): ", name, user));
+                    } else {
+                        out.push_str(&format!("RT: {}(@{}@{}): ", name, user, host));
+                    }
+                }
+            }
+            _ => out.push_str(&after[..=end]),
+        }
+        rest = &after[end + 1..];
+    }
+    
+    out.push_str(rest);
+    out
+}
+
+/// 表示用に本文を一定の文字数で切り詰める
+pub fn truncate_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        format!("{}...", text.chars().take(max_chars).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// 本文からリンクのプレビューを取得するURLを探す（最初の1つ）
+///
+/// 表示用に切り詰める前の本文を渡すこと。切り詰めた後の本文では、
+/// 境界をまたぐURLが途中で切れたまま見つかってしまう。
+pub fn detect_url(text: &str) -> Option<String> {
+    let url_regex = regex::Regex::new(r"https?://[^\s]+").ok()?;
+    url_regex.find(text).map(|m| m.as_str().to_string())
+}
+
+/// CWと本文から表示用テキストを作る（CWの扱いは`mode`に従う）
+///
+/// 本文がなければ`mode`に関係なくCWを表示する。
+pub fn display_text(text: Option<&str>, cw: Option<&str>, mode: CwMode) -> String {
+    let text = text.unwrap_or_default();
+    match cw {
+        Some(cw) if !cw.is_empty() => match mode {
+            _ if text.is_empty() => format!("CW: {}", cw),
+            CwMode::CwOnly => format!("CW: {}", cw),
+            CwMode::Both => format!("CW: {} — {}", cw, text),
+            CwMode::BodyOnly => text.to_string(),
+        },
+        _ => text.to_string(),
+    }
+}
+
+/// コメントの本文にするテキスト（切り詰める前）
+///
+/// 純粋なリノートならリノート元の本文（切り詰め済み）、引用なら本人のコメントを使う。
+pub fn note_text(note: &ParsedNote, cw_mode: CwMode) -> String {
+    match &note.renote {
+        Some(renote) if !note.is_quote() => renote_info(renote, cw_mode).3,
+        _ => display_text(note.text.as_deref(), note.cw.as_deref(), cw_mode),
+    }
+}
+
+/// リンクのプレビューを取得するURLを探すテキスト（`note_text`と同じ投稿の、切り詰める前の本文）
+///
+/// 純粋なリノートの`note_text`は切り詰め済みなので、リノート元の本文をそのまま使う。
+pub fn preview_text(note: &ParsedNote, cw_mode: CwMode) -> String {
+    match &note.renote {
+        Some(renote) if !note.is_quote() => display_text(renote.text.as_deref(), renote.cw.as_deref(), cw_mode),
+        _ => display_text(note.text.as_deref(), note.cw.as_deref(), cw_mode),
+    }
+}
+
+/// コメントの本文を組み立てる（新しいノートと編集されたノートで共通）
+///
+/// 一定の文字数で切り詰め、リプライなら先頭に ↩ を付け、絵文字を表示しない設定ならショートコードを取り除く。
+pub fn comment_body(text_content: &str, reply_marker: bool, emoji_mode: EmojiMode) -> String {
+    let mut text = truncate_text(text_content, MAX_TEXT_CHARS);
+    if reply_marker {
+        text.insert_str(0, REPLY_MARKER);
+    }
+    if emoji_mode == EmojiMode::Hidden {
+        text = strip_emoji_shortcodes(&text);
+    }
+    text
+}
+
+/// 文字列から、実行のたびに変わらないハッシュ値を求める（FNV-1a）
+///
+/// 標準の`DefaultHasher`は実行ごとに結果が変わりうるので、表示に使う値にはこちらを使う。
+pub fn stable_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+/// 匿名表示で使うユーザーの仮名（`User#1a2b3c4d`、同じ`salt`なら同じユーザーは常に同じ）
+///
+/// `salt`はインストールごとのランダムな値。知っているユーザーの仮名を他人が計算できないように混ぜる。
+pub fn pseudonym(username: &str, host: &str, salt: &str) -> String {
+    let hash = stable_hash(&format!("{}:{}@{}", salt, username, host.to_lowercase()));
+    format!("User#{:08x}", hash >> 32)
+}
+
+/// リノート元の (名前, username, host, 切り詰めた本文)
+pub fn renote_info(renote: &ParsedNote, cw_mode: CwMode) -> (String, String, String, String) {
+    let orig_text = display_text(renote.text.as_deref(), renote.cw.as_deref(), cw_mode);
+    (
+        renote.name.clone(),
+        renote.username.clone(),
+        renote.user_host.clone().unwrap_or_default(),
+        truncate_text(&orig_text, MAX_RENOTE_TEXT_CHARS),
+    )
+}
+
+/// 引用の引用…の元投稿を、リノート元の次の段から近い順に最大`depth`件 (名前, username, host, 切り詰めた本文) で返す
+pub fn quote_chain(renote: &ParsedNote, depth: usize, cw_mode: CwMode) -> Vec<(String, String, String, String)> {
+    std::iter::successors(renote.renote.as_deref(), |note| note.renote.as_deref())
+        .take(depth)
+        .map(|note| renote_info(note, cw_mode))
+        .collect()
+}
+
+/// ノートの`createdAt`（ISO 8601）をローカル時刻にする
+pub fn parse_created_at(created_at: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(created_at)
+        .ok()
+        .map(|time| time.with_timezone(&Local))
+}
+
+/// 投稿時刻を`format`（strftime形式）で文字列にする
+///
+/// 書式が不正なときは`None`。
+pub fn format_timestamp(time: &DateTime<Local>, format: &str) -> Option<String> {
+    let mut out = String::new();
+    write!(out, "{}", time.format(format)).ok()?;
+    Some(out)
+}
+
+/// 購読より前に投稿されたノートか（再接続の直後にまとめて届く古い投稿を捨てるのに使う）
+///
+/// 投稿時刻が分からないものは捨てない。
+pub fn is_backfill(created_at: Option<&str>, subscribed_at: &DateTime<Local>) -> bool {
+    created_at
+        .and_then(parse_created_at)
+        .is_some_and(|created_at| created_at < *subscribed_at)
+}
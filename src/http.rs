@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+/// 既定のUser-Agent（設定で上書きしない場合）
+pub const DEFAULT_USER_AGENT: &str = concat!("MisskeyPostViewer/", env!("CARGO_PKG_VERSION"));
+
+static USER_AGENT: OnceLock<String> = OnceLock::new();
+
+/// 追加で信頼するルート証明書（PEM）
+static EXTRA_ROOT_CERT: OnceLock<Vec<u8>> = OnceLock::new();
+
+/// HTTPとWebSocketで送るUser-Agentを設定する（最初の1回だけ有効、`None`なら既定値）
+pub fn set_user_agent(user_agent: Option<String>) {
+    let user_agent = user_agent
+        .filter(|ua| !ua.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+    let _ = USER_AGENT.set(user_agent);
+}
+
+/// サーバーのURLの起点（`https://misskey.io`など、末尾の`/`なし）
+///
+/// `secure`が`false`ならローカル開発用に`http://`を使う。
+pub fn base_url(host: &str, secure: bool) -> String {
+    let scheme = if secure { "https" } else { "http" };
+    format!("{}://{}", scheme, host)
+}
+
+/// 現在のUser-Agent
+pub fn user_agent() -> &'static str {
+    USER_AGENT.get().map(String::as_str).unwrap_or(DEFAULT_USER_AGENT)
+}
+
+/// 自前の認証局で署名したサーバー向けに、追加で信頼するルート証明書（PEMファイル）を読み込む
+///
+/// 最初の1回だけ有効。読み込めなかった場合はエラーを表示して無視する。
+pub fn set_extra_root_cert(path: Option<&str>) {
+    let Some(path) = path.filter(|p| !p.trim().is_empty()) else {
+        return;
+    };
+    let pem = match std::fs::read(path) {
+        Ok(pem) => pem,
+        Err(e) => {
+            eprintln!("[HTTP] Failed to read root certificate {}: {}", path, e);
+            return;
+        }
+    };
+    if let Err(e) = reqwest::Certificate::from_pem(&pem) {
+        eprintln!("[HTTP] Invalid root certificate {}: {}", path, e);
+        return;
+    }
+    println!("[HTTP] Using extra root certificate: {}", path);
+    let _ = EXTRA_ROOT_CERT.set(pem);
+}
+
+/// 共通設定を適用したHTTPクライアントのビルダー
+///
+/// アプリ内のHTTPクライアントはすべてここから作る。
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let mut builder = reqwest::Client::builder().user_agent(user_agent());
+    if let Some(cert) = EXTRA_ROOT_CERT.get().and_then(|pem| reqwest::Certificate::from_pem(pem).ok()) {
+        builder = builder.add_root_certificate(cert);
+    }
+    builder
+}
+
+/// インスタンスのAPI用のHTTPクライアント
+///
+/// `accept_invalid_certs`なら証明書を検証しない（ローカルの開発用サーバー向け。通信内容は保護されない）。
+pub fn api_client(accept_invalid_certs: bool) -> reqwest::Client {
+    client_builder()
+        .danger_accept_invalid_certs(accept_invalid_certs)
+        .build()
+        .unwrap_or_else(|e| {
+            eprintln!("[HTTP] Failed to create HTTP client: {}", e);
+            reqwest::Client::new()
+        })
+}
+
+/// `timeout`で諦める、共通設定のHTTPクライアント（受信を待たせたくない問い合わせ向け）
+pub fn client_with_timeout(timeout: std::time::Duration) -> reqwest::Client {
+    client_builder().timeout(timeout).build().unwrap_or_else(|e| {
+        eprintln!("[HTTP] Failed to create HTTP client: {}", e);
+        reqwest::Client::new()
+    })
+}
+
+/// WebSocket用のTLSコネクタ
+///
+/// 追加のルート証明書も証明書検証の省略も使わない場合は`None`（既定のコネクタを使う）。
+pub fn tls_connector(accept_invalid_certs: bool) -> Result<Option<native_tls::TlsConnector>, Box<dyn std::error::Error + Send + Sync>> {
+    let extra_root = EXTRA_ROOT_CERT.get();
+    if extra_root.is_none() && !accept_invalid_certs {
+        return Ok(None);
+    }
+    let mut builder = native_tls::TlsConnector::builder();
+    if let Some(pem) = extra_root {
+        builder.add_root_certificate(native_tls::Certificate::from_pem(pem)?);
+    }
+    builder.danger_accept_invalid_certs(accept_invalid_certs);
+    Ok(Some(builder.build()?))
+}
+
+/// 共通設定を適用したHTTPクライアント
+pub fn client() -> reqwest::Client {
+    client_builder().build().unwrap_or_else(|e| {
+        eprintln!("[HTTP] Failed to create HTTP client: {}", e);
+        reqwest::Client::new()
+    })
+}
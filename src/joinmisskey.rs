@@ -45,7 +45,7 @@ pub struct InstancesResponse {
 
 /// JoinMisskey APIからインスタンス一覧を取得
 pub async fn fetch_instances() -> Result<Vec<InstanceInfo>, Box<dyn std::error::Error>> {
-    let client = reqwest::Client::new();
+    let client = crate::http::client();
     let response = client
         .get("https://instanceapp.misskey.page/instances.json")
         .send()
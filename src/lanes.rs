@@ -0,0 +1,72 @@
+/// 流れているコメントが画面上で占めている範囲
+#[derive(Debug, Clone, Copy)]
+pub struct LaneOccupant {
+    pub top: f32,
+    pub bottom: f32,
+    /// コメントの右端のX座標
+    pub tail_x: f32,
+}
+
+/// 新しいコメントを流すレーンの配置
+#[derive(Debug, Clone, Copy)]
+pub struct LaneLayout {
+    /// レーンを置ける範囲の上端と下端
+    pub area_top: f32,
+    pub area_bottom: f32,
+    /// 1レーンの高さ
+    pub lane_height: f32,
+    /// コメントが出てくる画面右端のX座標
+    pub spawn_x: f32,
+    /// 同じレーンの前のコメントの右端から空ける最小の間隔
+    pub min_gap_px: f32,
+}
+
+/// 高さ`height`のコメントを置くY座標を決める
+///
+/// 上のレーンから順に、重なる範囲にいるコメントの右端がすべて
+/// `spawn_x - min_gap_px`より左に抜けている位置を探す。
+/// 空きがなければ、前のコメントがもっとも早く抜ける位置を返す。
+pub fn allocate_lane(layout: &LaneLayout, occupants: &[LaneOccupant], height: f32) -> f32 {
+    let lane_height = layout.lane_height.max(1.0);
+    let usable = (layout.area_bottom - layout.area_top - height).max(0.0);
+    let lane_count = (usable / lane_height).floor() as usize + 1;
+    let threshold = layout.spawn_x - layout.min_gap_px;
+
+    let mut best_y = layout.area_top;
+    let mut best_tail = f32::INFINITY;
+    for lane in 0..lane_count {
+        let top = layout.area_top + lane as f32 * lane_height;
+        let bottom = top + height;
+        let tail = occupants
+            .iter()
+            .filter(|o| o.top < bottom && o.bottom > top)
+            .map(|o| o.tail_x)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        if tail <= threshold {
+            return top;
+        }
+        if tail < best_tail {
+            best_tail = tail;
+            best_y = top;
+        }
+    }
+    best_y
+}
+
+impl LaneLayout {
+    /// 範囲を上から`count`等分したうちの`index`番目だけを使う配置にする
+    ///
+    /// アカウントごとに流す帯を分けるときに使う。`count`が0や`index`が範囲外なら全体のまま。
+    pub fn band(&self, index: usize, count: usize) -> LaneLayout {
+        if count == 0 || index >= count {
+            return *self;
+        }
+        let band_height = (self.area_bottom - self.area_top) / count as f32;
+        LaneLayout {
+            area_top: self.area_top + band_height * index as f32,
+            area_bottom: self.area_top + band_height * (index + 1) as f32,
+            ..*self
+        }
+    }
+}
@@ -3,9 +3,41 @@ pub mod config;
 pub mod emoji;
 pub mod miauth;
 pub mod joinmisskey;
+pub mod note;
+pub mod format;
+pub mod segment;
+pub mod mfm;
+pub mod lanes;
+pub mod download;
+pub mod dedupe;
+pub mod http;
+pub mod thumbnail;
+pub mod stats;
+pub mod activity;
+pub mod autostart;
+pub mod sound;
+pub mod state;
+pub mod bidi;
+pub mod ratelimit;
+pub mod mastodon;
+pub mod bluesky;
+pub mod push;
+pub mod source;
+pub mod toast;
 
-pub use misskey::MisskeyClient;
-pub use config::{AppConfig, Account, TimelineType};
-pub use emoji::{EmojiInfo, EmojiCache, AnimatedEmoji};
+pub use misskey::{MisskeyClient, MiUser, ConnectOptions};
+pub use config::{config_path, profiles_dir, list_profiles, DEFAULT_PROFILE_NAME, FIREHOSE_SAMPLE_RATE_LIMIT, find_highlight, set_config_path, migrate_legacy_config, AppConfig, Account, TimelineType, SourceType, HighlightRule, UsernameMode, TimestampPosition, MfmMode, CwMode, EmojiMode, RendererChoice, StreamLayout, SoundTrigger, Theme, ZOrderMode};
+pub use emoji::{EmojiInfo, EmojiCache, AnimatedEmoji, parse_emoji_catalog, parse_emoji_list};
 pub use miauth::MiAuthSession;
 pub use joinmisskey::{InstanceInfo, fetch_instances};
+pub use note::{NoteCounts, NoteType, ParsedNote, ParseError, ParseOptions, TimelineEvent, parse_event, parse_message, parse_note, normalize_whitespace};
+pub use misskey::EventStream;
+pub use mastodon::MastodonClient;
+pub use bluesky::BlueskyClient;
+pub use source::StreamClient;
+pub use download::DownloadPool;
+pub use dedupe::RecentIds;
+pub use sound::{SoundCooldown, SoundPlayer};
+pub use ratelimit::HostBackoff;
+pub use state::{SavedComment, SavedState, state_path};
+pub use toast::Toasts;
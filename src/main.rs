@@ -1,10 +1,27 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use eframe::egui;
-use misskey_post_viewer::{MisskeyClient, AppConfig, Account, TimelineType, EmojiInfo, EmojiCache};
+use misskey_post_viewer::emoji::{parse_emoji_catalog, FAILURE_TTL, MAX_NOTE_EMOJIS};
+use misskey_post_viewer::autostart;
+use misskey_post_viewer::{StreamClient, SourceType, MiUser, ConnectOptions, AppConfig, Account, TimelineType, EmojiInfo, EmojiCache, DownloadPool, HostBackoff, RecentIds, SavedComment, SavedState, SoundCooldown, SoundPlayer, Toasts, state_path, NoteCounts, ParsedNote, ParseOptions, TimelineEvent};
+use misskey_post_viewer::bidi::visual_order;
+use misskey_post_viewer::bluesky::DEFAULT_JETSTREAM_HOST;
+use misskey_post_viewer::push::{self, ExternalComment};
+use misskey_post_viewer::ratelimit::{parse_retry_after, unavailable_backoff, SHORT_CONNECTION};
+use misskey_post_viewer::format::{render_comment, CommentFields, DEFAULT_COMMENT_FORMAT, USERNAME_HEADER_FORMAT, BODY_ONLY_FORMAT};
+use misskey_post_viewer::format::{display_text, truncate_text, renote_info, note_text, preview_text, comment_body, REPLY_MARKER, quote_chain as format_quote_chain, parse_created_at, format_timestamp, is_backfill, pseudonym, detect_url, MAX_TEXT_CHARS};
+use misskey_post_viewer::segment::{segment_text, first_emojis, limit_emoji, limit_lines, shortcode_names, split_emoji_host, strip_emoji_shortcodes, TextSegment};
+use misskey_post_viewer::mfm::{parse_mfm_spans, MfmEffect, MfmSpan};
+use misskey_post_viewer::http::{set_extra_root_cert, set_user_agent, DEFAULT_USER_AGENT};
+use misskey_post_viewer::stats::StatsRecorder;
+use misskey_post_viewer::activity::{ActivityLog, ActivityRow};
+use misskey_post_viewer::thumbnail::{resize_for_display, ImageShape};
+use misskey_post_viewer::lanes::{allocate_lane, LaneLayout, LaneOccupant};
+use misskey_post_viewer::{UsernameMode, TimestampPosition, MfmMode, EmojiMode, CwMode, RendererChoice, StreamLayout, Theme, ZOrderMode, NoteType, config_path, profiles_dir, list_profiles, find_highlight, DEFAULT_PROFILE_NAME, FIREHOSE_SAMPLE_RATE_LIMIT, set_config_path, migrate_legacy_config};
 use raw_window_handle::{HasWindowHandle, RawWindowHandle};
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::runtime::Runtime;
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver};
 use tokio_tungstenite::tungstenite::protocol::Message;
@@ -13,7 +30,8 @@ use windows::Win32::UI::WindowsAndMessaging::{
     GetWindowLongPtrW, SetWindowLongPtrW, SetForegroundWindow, PostMessageW, FindWindowW,
     GWL_EXSTYLE, WS_EX_LAYERED, WS_EX_TRANSPARENT, WM_USER,
 };
-use tray_icon::{TrayIconBuilder, menu::{Menu, MenuItem}};
+use global_hotkey::{hotkey::HotKey, GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use tray_icon::{TrayIconBuilder, menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu}};
 
 #[derive(Clone)]
 struct UrlPreview {
@@ -28,85 +46,91 @@ struct UrlPreview {
 struct PreviewImageCache {
     cache: std::collections::HashMap<String, Option<egui::TextureHandle>>,
     downloading: std::collections::HashMap<String, bool>,
-    rx: std::sync::mpsc::Receiver<(String, egui::ColorImage)>,
-    tx: std::sync::mpsc::Sender<(String, egui::ColorImage)>,
+    rx: std::sync::mpsc::Receiver<(String, Option<egui::ColorImage>)>, // Noneは失敗
+    tx: std::sync::mpsc::Sender<(String, Option<egui::ColorImage>)>,
+    pool: Arc<DownloadPool>,
+    failed_at: std::collections::HashMap<String, std::time::Instant>, // 失敗した時刻（FAILURE_TTL後に再取得）
 }
 
 impl PreviewImageCache {
-    fn new() -> Self {
-        let (tx, rx) = std::sync::mpsc::channel::<(String, egui::ColorImage)>();
+    fn new(pool: Arc<DownloadPool>) -> Self {
+        let (tx, rx) = std::sync::mpsc::channel::<(String, Option<egui::ColorImage>)>();
         Self {
             cache: std::collections::HashMap::new(),
             downloading: std::collections::HashMap::new(),
             rx,
             tx,
+            pool,
+            failed_at: std::collections::HashMap::new(),
         }
     }
 
     fn load_image(&mut self, url: &str, debug_mode: bool) -> Option<egui::TextureHandle> {
+        self.load(url.to_string(), url, None, debug_mode)
+    }
+    
+    /// `size`四方に収まるように縮小した画像を読み込む（`Circle`なら円形に切り抜く）
+    ///
+    /// 同じURLでも大きさと形ごとに別のテクスチャとしてキャッシュする。
+    fn load_image_sized(&mut self, url: &str, size: u32, shape: ImageShape, debug_mode: bool) -> Option<egui::TextureHandle> {
+        self.load(format!("{}#{}{:?}", url, size, shape), url, Some((size, shape)), debug_mode)
+    }
+    
+    // `key`でキャッシュし、`url`からダウンロードする
+    fn load(&mut self, key: String, url: &str, resize: Option<(u32, ImageShape)>, debug_mode: bool) -> Option<egui::TextureHandle> {
         // キャッシュをチェック
-        if let Some(cached) = self.cache.get(url) {
+        if let Some(cached) = self.cache.get(&key) {
             return cached.clone();
         }
         
+        // 失敗してから時間が経っていなければ取得し直さない
+        if let Some(at) = self.failed_at.get(&key) {
+            if at.elapsed() < FAILURE_TTL {
+                return None;
+            }
+            self.failed_at.remove(&key);
+        }
+        
         // ダウンロード中かチェック
-        if self.downloading.contains_key(url) {
+        if self.downloading.contains_key(&key) {
             return None;
         }
         
         // ダウンロード開始
-        self.downloading.insert(url.to_string(), true);
+        self.downloading.insert(key.clone(), true);
         let url_clone = url.to_string();
         let tx = self.tx.clone();
         
-        std::thread::spawn(move || {
-            use std::time::Duration;
-            
-            let client = reqwest::blocking::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build();
+        self.pool.fetch(url_clone.clone(), move |result| {
+            let bytes = match result {
+                Ok(bytes) => bytes,
+                Err(e) => {
+                    if debug_mode { eprintln!("Failed to download preview image: {}", e); }
+                    let _ = tx.send((key, None));
+                    return;
+                }
+            };
             
-            match client {
-                Ok(client) => {
-                    match client.get(&url_clone).send() {
-                        Ok(response) => {
-                            if response.status().is_success() {
-                                match response.bytes() {
-                                    Ok(bytes) => {
-                                        // 画像デコードもこのスレッドで実行
-                                        match image::load_from_memory(&bytes) {
-                                            Ok(img) => {
-                                                let size = [img.width() as usize, img.height() as usize];
-                                                let rgba = img.to_rgba8();
-                                                let pixels = rgba.as_flat_samples();
-                                                let color_image = egui::ColorImage::from_rgba_unmultiplied(
-                                                    size, 
-                                                    pixels.as_slice()
-                                                );
-                                                let _ = tx.send((url_clone.clone(), color_image));
-                                            }
-                                            Err(e) => {
-                                                if debug_mode { 
-                                                    eprintln!("Failed to decode preview image {}: {}", url_clone, e); 
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        if debug_mode { eprintln!("Failed to read preview image bytes: {}", e); }
-                                    }
-                                }
-                            } else {
-                                if debug_mode { eprintln!("Failed to download preview image (HTTP {})", response.status()); }
-                            }
-                        }
-                        Err(e) => {
-                            if debug_mode { eprintln!("Failed to download preview image: {}", e); }
-                        }
-                    }
+            // 画像デコードと縮小もこのスレッドで実行
+            match image::load_from_memory(&bytes) {
+                Ok(img) => {
+                    let rgba = match resize {
+                        Some((size, shape)) => resize_for_display(&img, size, shape),
+                        None => img.to_rgba8(),
+                    };
+                    let size = [rgba.width() as usize, rgba.height() as usize];
+                    let pixels = rgba.as_flat_samples();
+                    let color_image = egui::ColorImage::from_rgba_unmultiplied(
+                        size, 
+                        pixels.as_slice()
+                    );
+                    let _ = tx.send((key, Some(color_image)));
                 }
                 Err(e) => {
-                    if debug_mode { eprintln!("Failed to create HTTP client: {}", e); }
+                    if debug_mode { 
+                        eprintln!("Failed to decode preview image {}: {}", url_clone, e); 
+                    }
+                    let _ = tx.send((key, None));
                 }
             }
         });
@@ -118,6 +142,11 @@ impl PreviewImageCache {
         while let Ok((url, color_image)) = self.rx.try_recv() {
             self.downloading.remove(&url);
             
+            let Some(color_image) = color_image else {
+                self.failed_at.insert(url, std::time::Instant::now());
+                continue;
+            };
+            
             // デコード済みの画像をテクスチャに変換（軽い処理）
             let texture = ctx.load_texture(
                 &url,
@@ -140,20 +169,260 @@ struct Comment {
     username: String,
     user_host: Option<String>,
     renote_info: Option<(String, String, String, String)>, // (元投稿者のname, 元投稿者のusername, 元投稿者のhost, 元投稿テキスト)
+    is_quote: bool, // コメント付きのリノート（元投稿は本文の下に小さく表示）
+    quote_chain: Vec<(String, String, String, String)>, // リノート元がさらに引用していた投稿（近い順、`renote_info`と同じ形）
     emojis: Vec<EmojiInfo>, // カスタム絵文字情報
     url_preview: Option<UrlPreview>, // URLプレビュー情報
-    account_color: [u8; 3], // このコメントが属するアカウントの文字色
+    account_color: [u8; 3], // このコメントが属するアカウントの文字色（ハイライト時は上書き）
     account_name: String, // このコメントが属するアカウント名
+    scale: f32, // 文字サイズの倍率（ハイライトルールで変更）
+    mentioned: bool, // 自分宛てのメンションを含むか
+    note_id: String, // 削除・編集イベントとの照合用
+    reply_marker: bool, // 本文の先頭に ↩ を付けたリプライ（編集後の本文にも付け直す）
+    width: f32, // 描画した幅（初回描画までは推定値）
+    height: f32, // レーン割り当てに使った高さ
+    age: f32, // 画面に出てからの経過秒数
+    instance_icon_url: Option<String>, // 投稿者のサーバーのアイコン（表示しない設定ならNone）
+    avatar_url: Option<String>, // 投稿者のアバター（表示しない設定ならNone）
+    host: String, // 投稿者のサーバー（ローカルユーザーなら接続先のサーバー）
+    counts: Option<NoteCounts>, // 本文の後ろに表示するリアクション数など（表示しない設定ならNone）
+    created_at: Option<chrono::DateTime<chrono::Local>>, // 投稿時刻（ローカル時刻）
+}
+
+impl Comment {
+    /// 状態ファイルに保存する形にする（プレビューやリノート元などは保存しない）
+    fn to_saved(&self) -> SavedComment {
+        SavedComment {
+            note_id: self.note_id.clone(),
+            text: self.text.clone(),
+            name: self.name.clone(),
+            username: self.username.clone(),
+            user_host: self.user_host.clone(),
+            host: self.host.clone(),
+            account_name: self.account_name.clone(),
+            account_color: self.account_color,
+            emojis: self.emojis.clone(),
+            speed: self.speed,
+            scale: self.scale,
+        }
+    }
+    
+    /// 画面に出す文字列のうち、上限を超えた絵文字は文字のまま表示するので画像も読み込まない
+    fn limit_emojis(&mut self, max: usize) {
+        if max == 0 {
+            return;
+        }
+        let mut shown_text = format!("{} {}", self.name, self.text);
+        for (rn_name, _, _, rn_text) in self.renote_info.iter().chain(self.quote_chain.iter()) {
+            shown_text.push_str(&format!(" {} {}", rn_name, rn_text));
+        }
+        self.emojis = first_emojis(&shown_text, &self.emojis, max);
+    }
+    
+    /// 外部ツールから届いたコメントにする（`source`はアカウント名の代わりに表示する）
+    ///
+    /// 絵文字は他のソースと同じく、本文に出てくる先頭の`max_emojis`種類（0なら`MAX_NOTE_EMOJIS`個まで）だけにする。
+    fn external(comment: ExternalComment, source: &str, max_emojis: usize) -> Self {
+        let mut external = Comment {
+            text: truncate_text(&comment.text, MAX_TEXT_CHARS),
+            x: 2000.0,
+            y: 0.0,
+            speed: rand::random_range(4.0..8.0),
+            name: comment.name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| source.to_string()),
+            username: source.to_lowercase(),
+            user_host: None,
+            renote_info: None,
+            is_quote: false,
+            quote_chain: Vec::new(),
+            emojis: comment.emojis.into_iter().take(MAX_NOTE_EMOJIS).collect(),
+            url_preview: None,
+            account_color: comment.color.unwrap_or([255, 255, 255]),
+            account_name: source.to_string(),
+            scale: 1.0,
+            mentioned: false,
+            note_id: String::new(),
+            reply_marker: false,
+            width: 0.0,
+            height: 0.0,
+            age: 0.0,
+            instance_icon_url: None,
+            avatar_url: None,
+            host: String::new(),
+            counts: None,
+            created_at: Some(chrono::Local::now()),
+        };
+        external.limit_emojis(max_emojis);
+        external
+    }
+    
+    /// 保存しておいたコメントから戻す（画面右端から流し直す）
+    fn from_saved(saved: SavedComment) -> Self {
+        let reply_marker = saved.text.starts_with(REPLY_MARKER);
+        Comment {
+            text: saved.text,
+            x: 2000.0,
+            y: 0.0,
+            speed: saved.speed,
+            name: saved.name,
+            username: saved.username,
+            user_host: saved.user_host,
+            renote_info: None,
+            is_quote: false,
+            quote_chain: Vec::new(),
+            emojis: saved.emojis,
+            url_preview: None,
+            account_color: saved.account_color,
+            account_name: saved.account_name,
+            scale: saved.scale,
+            mentioned: false,
+            note_id: saved.note_id,
+            reply_marker,
+            width: 0.0,
+            height: 0.0,
+            age: 0.0,
+            instance_icon_url: None,
+            avatar_url: None,
+            host: saved.host,
+            counts: None,
+            created_at: None,
+        }
+    }
+}
+
+/// 受信タスクからUIスレッドへの通知
+enum CommentSignal {
+    Add(Box<Comment>),
+    Remove(String), // 削除されたノートのID
+    Update { note_id: String, text: String, emojis: Vec<EmojiInfo> }, // 編集後の本文（切り詰める前）と、そこで新しく使われた絵文字
+    Notice(String), // 利用者に知らせるエラー（トーストで表示）
+}
+
+/// 受信タスク側の送信口。送るたびにUIスレッドを起こすので、UI側は待機中に間隔を空けて再描画できる
+#[derive(Clone)]
+struct CommentSender {
+    tx: std::sync::mpsc::Sender<CommentSignal>,
+    ctx: egui::Context,
+}
+
+impl CommentSender {
+    fn send(&self, signal: CommentSignal) {
+        if self.tx.send(signal).is_ok() {
+            self.ctx.request_repaint();
+        }
+    }
+}
+
+/// コメントが流れるまでの各段階の件数（取りこぼしの調査用にデバッグ表示する）
+#[derive(Default)]
+struct PipelineCounters {
+    received: AtomicUsize,  // 受信タスクから届いたコメント
+    displayed: AtomicUsize, // 画面に流したコメント
+    dropped: AtomicUsize,   // 非表示中やソロ表示で流さずに捨てたコメント
+    expired: AtomicUsize,   // 表示時間の上限を超えて、画面外に出る前に消したコメント
+    filtered: AtomicUsize,  // リプライ・リノート・種類・重複の設定で受信タスクが捨てたノート
+}
+
+impl PipelineCounters {
+    fn summary(&self) -> String {
+        format!(
+            "received: {}  displayed: {}  dropped: {}  filtered: {}",
+            self.received.load(Ordering::Relaxed),
+            self.displayed.load(Ordering::Relaxed),
+            self.dropped.load(Ordering::Relaxed),
+            self.filtered.load(Ordering::Relaxed),
+        )
+    }
+    
+    /// 表示したあとに消した件数（流れてくる前に捨てた`dropped`とは別に数える）
+    fn expired_summary(&self) -> String {
+        format!("expired: {}", self.expired.load(Ordering::Relaxed))
+    }
+}
+
+/// アカウントごとの接続状態（接続中画面に表示する）
+#[derive(Default)]
+struct AccountStatus {
+    connecting_since: Option<std::time::Instant>, // 接続を試みはじめた時刻（接続済みならNone）
+    last_error: Option<String>, // 直近の接続エラー
+    gave_up: bool, // `max_reconnect_attempts`回続けて失敗したので再接続をやめた
+    unavailable: bool, // 接続してもすぐ切られるのが続いている（メンテナンス中など）
+}
+
+type ConnectionStatuses = Arc<Mutex<BTreeMap<String, AccountStatus>>>;
+
+/// 流れているノートのキャプチャ（`subNote`）を外すよう受信タスクに知らせる、アカウント名ごとの送り口
+#[derive(Clone, Default)]
+struct NoteSubscriptions(Arc<Mutex<HashMap<String, tokio::sync::mpsc::UnboundedSender<String>>>>);
+
+impl NoteSubscriptions {
+    /// 受信タスクを登録し、キャプチャを外すノートIDを受け取る口を返す（同じアカウントの前のタスクは置き換える）
+    fn register(&self, account_name: &str) -> tokio::sync::mpsc::UnboundedReceiver<String> {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.0.lock().unwrap().insert(account_name.to_string(), tx);
+        rx
+    }
+    
+    /// コメントが画面から消えたので、そのノートのキャプチャを外す
+    fn release(&self, comment: &Comment) {
+        if comment.note_id.is_empty() {
+            return;
+        }
+        if let Some(tx) = self.0.lock().unwrap().get(&comment.account_name) {
+            let _ = tx.send(comment.note_id.clone());
+        }
+    }
+}
+
+/// コメントが流れるまでの画面の状態
+#[derive(Clone, Copy, PartialEq)]
+enum OverlayState {
+    NoAccounts, // 有効なアカウントがない（接続するものがない）
+    Connecting, // 接続中（一部が諦めていても残りを待つ）
+    AllFailed,  // すべてのアカウントが再接続を諦めた
+}
+
+fn update_status(statuses: &ConnectionStatuses, account_name: &str, f: impl FnOnce(&mut AccountStatus)) {
+    if let Some(status) = statuses.lock().unwrap().get_mut(account_name) {
+        f(status);
+    }
+}
+
+/// 続けて`max_attempts`回失敗していたら再接続をやめた状態にしてtrueを返す
+fn give_up_if_exhausted(statuses: &ConnectionStatuses, account_name: &str, failures: u32, max_attempts: Option<u32>) -> bool {
+    if max_attempts.is_none_or(|max| failures < max) {
+        return false;
+    }
+    eprintln!("[{}] Giving up after {} failed attempts", account_name, failures);
+    update_status(statuses, account_name, |s| {
+        s.connecting_since = None;
+        s.gave_up = true;
+    });
+    true
+}
+
+/// 接続が切れたので再接続を試みている状態にする（接続中ならその開始時刻は保つ）
+fn mark_connecting(status: &mut AccountStatus, error: Option<String>) {
+    status.connecting_since.get_or_insert_with(std::time::Instant::now);
+    if error.is_some() {
+        status.last_error = error;
+    }
 }
 
 enum TrayEvent {
     Settings,
+    ToggleVisible,
+    Stats,
+    ExportCsv,
+    SwitchProfile(String),
+    Solo(Option<String>), // Noneで解除
+    OpenProfilesDir,
+    ClearComments, // ホットキーからも送られる
     Quit,
 }
 
 struct MisskeyViewerApp {
     comments: VecDeque<Comment>,
-    rx: std::sync::mpsc::Receiver<Comment>,
+    rx: std::sync::mpsc::Receiver<CommentSignal>,
     tray_rx: CrossbeamReceiver<TrayEvent>,
     tray_event_flag: Arc<Mutex<bool>>,
     reconnect_tx: tokio::sync::mpsc::UnboundedSender<AppConfig>,
@@ -161,12 +430,39 @@ struct MisskeyViewerApp {
     window_configured: bool,
     config: AppConfig,
     is_connected: Arc<Mutex<bool>>,
+    // アカウントごとの接続状態
+    connection_statuses: ConnectionStatuses,
+    // コメントが消えたノートのキャプチャを外すための送り口
+    note_subscriptions: NoteSubscriptions,
     // 絵文字キャッシュ
     emoji_cache: EmojiCache,
     // プレビュー画像キャッシュ
     preview_image_cache: PreviewImageCache,
     // 設定ファイル監視用
     config_last_modified: Option<std::time::SystemTime>,
+    // 解析に失敗したメッセージ数（デバッグ表示用）
+    parse_failures: Arc<AtomicUsize>,
+    // 受信から表示までの件数と、最後にログへ出した時刻
+    counters: Arc<PipelineCounters>,
+    emoji_backoff: Arc<HostBackoff>, // 絵文字APIのレート制限の状態（デバッグ表示用）
+    last_counters_log: std::time::Instant,
+    // 表示ON/OFF（OFFの間も接続は維持し、受信したコメントは捨てる）
+    visible: bool,
+    // 右上に少しの間だけ出す通知
+    toasts: Toasts,
+    // 直近の投稿の統計と、その表示ウィンドウを開いているか
+    stats: StatsRecorder,
+    show_stats: bool,
+    // 流れたノートの記録（トレイからCSVに書き出す）
+    activity_log: Arc<Mutex<ActivityLog>>,
+    // 表示済みのノートIDと、状態ファイルに最後に保存した時刻（`persist_state`用）
+    seen_notes: Arc<Mutex<RecentIds>>,
+    last_state_save: std::time::Instant,
+    // このアカウントのコメントだけを流す（トレイから一時的に切り替える。設定には保存しない）
+    solo_account: Option<String>,
+    // キーワードの効果音（出力デバイスは最初に鳴らすときに開く）
+    sound_player: Option<SoundPlayer>,
+    sound_cooldown: SoundCooldown,
 }
 
 struct SettingsWindow {
@@ -182,6 +478,10 @@ struct SettingsWindow {
     // サーバー候補
     available_instances: Vec<misskey_post_viewer::InstanceInfo>,
     instances_loaded: bool,
+    // Windowsのスタートアップに登録済みか（レジストリから読んだ値）
+    autostart: bool,
+    // 直近の接続テストの結果 (account_index, 結果)
+    connection_test: Option<(usize, Result<String, String>)>,
 }
 
 impl SettingsWindow {
@@ -196,6 +496,8 @@ impl SettingsWindow {
             pending_miauth: None,
             available_instances: Vec::new(),
             instances_loaded: false,
+            autostart: autostart::is_enabled(),
+            connection_test: None,
         }
     }
 }
@@ -271,6 +573,24 @@ impl SettingsWindow {
                             if is_selected {
                                 ui.separator();
                                 
+                                // 接続先の種類
+                                ui.horizontal(|ui| {
+                                    ui.label("種類:");
+                                    egui::ComboBox::from_id_salt(format!("source_{}", idx))
+                                        .selected_text(account.source.display_name())
+                                        .show_ui(ui, |ui| {
+                                            for source in [SourceType::Misskey, SourceType::Mastodon, SourceType::Bluesky] {
+                                                if ui.selectable_value(&mut account.source, source, source.display_name()).clicked() {
+                                                    // BlueskyのhostはJetstreamのサーバー
+                                                    if source == SourceType::Bluesky && account.host.trim().is_empty() {
+                                                        account.host = DEFAULT_JETSTREAM_HOST.to_string();
+                                                    }
+                                                    changed = true;
+                                                }
+                                            }
+                                        });
+                                });
+                                
                                 // タイムライン選択
                                 ui.horizontal(|ui| {
                                     ui.label("タイムライン:");
@@ -306,12 +626,79 @@ impl SettingsWindow {
                                     }
                                 });
                                 
+                                ui.horizontal(|ui| {
+                                    if ui.checkbox(&mut account.show_replies, "リプライを表示").changed() {
+                                        changed = true;
+                                    }
+                                    if ui.add_enabled(account.show_replies, egui::Checkbox::new(&mut account.mark_replies, "先頭に ↩ を付ける")).changed() {
+                                        changed = true;
+                                    }
+                                });
+                                if ui.checkbox(&mut account.hide_pure_renotes, "コメントなしのリノートを表示しない").changed() {
+                                    changed = true;
+                                }
+                                if account.source == SourceType::Misskey
+                                    && ui.checkbox(&mut account.server_hide_renotes, "リノートをサーバーから受け取らない（通信量を減らす）").changed()
+                                {
+                                    changed = true;
+                                }
+                                if ui.checkbox(&mut account.skip_backfill, "接続直後に届く古い投稿を表示しない").changed() {
+                                    changed = true;
+                                }
+                                if account.source == SourceType::Misskey {
+                                    // サーバーの既定と同じ値にしたら取り除き、既定に任せる
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.label("購読時:");
+                                        for (key, label, server_default) in CHANNEL_PARAM_TOGGLES {
+                                            let mut value = account.channel_param(key).unwrap_or(server_default);
+                                            if ui.checkbox(&mut value, label).changed() {
+                                                account.set_channel_param(key, (value != server_default).then_some(value));
+                                                changed = true;
+                                            }
+                                        }
+                                    });
+                                }
+                                if ui.add(egui::Slider::new(&mut account.sample_rate, 0.0..=1.0).text("流す割合 (1.0ですべて)")).changed() {
+                                    changed = true;
+                                }
+                                if account.effective_sample_rate() < account.sample_rate {
+                                    ui.label(format!(
+                                        "firehose全体を受け取っているので {:.0}% までにしています（channel_paramsのwantedDidsで投稿者を絞れます）",
+                                        FIREHOSE_SAMPLE_RATE_LIMIT * 100.0
+                                    ));
+                                }
+                                let mut insecure = !account.secure;
+                                if ui.checkbox(&mut insecure, "暗号化しない（ws:// / http://、ローカル開発用）").changed() {
+                                    account.secure = !insecure;
+                                    changed = true;
+                                }
+                                if ui.checkbox(&mut account.danger_accept_invalid_certs, "⚠ 証明書を検証しない（ローカル開発用・危険）").changed() {
+                                    changed = true;
+                                }
+                                
                                 // トークン表示（隠す）
                                 if account.token.is_some() {
                                     ui.label("トークン: ********（設定済み）");
                                 } else {
                                     ui.label("トークン: 未設定");
                                 }
+                                
+                                // 保存する前にサーバーとトークンで接続できるか確かめる
+                                ui.horizontal(|ui| {
+                                    if ui.button("接続テスト").clicked() {
+                                        let rt = Runtime::new().expect("Failed to create runtime");
+                                        self.connection_test = Some((idx, rt.block_on(test_account(account))));
+                                    }
+                                    match &self.connection_test {
+                                        Some((test_idx, Ok(message))) if *test_idx == idx => {
+                                            ui.colored_label(egui::Color32::from_rgb(80, 200, 120), format!("✓ {}", message));
+                                        }
+                                        Some((test_idx, Err(message))) if *test_idx == idx => {
+                                            ui.colored_label(egui::Color32::from_rgb(230, 80, 80), format!("✗ {}", message));
+                                        }
+                                        _ => {}
+                                    }
+                                });
                             }
                         });
                         ui.add_space(3.0);
@@ -528,10 +915,10 @@ impl SettingsWindow {
                     // 一時的なアカウントインデックスとして使用
                     let temp_index = self.config.accounts.len();
                     let session = misskey_post_viewer::MiAuthSession::new(
-                        &self.edit_account_host,
+                        &misskey_post_viewer::http::base_url(&self.edit_account_host, true),
                         "Misskey Post Viewer",
                         Some("ニコニコ風コメント表示アプリ"),
-                        &["read:account", "read:messaging"]
+                        &["read:account", "read:messaging", "read:blocks", "read:mutes"]
                     );
                     println!("MiAuth URL: {}", session.url);
                     let _ = open::that(&session.url);
@@ -564,95 +951,530 @@ impl SettingsWindow {
             ui.add_space(10.0);
             ui.separator();
             
+            // 表示設定
+            ui.label("表示設定 (保存で反映):");
+            ui.add_space(5.0);
+            ui.checkbox(&mut self.config.highlight_mentions, "自分宛てのメンションを強調表示 (トークンが必要)");
+            ui.checkbox(&mut self.config.persist_state, "流れているコメントを定期的に保存し、異常終了後の起動時に戻す");
+            ui.checkbox(&mut self.config.sync_server_blocks, "サーバーでブロック・ミュートしているユーザーを表示しない (トークンが必要)");
+            
             ui.horizontal(|ui| {
-                if ui.button("保存").clicked() {
-                    if let Err(e) = self.config.save() {
-                        eprintln!("設定の保存に失敗: {}", e);
+                ui.label("表示形式:");
+                ui.add(egui::TextEdit::singleline(&mut self.config.comment_format).desired_width(350.0));
+                if ui.button("既定に戻す").clicked() {
+                    self.config.comment_format = DEFAULT_COMMENT_FORMAT.to_string();
+                }
+            });
+            ui.label("使える置換: {account} {name} {user} {host} {text} {renote}");
+            
+            ui.add(egui::Slider::new(&mut self.config.color_jitter, 0..=64).text("文字色のゆらぎ (0で無効)"));
+            ui.add(egui::Slider::new(&mut self.config.length_speed_factor, 0.0..=1.0).text("長文ほど遅くする強さ (0で無効)"));
+            ui.checkbox(&mut self.config.width_based_speed, "コメントの幅に合わせて速度を変える（長いほど遅く）");
+            ui.add(egui::Slider::new(&mut self.config.max_lines, 0..=20).text("最大行数 (0で無制限)"));
+            ui.add(egui::Slider::new(&mut self.config.target_fps, 0..=240).text("最大フレームレート (0で無制限)"));
+            ui.add(egui::Slider::new(&mut self.config.min_gap_px, 0.0..=300.0).text("同じ段のコメントの間隔 (px)"));
+            ui.add(egui::Slider::new(&mut self.config.max_concurrent_downloads, 1..=16).text("絵文字・画像の同時ダウンロード数 (再起動後に反映)"));
+            ui.add(egui::Slider::new(&mut self.config.download_timeout_secs, 1..=60).text("絵文字・画像・OGPのタイムアウト秒数 (再起動後に反映)"));
+            ui.horizontal(|ui| {
+                let mut limited = self.config.max_reconnect_attempts.is_some();
+                if ui.checkbox(&mut limited, "再接続の回数を制限").changed() {
+                    self.config.max_reconnect_attempts = if limited { Some(10) } else { None };
+                }
+                if let Some(attempts) = self.config.max_reconnect_attempts.as_mut() {
+                    ui.add(egui::Slider::new(attempts, 1..=100).text("回"));
+                }
+            });
+            ui.checkbox(&mut self.config.hide_idle_overlay, "有効なアカウントがないときは案内を表示しない");
+            ui.add(egui::Slider::new(&mut self.config.startup_delay_secs, 0..=120).text("起動後に接続を始めるまでの秒数 (再起動後に反映)"));
+            ui.horizontal(|ui| {
+                let mut limited = self.config.max_lifetime_secs.is_some();
+                if ui.checkbox(&mut limited, "表示時間の上限").changed() {
+                    self.config.max_lifetime_secs = if limited { Some(10.0) } else { None };
+                }
+                if let Some(secs) = self.config.max_lifetime_secs.as_mut() {
+                    ui.add(egui::Slider::new(secs, 1.0..=60.0).text("秒"));
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut enabled = self.config.enable_http_source.is_some();
+                if ui.checkbox(&mut enabled, "HTTPでコメントを受け付ける (再起動後に反映)").changed() {
+                    self.config.enable_http_source = if enabled { Some(DEFAULT_HTTP_SOURCE_PORT) } else { None };
+                }
+                if let Some(port) = self.config.enable_http_source.as_mut() {
+                    ui.label("ポート:");
+                    ui.add(egui::DragValue::new(port).range(1024..=65535));
+                    ui.label("合言葉:");
+                    let mut secret = self.config.http_source_secret.clone().unwrap_or_default();
+                    if ui.add(egui::TextEdit::singleline(&mut secret).password(true).desired_width(120.0)).changed() {
+                        self.config.http_source_secret = if secret.is_empty() { None } else { Some(secret) };
+                    }
+                    if self.config.http_source_secret.as_deref().is_none_or(|s| s.trim().is_empty()) {
+                        ui.colored_label(egui::Color32::from_rgb(230, 80, 80), "合言葉を設定するまで受け付けません");
                     }
                 }
-                
-                if ui.button("閉じる").clicked() {
-                    keep_open = false;
+            });
+            ui.horizontal(|ui| {
+                ui.label("User-Agent (再起動後に反映):");
+                let mut user_agent = self.config.user_agent.clone().unwrap_or_default();
+                if ui.add(egui::TextEdit::singleline(&mut user_agent).hint_text(DEFAULT_USER_AGENT)).changed() {
+                    self.config.user_agent = if user_agent.trim().is_empty() { None } else { Some(user_agent) };
                 }
             });
-            
-            ui.add_space(10.0);
-        
-        keep_open
-    }
-}
-
-impl MisskeyViewerApp {
-    fn new(
-        cc: &eframe::CreationContext<'_>, 
-        config: AppConfig,
-        tray_rx: CrossbeamReceiver<TrayEvent>,
-        tray_event_flag: Arc<Mutex<bool>>
-    ) -> Self {
-        // フォント設定 (日本語表示のため)
-        let mut fonts = egui::FontDefinitions::default();
-        
-        // システムフォントを読み込む試み (Windows)
-        let font_path = "C:\\Windows\\Fonts\\meiryo.ttc";
-        if std::path::Path::new(font_path).exists() {
-            if let Ok(font_data) = std::fs::read(font_path) {
-                fonts.font_data.insert(
-                    "my_font".to_owned(),
-                    egui::FontData::from_owned(font_data).tweak(
-                        egui::FontTweak {
-                            scale: 1.0,
-                            ..Default::default()
+            ui.horizontal(|ui| {
+                ui.label("追加のルート証明書 (PEM、再起動後に反映):");
+                let mut cert_path = self.config.extra_root_cert.clone().unwrap_or_default();
+                if ui.add(egui::TextEdit::singleline(&mut cert_path).hint_text("C:\\path\\to\\ca.pem")).changed() {
+                    self.config.extra_root_cert = if cert_path.trim().is_empty() { None } else { Some(cert_path) };
+                }
+            });
+            ui.checkbox(&mut self.config.normalize_whitespace, "空行と前後の空白を取り除く");
+            ui.checkbox(&mut self.config.remove_deleted, "削除された投稿のコメントを消す");
+            ui.checkbox(&mut self.config.apply_edits, "編集された投稿のコメントを書き換える");
+            ui.horizontal_wrapped(|ui| {
+                ui.label("表示しないノート:");
+                for note_type in NoteType::ALL {
+                    let mut hidden = self.config.hidden_note_types.contains(&note_type);
+                    if ui.checkbox(&mut hidden, note_type.display_name()).changed() {
+                        if hidden {
+                            self.config.hidden_note_types.push(note_type);
+                        } else {
+                            self.config.hidden_note_types.retain(|t| *t != note_type);
                         }
-                    ).into(),
-                );
-                
-                // Proportionalフォントの先頭に挿入
-                fonts.families
-                    .entry(egui::FontFamily::Proportional)
-                    .or_default()
-                    .insert(0, "my_font".to_owned());
-                
-                // Monospaceフォントの先頭に挿入
-                fonts.families
-                    .entry(egui::FontFamily::Monospace)
-                    .or_default()
-                    .insert(0, "my_font".to_owned());
+                    }
+                }
+            });
+            if ui.checkbox(&mut self.autostart, "Windowsスタートアップに登録").changed() {
+                if let Err(e) = autostart::set_enabled(self.autostart) {
+                    eprintln!("[AUTOSTART] Failed to update registration: {}", e);
+                }
+                // 失敗したときにチェックが実際の状態とずれないように読み直す
+                self.autostart = autostart::is_enabled();
             }
-        }
-
-        cc.egui_ctx.set_fonts(fonts);
-        
-        let (tx, rx) = std::sync::mpsc::channel();
-        let (reconnect_tx, mut reconnect_rx) = tokio::sync::mpsc::unbounded_channel::<AppConfig>();
-        let is_connected = Arc::new(Mutex::new(false));
-        let is_connected_clone = is_connected.clone();
-        let runtime = Runtime::new().expect("Failed to create Tokio runtime");
-
-        // 複数Misskeyクライアントを並列実行
-        let mut current_config = config.clone();
-        let debug_mode = current_config.debug;
-        
-        // 各アカウント用のタスクハンドルを保持
-        let mut account_handles: Vec<tokio::task::JoinHandle<()>> = Vec::new();
-        
-        runtime.spawn(async move {
-            // 初回起動
-            let mut should_start = true;
-            
-            loop {
-                // 再接続リクエストをチェック
-                if let Ok(new_config) = reconnect_rx.try_recv() {
-                    println!("[MANUAL] Config update received, reconnecting all accounts...");
-                    *is_connected_clone.lock().unwrap() = false;
-                    current_config = new_config;
-                    
-                    // 既存のタスクをすべてキャンセル（自動的に切断）
-                    for handle in account_handles.drain(..) {
-                        handle.abort();
+            ui.add(egui::Slider::new(&mut self.config.emoji_anim_speed, 0.1..=4.0).logarithmic(true).text("絵文字アニメーションの速さ"));
+            ui.horizontal(|ui| {
+                let mut limited = self.config.max_emoji_width.is_some();
+                if ui.checkbox(&mut limited, "横長の絵文字の幅を制限").changed() {
+                    self.config.max_emoji_width = if limited { Some(96.0) } else { None };
+                }
+                if let Some(width) = self.config.max_emoji_width.as_mut() {
+                    ui.add(egui::Slider::new(width, 24.0..=400.0).text("px"));
+                }
+            });
+            ui.add(egui::Slider::new(&mut self.config.quote_chain_depth, 0..=MAX_QUOTE_CHAIN_DEPTH).text("引用の引用を表示する段数"));
+            ui.checkbox(&mut self.config.resolve_renote_emojis, "リノート元の絵文字も取得する（オフでAPIへの問い合わせを減らす）");
+            ui.add(egui::Slider::new(&mut self.config.emoji_scale, MIN_EMOJI_SCALE..=MAX_EMOJI_SCALE).text("文字に対する絵文字の大きさ"));
+            ui.add(egui::Slider::new(&mut self.config.max_emoji_per_comment, 0..=100).text("1コメントの絵文字の最大数（0で無制限）"));
+            ui.horizontal(|ui| {
+                ui.label("テーマ:");
+                // 選んだ時点で各項目に書き込む（テーマ自体は保存しない）
+                egui::ComboBox::from_id_salt("theme")
+                    .selected_text("選んで適用")
+                    .show_ui(ui, |ui| {
+                        for theme in Theme::ALL {
+                            if ui.selectable_label(false, theme.display_name()).clicked() {
+                                self.config.apply_theme(theme);
+                            }
+                        }
+                    });
+            });
+            ui.horizontal(|ui| {
+                ui.label("文字の影:");
+                ui.color_edit_button_srgba_unmultiplied(&mut self.config.shadow_color);
+                ui.add(egui::DragValue::new(&mut self.config.shadow_offset[0]).range(-10.0..=10.0).speed(0.1).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut self.config.shadow_offset[1]).range(-10.0..=10.0).speed(0.1).prefix("y: "));
+            });
+            ui.horizontal(|ui| {
+                let mut enabled = self.config.text_background.is_some();
+                if ui.checkbox(&mut enabled, "コメントの後ろに帯を表示").changed() {
+                    self.config.text_background = if enabled { Some(DEFAULT_TEXT_BACKGROUND) } else { None };
+                }
+                if let Some(rgba) = self.config.text_background.as_mut() {
+                    ui.color_edit_button_srgba_unmultiplied(rgba);
+                }
+            });
+            ui.checkbox(&mut self.config.show_avatars, "投稿者のアバターを表示");
+            ui.checkbox(&mut self.config.anonymize, "名前を仮名（User#1a2b3c4d）で表示（画面共有用、アバターも表示しない）");
+            ui.checkbox(&mut self.config.show_counts, "リノート・リアクション・リプライの数を表示");
+            ui.checkbox(&mut self.config.show_instance_icon, "投稿者のサーバーのアイコンを表示");
+            ui.horizontal(|ui| {
+                let mut focused = self.config.focus_host.is_some();
+                if ui.checkbox(&mut focused, "このサーバー以外の投稿者を薄く表示").changed() {
+                    self.config.focus_host = if focused {
+                        Some(self.config.accounts.first().map(|a| a.host.clone()).unwrap_or_default())
+                    } else {
+                        None
+                    };
+                }
+                if let Some(host) = self.config.focus_host.as_mut() {
+                    ui.text_edit_singleline(host);
+                }
+            });
+            ui.horizontal(|ui| {
+                let mut enabled = self.config.clear_hotkey.is_some();
+                if ui.checkbox(&mut enabled, "コメントを消去するホットキー（再起動後に有効）").changed() {
+                    self.config.clear_hotkey = if enabled { Some(DEFAULT_CLEAR_HOTKEY.to_string()) } else { None };
+                }
+                if let Some(keys) = self.config.clear_hotkey.as_mut() {
+                    ui.text_edit_singleline(keys);
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.config.high_contrast, "ハイコントラスト表示（文字色を統一し、縁取りと文字を大きくする）");
+                if self.config.high_contrast {
+                    let rgb = self.config.high_contrast_color;
+                    let mut color = egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                    if ui.color_edit_button_srgba(&mut color).changed() {
+                        self.config.high_contrast_color = [color.r(), color.g(), color.b()];
                     }
-                    should_start = true;
                 }
-                
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("MFM:");
+                egui::ComboBox::from_id_salt("render_mfm")
+                    .selected_text(self.config.render_mfm.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in [MfmMode::Raw, MfmMode::Strip, MfmMode::Render] {
+                            ui.selectable_value(&mut self.config.render_mfm, mode, mode.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("カスタム絵文字:");
+                egui::ComboBox::from_id_salt("emoji_mode")
+                    .selected_text(self.config.emoji_mode.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in [EmojiMode::Image, EmojiMode::Shortcode, EmojiMode::Hidden] {
+                            ui.selectable_value(&mut self.config.emoji_mode, mode, mode.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("CW付きの投稿:");
+                egui::ComboBox::from_id_salt("cw_mode")
+                    .selected_text(self.config.cw_mode.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in [CwMode::CwOnly, CwMode::Both, CwMode::BodyOnly] {
+                            ui.selectable_value(&mut self.config.cw_mode, mode, mode.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("複数アカウントの表示:");
+                egui::ComboBox::from_id_salt("stream_layout")
+                    .selected_text(self.config.layout.display_name())
+                    .show_ui(ui, |ui| {
+                        for layout in [StreamLayout::Merged, StreamLayout::SplitByAccount] {
+                            ui.selectable_value(&mut self.config.layout, layout, layout.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("重なったコメント:");
+                egui::ComboBox::from_id_salt("z_order_mode")
+                    .selected_text(self.config.z_order_mode.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in [ZOrderMode::NewestOnTop, ZOrderMode::OldestOnTop] {
+                            ui.selectable_value(&mut self.config.z_order_mode, mode, mode.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("描画バックエンド (再起動後に反映):");
+                egui::ComboBox::from_id_salt("renderer")
+                    .selected_text(self.config.renderer.display_name())
+                    .show_ui(ui, |ui| {
+                        for choice in [RendererChoice::Glow, RendererChoice::Wgpu] {
+                            ui.selectable_value(&mut self.config.renderer, choice, choice.display_name());
+                        }
+                    });
+            });
+            
+            ui.horizontal(|ui| {
+                ui.label("ユーザー名:");
+                egui::ComboBox::from_id_salt("show_username")
+                    .selected_text(self.config.show_username.display_name())
+                    .show_ui(ui, |ui| {
+                        for mode in [UsernameMode::Inline, UsernameMode::Above, UsernameMode::Hidden] {
+                            ui.selectable_value(&mut self.config.show_username, mode, mode.display_name());
+                        }
+                    });
+            });
+            
+            ui.checkbox(&mut self.config.show_timestamp, "投稿時刻を表示");
+            if self.config.show_timestamp {
+                ui.horizontal(|ui| {
+                    ui.label("書式:");
+                    ui.text_edit_singleline(&mut self.config.timestamp_format)
+                        .on_hover_text("%H:%M（時:分）、%m/%d %H:%M:%S（月/日 時:分:秒）など");
+                    egui::ComboBox::from_id_salt("timestamp_position")
+                        .selected_text(self.config.timestamp_position.display_name())
+                        .show_ui(ui, |ui| {
+                            for position in [TimestampPosition::Prefix, TimestampPosition::Suffix] {
+                                ui.selectable_value(&mut self.config.timestamp_position, position, position.display_name());
+                            }
+                        });
+                });
+            }
+            
+            ui.add_space(10.0);
+            
+            // キーワードハイライト
+            ui.label("キーワードハイライト:");
+            ui.add_space(5.0);
+            
+            let mut remove_rule = None;
+            for (idx, rule) in self.config.highlight_rules.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut rule.pattern)
+                        .hint_text("キーワード")
+                        .desired_width(150.0));
+                    
+                    // 色の上書き（チェックなしならアカウントの文字色）
+                    let mut override_color = rule.color.is_some();
+                    if ui.checkbox(&mut override_color, "色").changed() {
+                        rule.color = if override_color { Some([255, 255, 0]) } else { None };
+                    }
+                    if let Some(rgb) = rule.color.as_mut() {
+                        let mut color = egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
+                        if ui.color_edit_button_srgba(&mut color).changed() {
+                            *rgb = [color.r(), color.g(), color.b()];
+                        }
+                    }
+                    
+                    ui.add(egui::Slider::new(&mut rule.scale, 0.5..=3.0).text("サイズ"));
+                    ui.add(egui::Slider::new(&mut rule.speed_multiplier, 0.2..=2.0).text("速度"));
+                    
+                    if ui.button("🗑").clicked() {
+                        remove_rule = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = remove_rule {
+                self.config.highlight_rules.remove(idx);
+            }
+            
+            if ui.button("➕ ルールを追加").clicked() {
+                self.config.highlight_rules.push(misskey_post_viewer::HighlightRule::default());
+            }
+            
+            ui.add_space(10.0);
+            ui.label("キーワードで効果音を鳴らす:");
+            ui.add_space(5.0);
+            
+            let mut remove_trigger = None;
+            for (idx, trigger) in self.config.sound_triggers.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut trigger.pattern)
+                        .hint_text("キーワード")
+                        .desired_width(150.0));
+                    let mut sound = trigger.sound.to_string_lossy().into_owned();
+                    if ui.add(egui::TextEdit::singleline(&mut sound)
+                        .hint_text("効果音のファイル (wav / mp3 / ogg)")
+                        .desired_width(250.0)).changed() {
+                        trigger.sound = sound.into();
+                    }
+                    if ui.button("🗑").clicked() {
+                        remove_trigger = Some(idx);
+                    }
+                });
+            }
+            if let Some(idx) = remove_trigger {
+                self.config.sound_triggers.remove(idx);
+            }
+            
+            ui.horizontal(|ui| {
+                if ui.button("➕ 効果音を追加").clicked() {
+                    self.config.sound_triggers.push(misskey_post_viewer::SoundTrigger::default());
+                }
+                ui.add(egui::Slider::new(&mut self.config.sound_cooldown_secs, 0.0..=30.0).text("同じ効果音の間隔 (秒)"));
+            });
+            
+            ui.add_space(10.0);
+            ui.separator();
+            
+            ui.horizontal(|ui| {
+                if ui.button("保存").clicked() {
+                    if let Err(e) = self.config.save() {
+                        eprintln!("設定の保存に失敗: {}", e);
+                    }
+                }
+                
+                if ui.button("閉じる").clicked() {
+                    keep_open = false;
+                }
+            });
+            
+            ui.add_space(10.0);
+        
+        keep_open
+    }
+}
+
+impl MisskeyViewerApp {
+    fn new(
+        cc: &eframe::CreationContext<'_>, 
+        config: AppConfig,
+        tray_rx: CrossbeamReceiver<TrayEvent>,
+        tray_event_flag: Arc<Mutex<bool>>,
+        read_stdin: bool,
+    ) -> Self {
+        // フォント設定 (日本語表示のため)
+        let mut fonts = egui::FontDefinitions::default();
+        
+        // システムフォントを読み込む試み (Windows)
+        let font_path = "C:\\Windows\\Fonts\\meiryo.ttc";
+        if std::path::Path::new(font_path).exists() {
+            if let Ok(font_data) = std::fs::read(font_path) {
+                fonts.font_data.insert(
+                    "my_font".to_owned(),
+                    egui::FontData::from_owned(font_data).tweak(
+                        egui::FontTweak {
+                            scale: 1.0,
+                            ..Default::default()
+                        }
+                    ).into(),
+                );
+                
+                // Proportionalフォントの先頭に挿入
+                fonts.families
+                    .entry(egui::FontFamily::Proportional)
+                    .or_default()
+                    .insert(0, "my_font".to_owned());
+                
+                // Monospaceフォントの先頭に挿入
+                fonts.families
+                    .entry(egui::FontFamily::Monospace)
+                    .or_default()
+                    .insert(0, "my_font".to_owned());
+            }
+        }
+
+        cc.egui_ctx.set_fonts(fonts);
+        
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = CommentSender { tx, ctx: cc.egui_ctx.clone() };
+        let (reconnect_tx, mut reconnect_rx) = tokio::sync::mpsc::unbounded_channel::<AppConfig>();
+        let is_connected = Arc::new(Mutex::new(false));
+        let is_connected_clone = is_connected.clone();
+        let connection_statuses: ConnectionStatuses = Arc::new(Mutex::new(BTreeMap::new()));
+        let connection_statuses_outer = connection_statuses.clone();
+        let note_subscriptions = NoteSubscriptions::default();
+        let note_subscriptions_outer = note_subscriptions.clone();
+        let parse_failures = Arc::new(AtomicUsize::new(0));
+        let parse_failures_outer = parse_failures.clone();
+        let counters = Arc::new(PipelineCounters::default());
+        let counters_outer = counters.clone();
+        let emoji_backoff = Arc::new(HostBackoff::new());
+        let emoji_backoff_outer = emoji_backoff.clone();
+        let runtime = Runtime::new().expect("Failed to create Tokio runtime");
+        
+        // 絵文字・プレビュー画像・OGPで共有するダウンロードプール（接続はランタイム上で使い回す）
+        let download_pool = Arc::new(DownloadPool::new(
+            runtime.handle().clone(),
+            config.max_concurrent_downloads,
+            std::time::Duration::from_secs(config.download_timeout_secs),
+        ));
+        let http_client = download_pool.client();
+        
+        // `--stdin`: 標準入力の各行をコメントとして流す
+        if read_stdin {
+            let tx = tx.clone();
+            let max_emojis = config.max_emoji_per_comment;
+            std::thread::spawn(move || {
+                use std::io::BufRead;
+                for line in std::io::stdin().lock().lines() {
+                    match line {
+                        Ok(line) => {
+                            if let Some(comment) = ExternalComment::from_line(&line) {
+                                tx.send(CommentSignal::Add(Box::new(Comment::external(comment, "stdin", max_emojis))));
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("[STDIN] Failed to read: {}", e);
+                            break;
+                        }
+                    }
+                }
+                println!("[STDIN] End of input");
+            });
+        }
+        
+        // 外部ツールからコメントを受け付ける（ポートの変更は再起動後に反映）
+        if let Some(port) = config.enable_http_source {
+            let tx = tx.clone();
+            let secret = config.http_source_secret.clone();
+            let max_emojis = config.max_emoji_per_comment;
+            runtime.spawn(async move {
+                let result = push::serve(port, secret, move |comment| {
+                    tx.send(CommentSignal::Add(Box::new(Comment::external(comment, "HTTP", max_emojis))));
+                }).await;
+                if let Err(e) = result {
+                    eprintln!("[PUSH] HTTP source stopped: {}", e);
+                }
+            });
+        }
+
+        // 複数Misskeyクライアントを並列実行
+        let mut current_config = config.clone();
+        let debug_mode = current_config.debug;
+        
+        // 表示済みのノートID。再接続でタスクを作り直しても引き継ぎ、
+        // 接続し直した直後に同じノートが届いても二重に流さない
+        let seen_notes = Arc::new(Mutex::new(RecentIds::new(SEEN_NOTES_CAPACITY)));
+        // 異常終了する前に流れていたコメントと表示済みIDを戻す
+        if config.persist_state {
+            if let Some(state) = SavedState::load(&state_path()) {
+                println!("[STATE] Restoring {} comments", state.comments.len());
+                let mut seen = seen_notes.lock().unwrap();
+                for id in &state.seen_ids {
+                    seen.insert(id);
+                }
+                for saved in state.comments {
+                    tx.send(CommentSignal::Add(Box::new(Comment::from_saved(saved))));
+                }
+            }
+        }
+        let seen_notes_outer = seen_notes.clone();
+        // CSVに書き出せるように、流れたノートを記録しておく
+        let activity_log = Arc::new(Mutex::new(ActivityLog::new(ACTIVITY_LOG_CAPACITY)));
+        let activity_log_outer = activity_log.clone();
+        
+        // 各アカウント用のタスクハンドルを保持
+        let mut account_handles: Vec<tokio::task::JoinHandle<()>> = Vec::new();
+        
+        runtime.spawn(async move {
+            // ログイン直後の自動起動ではネットワークの準備ができるまで待ってから接続する
+            // （待っている間は接続中の画面を表示する）
+            if current_config.startup_delay_secs > 0 {
+                println!("[INFO] Waiting {}s before connecting...", current_config.startup_delay_secs);
+                tokio::time::sleep(tokio::time::Duration::from_secs(current_config.startup_delay_secs)).await;
+            }
+            
+            // 初回起動
+            let mut should_start = true;
+            
+            loop {
+                // 再接続リクエストをチェック
+                if let Ok(new_config) = reconnect_rx.try_recv() {
+                    println!("[MANUAL] Config update received, reconnecting all accounts...");
+                    *is_connected_clone.lock().unwrap() = false;
+                    current_config = new_config;
+                    
+                    // 既存のタスクをすべてキャンセル（自動的に切断）
+                    // 流れているコメントはUI側が持っているので消えず、表示済みIDも`seen_notes`に残る
+                    for handle in account_handles.drain(..) {
+                        handle.abort();
+                    }
+                    connection_statuses_outer.lock().unwrap().clear();
+                    should_start = true;
+                }
+                
                 // タスクが起動していない場合のみ起動
                 if should_start {
                     should_start = false;
@@ -674,254 +1496,506 @@ impl MisskeyViewerApp {
                     // 各アカウントごとに並列接続タスクを起動
                     for account in enabled_accounts {
                     let tx_clone = tx.clone();
+                    let http_client = http_client.clone();
                     let account_clone = account.clone();
                     let debug_clone = debug_mode;
+                    let highlight_rules = current_config.highlight_rules.clone();
+                    let highlight_mentions = current_config.highlight_mentions;
+                    let color_jitter = current_config.color_jitter;
+                    let length_speed_factor = current_config.length_speed_factor;
+                    let parse_options = ParseOptions {
+                        normalize_whitespace: current_config.normalize_whitespace,
+                        strip_mfm: current_config.render_mfm == MfmMode::Strip,
+                    };
+                    let parse_failures_clone = parse_failures_outer.clone();
+                    let counters = counters_outer.clone();
+                    let emoji_backoff = emoji_backoff_outer.clone();
+                    let remove_deleted = current_config.remove_deleted;
+                    let apply_edits = current_config.apply_edits;
+                    // 削除・編集を反映するときだけ、流れているノートをキャプチャする
+                    let capture_notes = remove_deleted || apply_edits;
+                    let mut unsubscribe_rx = note_subscriptions_outer.register(&account.name);
+                    let emoji_mode = current_config.emoji_mode;
+                    let max_emoji_per_comment = current_config.max_emoji_per_comment;
+                    let resolve_renote_emojis = current_config.resolve_renote_emojis;
+                    let quote_chain_depth = current_config.quote_chain_depth.min(MAX_QUOTE_CHAIN_DEPTH);
+                    let cw_mode = current_config.cw_mode;
+                    let anonymize = current_config.anonymize;
+                    let anonymize_salt = current_config.anonymize_salt.clone();
+                    let seen_notes = seen_notes.clone();
+                    let activity_log = activity_log_outer.clone();
+                    let show_instance_icon = current_config.show_instance_icon;
+                    let show_avatars = current_config.show_avatars;
+                    let show_counts = current_config.show_counts;
+                    let sync_server_blocks = current_config.sync_server_blocks && account.token.is_some();
+                    let hidden_note_types = current_config.hidden_note_types.clone();
+                    let statuses = connection_statuses_outer.clone();
+                    let max_reconnect_attempts = current_config.max_reconnect_attempts;
+                    let connect_options = ConnectOptions {
+                        secure: account.secure,
+                        accept_invalid_certs: account.danger_accept_invalid_certs,
+                    };
+                    statuses.lock().unwrap().insert(account.name.clone(), AccountStatus {
+                        connecting_since: Some(std::time::Instant::now()),
+                        ..Default::default()
+                    });
                     
                     let handle = tokio::spawn(async move {
                         let mut consecutive_failures = 0u32;
+                        // サーバーにチャンネルを拒否された回数（接続自体はできるので別に数え、ノートが届いたら戻す）
+                        let mut channel_rejections = 0u32;
+                        // 接続してすぐ切られた回数（ノートが届くか長く繋がったら戻す）
+                        let mut short_connections = 0u32;
+                        // 自分のユーザー情報。再接続をまたいでキャッシュする
+                        let mut me: Option<MiUser> = None;
+                        // 他のサーバーの絵文字を調べる用（アカウントの証明書設定は使わず、常にhttpsで証明書を検証する）
+                        // 遅いサーバーで受信が止まらないように短めに諦める
+                        let remote_client = misskey_post_viewer::http::client_with_timeout(REMOTE_EMOJI_TIMEOUT);
+                        // サーバー側でブロック・ミュートしているユーザーIDと、最後に取得した時刻
+                        let mut hidden_users: HashSet<String> = HashSet::new();
+                        let mut hidden_users_synced: Option<std::time::Instant> = None;
                         loop {
                             let start_time = std::time::Instant::now();
-                            println!("[{}] Connecting to Misskey ({}) ...", account_clone.name, account_clone.host);
-                            match MisskeyClient::connect(&account_clone.host, account_clone.token.clone()).await {
+                            println!("[{}] Connecting to {} ({}) ...", account_clone.name, account_clone.source.display_name(), account_clone.host);
+                            match StreamClient::connect(&account_clone, connect_options).await {
                                 Ok(mut client) => {
                                     println!("[{}] WebSocket connected in {:?}!", account_clone.name, start_time.elapsed());
+                                    let connected_at = std::time::Instant::now();
+                                    let api_client = client.api_client();
                                     consecutive_failures = 0;
+                                    
+                                    if me.is_none() && account_clone.token.is_some() {
+                                        match client.whoami().await {
+                                            Ok(user) => {
+                                                println!("[{}] Logged in as @{}", account_clone.name, user.username);
+                                                me = Some(user.clone());
+                                            }
+                                            Err(e) => {
+                                                eprintln!("[{}] Failed to fetch own user info: {}", account_clone.name, e);
+                                            }
+                                        }
+                                    }
                                 
                                     // アカウントのタイムライン設定を使用
-                                    let channel = account_clone.timeline.to_channel_name();
-                                    let id = format!("{}-{}", channel, account_clone.name);
-
-                                    if let Err(e) = client.subscribe(channel, &id, serde_json::json!({})) {
+                                    if let Err(e) = client.subscribe(&account_clone) {
                                         eprintln!("[{}] Subscribe failed: {}", account_clone.name, e);
+                                        update_status(&statuses, &account_clone.name, |s| mark_connecting(s, Some(e.to_string())));
                                         consecutive_failures += 1;
+                                        if give_up_if_exhausted(&statuses, &account_clone.name, consecutive_failures, max_reconnect_attempts) {
+                                            return;
+                                        }
                                         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
                                         continue;
                                     }
-                                    println!("[{}] Subscribed to {} ({}).", account_clone.name, channel, account_clone.timeline.display_name());
+                                    println!("[{}] Subscribed to {} timeline.", account_clone.name, account_clone.timeline.display_name());
+                                    // 使えない状態のときは、ノートが届いて本当に復旧したと分かるまで表示を残す
+                                    update_status(&statuses, &account_clone.name, |s| if !s.unavailable { *s = AccountStatus::default() });
+                                    // 購読した時刻（直後に届く古い投稿を見分ける）
+                                    let subscribed = (std::time::Instant::now(), chrono::Local::now());
+                                    // チャンネルを拒否されて切ったか（そのときは拒否の間隔だけ待つ）
+                                    let mut rejected = false;
 
                                     loop {
-                                        // WebSocketメッセージを受信
-                                        let msg_result = client.next_message().await;
+                                        // WebSocketメッセージを受信（画面から消えたノートのキャプチャはその合間に外す）
+                                        let msg_result = tokio::select! {
+                                            msg_result = client.next_message() => msg_result,
+                                            Some(note_id) = unsubscribe_rx.recv() => {
+                                                if capture_notes {
+                                                    if let Err(e) = client.unsub_note(&note_id) {
+                                                        eprintln!("[{}] Failed to release note: {}", account_clone.name, e);
+                                                    }
+                                                }
+                                                continue;
+                                            }
+                                        };
                                         if let Some(msg_result) = msg_result {
                                             match msg_result {
                                 Ok(msg) => {
                                     // println!("Received: {:?}", msg); // デバッグ用: 全メッセージ表示
                                     if let Message::Text(text) = msg {
-                                        if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(&text) {
-                                            if let Some(body) = parsed.get("body") {
-                                                if let Some(type_) = body.get("type") {
-                                                    if type_ == "note" {
-                                                        if let Some(note_body) = body.get("body") {
-                                                            let user = note_body.get("user");
-                                                            let name = user.and_then(|u| u.get("name")).and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-                                                            let username = user.and_then(|u| u.get("username")).and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-                                                            let user_host = user.and_then(|u| u.get("host")).and_then(|v| v.as_str()).map(|s| s.to_string());
-                                                            
-                                                            // 絵文字情報を抽出
-                                                            let mut emojis = Vec::new();
-                                                            let host = &account_clone.host;
-                                                            
-                                                            if let Some(emojis_obj) = note_body.get("emojis") {
-                                                                if let Some(emoji_map) = emojis_obj.as_object() {
-                                                                    for (emoji_name, emoji_url) in emoji_map {
-                                                                        if let Some(url) = emoji_url.as_str() {
-                                                                            emojis.push(EmojiInfo {
-                                                                                name: emoji_name.clone(),
-                                                                                url: url.to_string(),
-                                                                            });
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                            
-                                                            // テキストと名前から絵文字タグを探して、まだURLが取得できていないものをAPIで取得
-                                                            let mut all_text = String::new();
-                                                            if let Some(text) = note_body.get("text").and_then(|v| v.as_str()) {
-                                                                all_text.push_str(text);
-                                                            }
-                                                            // 名前も追加
-                                                            all_text.push(' ');
-                                                            all_text.push_str(&name);
-                                                            
-                                                            // 正規表現で:emoji_name:パターンを抽出
-                                                            use regex::Regex;
-                                                            let emoji_pattern = Regex::new(r":([a-zA-Z0-9_]+):").unwrap();
-                                                            let emoji_names: Vec<String> = emoji_pattern
-                                                                .captures_iter(&all_text)
-                                                                .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-                                                                .collect();
-                                                            
-                                                            for emoji_name in emoji_names {
-                                                                // 既に取得済みかチェック
-                                                                if !emojis.iter().any(|e| e.name == emoji_name) {
-                                                                    // APIから取得を試みる（非同期）
-                                                                    if let Ok(response) = reqwest::get(format!("https://{}/api/emoji?name={}", host, emoji_name)).await {
-                                                                        if let Ok(emoji_data) = response.json::<serde_json::Value>().await {
-                                                                            if let Some(url) = emoji_data.get("url").and_then(|v| v.as_str()) {
-                                                                                emojis.push(EmojiInfo {
-                                                                                    name: emoji_name.clone(),
-                                                                                    url: url.to_string(),
-                                                                                });
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                            
-                                                            // リノートの場合は元の投稿情報とテキストを取得
-                                                            let renote_info = if let Some(renote) = note_body.get("renote") {
-                                                                let orig_user = renote.get("user");
-                                                                let orig_name = orig_user.and_then(|u| u.get("name")).and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-                                                                let orig_username = orig_user.and_then(|u| u.get("username")).and_then(|v| v.as_str()).unwrap_or("Unknown").to_string();
-                                                                let orig_host = orig_user.and_then(|u| u.get("host")).and_then(|v| v.as_str()).unwrap_or("").to_string();
-                                                                
-                                                                // リノート元の絵文字も取得
-                                                                if let Some(renote_emojis_obj) = renote.get("emojis") {
-                                                                    if let Some(emoji_map) = renote_emojis_obj.as_object() {
-                                                                        for (emoji_name, emoji_url) in emoji_map {
-                                                                            if let Some(url) = emoji_url.as_str() {
-                                                                                if !emojis.iter().any(|e| e.name == *emoji_name) {
-                                                                                    emojis.push(EmojiInfo {
-                                                                                        name: emoji_name.clone(),
-                                                                                        url: url.to_string(),
-                                                                                    });
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                                
-                                                                // リノート元のテキストから絵文字を抽出
-                                                                let mut renote_text_for_emoji = String::new();
-                                                                if let Some(text) = renote.get("text").and_then(|v| v.as_str()) {
-                                                                    renote_text_for_emoji.push_str(text);
-                                                                }
-                                                                if let Some(cw) = renote.get("cw").and_then(|v| v.as_str()) {
-                                                                    renote_text_for_emoji.push(' ');
-                                                                    renote_text_for_emoji.push_str(cw);
-                                                                }
-                                                                // リノート元の投稿者名も追加
-                                                                renote_text_for_emoji.push(' ');
-                                                                renote_text_for_emoji.push_str(&orig_name);
-                                                                
-                                                                // 正規表現で:emoji_name:パターンを抽出
-                                                                let renote_emoji_names: Vec<String> = emoji_pattern
-                                                                    .captures_iter(&renote_text_for_emoji)
-                                                                    .filter_map(|cap| cap.get(1).map(|m| m.as_str().to_string()))
-                                                                    .collect();
-                                                                
-                                                                for emoji_name in renote_emoji_names {
-                                                                    // 既に取得済みかチェック
-                                                                    if !emojis.iter().any(|e| e.name == emoji_name) {
-                                                                        // APIから取得を試みる
-                                                                        if let Ok(response) = reqwest::get(format!("https://{}/api/emoji?name={}", host, emoji_name)).await {
-                                                                            if let Ok(emoji_data) = response.json::<serde_json::Value>().await {
-                                                                                if let Some(url) = emoji_data.get("url").and_then(|v| v.as_str()) {
-                                                                                    emojis.push(EmojiInfo {
-                                                                                        name: emoji_name.clone(),
-                                                                                        url: url.to_string(),
-                                                                                    });
-                                                                                }
-                                                                            }
-                                                                        }
-                                                                    }
-                                                                }
-                                                                
-                                                                // リノート元のテキスト（CW優先）
-                                                                let orig_text_raw = if let Some(cw) = renote.get("cw").and_then(|v| v.as_str()) {
-                                                                    if !cw.is_empty() {
-                                                                        format!("CW: {}", cw)
-                                                                    } else {
-                                                                        renote.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string()
-                                                                    }
-                                                                } else {
-                                                                    renote.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string()
-                                                                };
-                                                                
-                                                                // リノートのテキストも切り詰める
-                                                                let orig_text = if orig_text_raw.chars().count() > 80 {
-                                                                    format!("{}...", orig_text_raw.chars().take(80).collect::<String>())
-                                                                } else {
-                                                                    orig_text_raw
-                                                                };
-                                                                
-                                                                Some((orig_name, orig_username, orig_host, orig_text))
-                                                            } else {
-                                                                None
-                                                            };
-                                                            
-                                                            // CWがある場合はCWの内容を、ない場合は本文を表示
-                                                            let text_content = if let Some((_, _, _, ref rn_text)) = renote_info {
-                                                                // リノートの場合はリノート元のテキストを使用
-                                                                rn_text.clone()
-                                                            } else if let Some(cw) = note_body.get("cw").and_then(|v| v.as_str()) {
-                                                                if !cw.is_empty() {
-                                                                    format!("CW: {}", cw)
-                                                                } else {
-                                                                    note_body.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string()
-                                                                }
-                                                            } else {
-                                                                note_body.get("text").and_then(|v| v.as_str()).unwrap_or("").to_string()
-                                                            };
-                                                            
-                                                            // テキストを一定の文字数で切り詰める（100文字まで）
-                                                            let truncated_text = if text_content.chars().count() > 100 {
-                                                                format!("{}...", text_content.chars().take(100).collect::<String>())
-                                                            } else {
-                                                                text_content.clone()
-                                                            };
-
-                                                            if !text_content.is_empty() || renote_info.is_some() {
-                                                                // URL検出してOGPメタデータを取得（非同期）
-                                                                let url_preview = if let Some(url) = detect_url(&text_content) {
-                                                                    // OGPメタデータを非同期で取得
-                                                                    fetch_ogp_metadata(&url, debug_clone).await
-                                                                } else {
-                                                                    None
-                                                                };
-                                                                
-                                                                // ランダムなY座標と速度を生成
-                                                                use rand::Rng;
-                                                                let mut rng = rand::rng();
-                                                                let y = rng.random_range(50.0..800.0); // 画面の高さに応じて調整が必要だが一旦固定
-                                                                let speed = rng.random_range(4.0..8.0); // 速度を上げる
-
-                                                                let comment = Comment {
-                                                                    text: truncated_text,
-                                                                    x: 2000.0, // 初期位置（画面右外）
-                                                                    y,
-                                                                    speed,
-                                                                    name,
-                                                                    username,
-                                                                    user_host,
-                                                                    renote_info,
-                                                                    emojis,
-                                                                    url_preview,
-                                                                    account_color: account_clone.text_color,
-                                                                    account_name: account_clone.name.clone(),
-                                                                };
-                                                                let _ = tx_clone.send(comment);
-                                                            }
-                                                        }
+                                        let note = match client.parse_event(&text, &parse_options) {
+                                            Ok(TimelineEvent::Note(note)) => {
+                                                channel_rejections = 0;
+                                                if short_connections > 0 {
+                                                    if unavailable_backoff(short_connections).is_some() {
+                                                        println!("[{}] Instance is available again", account_clone.name);
+                                                        update_status(&statuses, &account_clone.name, |s| *s = AccountStatus::default());
                                                     }
+                                                    short_connections = 0;
+                                                }
+                                                *note
+                                            }
+                                            Ok(TimelineEvent::ChannelError { reason }) => {
+                                                eprintln!("[{}] Channel rejected by server: {}", account_clone.name, reason);
+                                                let message = format!("{}: タイムラインに接続できません（{}）", account_clone.name, reason);
+                                                update_status(&statuses, &account_clone.name, |s| mark_connecting(s, Some(message.clone())));
+                                                tx_clone.send(CommentSignal::Notice(message));
+                                                channel_rejections += 1;
+                                                if give_up_if_exhausted(&statuses, &account_clone.name, channel_rejections, max_reconnect_attempts) {
+                                                    return;
                                                 }
+                                                // 同じ理由で拒否され続けないように間隔を空けて接続し直す
+                                                let wait_secs = 2u64.saturating_pow(channel_rejections - 1).min(CHANNEL_RETRY_MAX_SECS);
+                                                tokio::time::sleep(tokio::time::Duration::from_secs(wait_secs)).await;
+                                                rejected = true;
+                                                break;
                                             }
+                                            Ok(TimelineEvent::Deleted { note_id }) => {
+                                                if remove_deleted {
+                                                    tx_clone.send(CommentSignal::Remove(note_id));
+                                                }
+                                                continue;
+                                            }
+                                            Ok(TimelineEvent::Updated { note_id, text, cw }) => {
+                                                if !apply_edits {
+                                                    continue;
+                                                }
+                                                let text = display_text(text.as_deref(), cw.as_deref(), cw_mode);
+                                                // 編集で増えた絵文字も、新しいノートと同じ上限で問い合わせる
+                                                let mut emojis = Vec::new();
+                                                if emoji_mode == EmojiMode::Image && client.has_emoji_api() {
+                                                    let emoji_names = limit_emoji_names(shortcode_names(&text).into_iter(), max_emoji_per_comment);
+                                                    // 編集イベントには投稿者のサーバーが含まれないので、他のサーバーの絵文字は問い合わせない
+                                                    resolve_shortcodes(&api_client, &remote_client, &account_clone, &[], emoji_names, &mut emojis, &emoji_backoff, debug_clone).await;
+                                                }
+                                                tx_clone.send(CommentSignal::Update { note_id, text, emojis });
+                                                continue;
+                                            }
+                                            Ok(_) => continue, // ノート以外のイベント
+                                            Err(e) => {
+                                                parse_failures_clone.fetch_add(1, Ordering::Relaxed);
+                                                if debug_clone {
+                                                    eprintln!("[{}] Failed to parse message: {}", account_clone.name, e);
+                                                }
+                                                continue;
+                                            }
+                                        };
+                                        
+                                        // ブロック・ミュート一覧は接続後の最初のノートで取得し、以降は一定間隔で取り直す
+                                        if sync_server_blocks && hidden_users_synced.is_none_or(|t| t.elapsed() >= BLOCKLIST_REFRESH_INTERVAL) {
+                                            match client.fetch_hidden_user_ids().await {
+                                                Ok(ids) => {
+                                                    if debug_clone {
+                                                        println!("[{}] Synced {} blocked/muted users", account_clone.name, ids.len());
+                                                    }
+                                                    hidden_users = ids;
+                                                }
+                                                Err(e) => eprintln!("[{}] Failed to sync blocks/mutes: {}", account_clone.name, e),
+                                            }
+                                            hidden_users_synced = Some(std::time::Instant::now());
                                         }
-                                    }
+                                        let is_hidden_author = |n: &ParsedNote| n.user_id.as_ref().is_some_and(|id| hidden_users.contains(id));
+                                        if is_hidden_author(&note) || note.renote.as_deref().is_some_and(is_hidden_author) {
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        // 購読直後にまとめて届く、購読より前の投稿は流さない
+                                        if account_clone.skip_backfill
+                                            && subscribed.0.elapsed() < BACKFILL_WINDOW
+                                            && is_backfill(note.created_at.as_deref(), &subscribed.1)
+                                        {
+                                            if debug_clone {
+                                                println!("[{}] Skipped backfilled note {}", account_clone.name, note.id);
+                                            }
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        
+                                        let is_reply = note.reply_id.is_some();
+                                        if is_reply && !account_clone.show_replies {
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        // コメントなしのリノートは絵文字の取得などをする前に捨てる
+                                        if account_clone.hide_pure_renotes && note.is_pure_renote() {
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        if note.note_types.iter().any(|t| hidden_note_types.contains(t)) {
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        // 再接続の前後や同じサーバーの複数アカウントで届いたノートは一度だけ流す
+                                        if !note.id.is_empty() {
+                                            let key = format!("{}/{}", account_clone.host, note.id);
+                                            if !seen_notes.lock().unwrap().insert(&key) {
+                                                counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                                continue;
+                                            }
+                                        }
+                                        activity_log.lock().unwrap().record(ActivityRow {
+                                            timestamp: note.created_at.clone().unwrap_or_default(),
+                                            account: account_clone.name.clone(),
+                                            username: note.username.clone(),
+                                            host: note.user_host.clone().unwrap_or_else(|| account_clone.host.clone()),
+                                            note_type: if note.note_types.is_empty() {
+                                                "note".to_string()
+                                            } else {
+                                                note.note_types.iter().map(|t| t.to_config_str()).collect::<Vec<_>>().join(";")
+                                            },
+                                            text_length: note.text.as_deref().map(|t| t.chars().count()).unwrap_or(0),
+                                        });
+                                        // 流量が多いときは一部だけを流す（絵文字の取得などをする前に間引く）
+                                        let sample_rate = account_clone.effective_sample_rate();
+                                        if sample_rate < 1.0 && !rand::random_bool(sample_rate.clamp(0.0, 1.0) as f64) {
+                                            counters.filtered.fetch_add(1, Ordering::Relaxed);
+                                            continue;
+                                        }
+                                        
+                                        // Blueskyは投稿者のハンドルなどをここで調べる（間引いた後にする）。
+                                        // firehoseでは調べていない投稿者がある程度たまるまで待ち、まとめて問い合わせる
+                                        for note in client.resolve_authors(note).await {
+                                            let mut name = note.name.clone();
+                                            let mut username = note.username.clone();
+                                            let user_host = note.user_host.clone();
+                                        
+                                            // `:emoji@host:`を問い合わせてよいサーバー（投稿者とリノート元の投稿者のサーバー）
+                                            let note_hosts: Vec<String> = std::iter::successors(Some(&note), |n| n.renote.as_deref())
+                                                .filter_map(|n| n.user_host.clone())
+                                                .collect();
+                                        
+                                            // 絵文字を画像で表示しない場合は絵文字の解決もダウンロードもしない
+                                            let resolve_emojis = emoji_mode == EmojiMode::Image;
+                                            // 名前からの問い合わせはできるサーバーだけ（Mastodonは投稿に全部含まれている）
+                                            let lookup_emojis = resolve_emojis && client.has_emoji_api();
+                                        
+                                            // 絵文字情報を抽出
+                                            let mut emojis = if resolve_emojis { note.emojis.clone() } else { Vec::new() };
+                                        
+                                            // テキストと名前から絵文字タグを探して、まだURLが取得できていないものをAPIで取得
+                                            let mut all_text = String::new();
+                                            if let Some(text) = &note.text {
+                                                all_text.push_str(text);
+                                            }
+                                            // 名前も追加
+                                            all_text.push(' ');
+                                            all_text.push_str(&name);
+                                        
+                                            if lookup_emojis {
+                                                let emoji_names = limit_emoji_names(shortcode_names(&all_text).into_iter(), max_emoji_per_comment);
+                                                resolve_shortcodes(&api_client, &remote_client, &account_clone, &note_hosts, emoji_names, &mut emojis, &emoji_backoff, debug_clone).await;
+                                            }
+                                        
+                                            // リノートの場合は元の投稿情報とテキストを取得
+                                            let mut quote_chain = Vec::new();
+                                            let mut renote_info = if let Some(renote) = &note.renote {
+                                                let orig_name = renote.name.clone();
+                                                // リノート元の絵文字は無効にされていれば解決しない（ショートコードのまま表示）
+                                                let resolve_emojis = resolve_emojis && resolve_renote_emojis;
+                                                let lookup_emojis = lookup_emojis && resolve_renote_emojis;
+                                            
+                                                // リノート元の絵文字も取得
+                                                for emoji in renote.emojis.iter().filter(|_| resolve_emojis) {
+                                                    if !emojis.iter().any(|e| e.name == emoji.name) {
+                                                        emojis.push(emoji.clone());
+                                                    }
+                                                }
+                                            
+                                                // 引用の引用…も設定した段数まで表示する（絵文字はノートに含まれる分だけ使い、APIでは調べない）
+                                                quote_chain = format_quote_chain(renote, quote_chain_depth, cw_mode);
+                                                let chain_notes = std::iter::successors(renote.renote.as_deref(), |n| n.renote.as_deref()).take(quote_chain_depth);
+                                                for emoji in chain_notes.flat_map(|n| n.emojis.iter()).filter(|_| resolve_emojis) {
+                                                    if !emojis.iter().any(|e| e.name == emoji.name) {
+                                                        emojis.push(emoji.clone());
+                                                    }
+                                                }
+                                            
+                                                // リノート元のテキストから絵文字を抽出
+                                                let mut renote_text_for_emoji = String::new();
+                                                if let Some(text) = &renote.text {
+                                                    renote_text_for_emoji.push_str(text);
+                                                }
+                                                if let Some(cw) = &renote.cw {
+                                                    renote_text_for_emoji.push(' ');
+                                                    renote_text_for_emoji.push_str(cw);
+                                                }
+                                                // リノート元の投稿者名も追加
+                                                renote_text_for_emoji.push(' ');
+                                                renote_text_for_emoji.push_str(&orig_name);
+                                            
+                                                if lookup_emojis {
+                                                    let renote_emoji_names = limit_emoji_names(shortcode_names(&renote_text_for_emoji).into_iter(), max_emoji_per_comment);
+                                                    resolve_shortcodes(&api_client, &remote_client, &account_clone, &note_hosts, renote_emoji_names, &mut emojis, &emoji_backoff, debug_clone).await;
                                                 }
+                                            
+                                                Some(renote_info(renote, cw_mode))
+                                            } else {
+                                                None
+                                            };
+                                        
+                                            // CWがある場合はCWの内容を、ない場合は本文を表示
+                                            let is_quote = note.is_quote();
+                                            let text_content = note_text(&note, cw_mode);
+                                            // プレビューのURLは切り詰める前の本文から探す
+                                            let full_text = preview_text(&note, cw_mode);
+                                        
+                                            // 本文は編集されたときと同じ手順で組み立てる
+                                            let reply_marker = is_reply && account_clone.mark_replies;
+                                            let truncated_text = comment_body(&text_content, reply_marker, emoji_mode);
+                                        
+                                            // 絵文字を表示しない場合はショートコードごと取り除く
+                                            if emoji_mode == EmojiMode::Hidden {
+                                                name = strip_emoji_shortcodes(&name);
+                                                for (rn_name, _, _, rn_text) in renote_info.iter_mut().chain(quote_chain.iter_mut()) {
+                                                    *rn_name = strip_emoji_shortcodes(rn_name);
+                                                    *rn_text = strip_emoji_shortcodes(rn_text);
+                                                }
+                                            }
+
+                                            // 画面共有などのために、名前とユーザー名をユーザーごとの仮名にする
+                                            if anonymize {
+                                                let local_host = account_clone.host.as_str();
+                                                name = pseudonym(&username, user_host.as_deref().unwrap_or(local_host), &anonymize_salt);
+                                                username = name.clone();
+                                                for (rn_name, rn_user, rn_host, _) in renote_info.iter_mut().chain(quote_chain.iter_mut()) {
+                                                    *rn_name = pseudonym(rn_user, if rn_host.is_empty() { local_host } else { rn_host.as_str() }, &anonymize_salt);
+                                                    *rn_user = rn_name.clone();
+                                                }
+                                            }
+
+                                            if !text_content.is_empty() || renote_info.is_some() {
+                                                // URL検出してOGPメタデータを取得（非同期）
+                                                let url_preview = if let Some(url) = detect_url(&full_text) {
+                                                    // OGPメタデータを非同期で取得
+                                                    fetch_ogp_metadata(&http_client, &url, debug_clone).await
+                                                } else {
+                                                    None
+                                                };
+                                            
+                                                // ランダムなY座標と速度を生成
+                                                use rand::Rng;
+                                                let mut rng = rand::rng();
+                                                let mut speed = rng.random_range(4.0..8.0); // 速度を上げる
+                                            
+                                                // 文字数で速度を補正（100文字で最も遅く、0文字で最も速い）
+                                                if length_speed_factor > 0.0 {
+                                                    let len_ratio = (truncated_text.chars().count() as f32 / 100.0).min(1.0);
+                                                    let len_factor = length_speed_factor * (len_ratio - 0.5);
+                                                    speed *= (1.0 - len_factor).clamp(0.5, 1.5);
+                                                }
+                                            
+                                                // 同じアカウントのコメントが見分けやすいように色を少しずらす
+                                                let mut account_color = account_clone.text_color;
+                                                if color_jitter > 0 {
+                                                    let jitter = color_jitter as i16;
+                                                    for channel in account_color.iter_mut() {
+                                                        let offset = rng.random_range(-jitter..=jitter);
+                                                        *channel = (*channel as i16 + offset).clamp(0, 255) as u8;
+                                                    }
+                                                }
+                                            
+                                                // キーワードハイライト（最初に一致したルールを適用）
+                                                let mut scale = 1.0;
+                                                if let Some(rule) = find_highlight(&highlight_rules, &text_content) {
+                                                    if let Some(color) = rule.color {
+                                                        account_color = color;
+                                                    }
+                                                    scale = rule.scale;
+                                                    speed *= rule.speed_multiplier;
+                                                }
+                                            
+                                                // 投稿者のサーバーのアイコン（ローカルユーザーなら接続先のサーバー）
+                                                let instance_icon_url = show_instance_icon.then(|| {
+                                                    let base_url = match &user_host {
+                                                        Some(user_host) => misskey_post_viewer::http::base_url(user_host, true),
+                                                        None => account_clone.base_url(),
+                                                    };
+                                                    format!("{}/favicon.ico", base_url)
+                                                });
+                                            
+                                                // 自分宛てのメンションか判定（mentionsのID、なければ本文の@username）
+                                                let mentioned = highlight_mentions && me.as_ref().map(|my_user| {
+                                                    note.mentions.contains(&my_user.id)
+                                                        || text_mentions(&text_content, &my_user.username)
+                                                }).unwrap_or(false);
+                                                if mentioned {
+                                                    speed *= 0.7; // 読みやすいように少し遅くする
+                                                }
+
+                                                let mut comment = Comment {
+                                                    text: truncated_text,
+                                                    x: 2000.0, // 初期位置（画面右外）
+                                                    y: 0.0, // UI側でレーンに割り当てる
+                                                    speed,
+                                                    name,
+                                                    username,
+                                                    user_host,
+                                                    renote_info,
+                                                    is_quote,
+                                                    quote_chain,
+                                                    emojis,
+                                                    url_preview,
+                                                    account_color,
+                                                    account_name: account_clone.name.clone(),
+                                                    scale,
+                                                    mentioned,
+                                                    note_id: note.id.clone(),
+                                                    reply_marker,
+                                                    width: 0.0,
+                                                    height: 0.0,
+                                                    age: 0.0,
+                                                    instance_icon_url,
+                                                    avatar_url: note.avatar_url.clone().filter(|_| show_avatars && !anonymize),
+                                                    host: note.user_host.clone().unwrap_or_else(|| account_clone.host.clone()),
+                                                    // 純粋なリノートは元の投稿の数を表示する
+                                                    counts: show_counts.then(|| match &note.renote {
+                                                        Some(renote) if note.is_pure_renote() => renote.counts,
+                                                        _ => note.counts,
+                                                    }),
+                                                    created_at: note.created_at.as_deref().and_then(parse_created_at),
+                                                };
+                                                comment.limit_emojis(max_emoji_per_comment);
+                                                tx_clone.send(CommentSignal::Add(Box::new(comment)));
+                                            
+                                                // 流れている間の削除・編集を受け取れるようにキャプチャしておく
+                                                if capture_notes && !note.id.is_empty() {
+                                                    if let Err(e) = client.sub_note(&note.id) {
+                                                        eprintln!("[{}] Failed to capture note: {}", account_clone.name, e);
+                                                    }
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
                                                 Err(e) => {
                                                     eprintln!("[{}] WebSocket error: {}", account_clone.name, e);
+                                                    update_status(&statuses, &account_clone.name, |s| mark_connecting(s, Some(e.to_string())));
                                                     break;
                                                 }
                                             }
                                         } else {
+                                            update_status(&statuses, &account_clone.name, |s| mark_connecting(s, None));
                                             break;
                                         }
                                     }
+                                    
+                                    // 接続してすぐ切られるのが続くときはメンテナンス中などとみなし、長めに待ってから接続し直す
+                                    // （チャンネルの拒否はこちらから切ったので数えない）
+                                    if rejected {
+                                        continue;
+                                    }
+                                    if connected_at.elapsed() < SHORT_CONNECTION {
+                                        short_connections += 1;
+                                    } else {
+                                        short_connections = 0;
+                                    }
+                                    if let Some(wait) = unavailable_backoff(short_connections) {
+                                        eprintln!("[{}] Instance looks unavailable ({} short connections), retrying in {:?}", account_clone.name, short_connections, wait);
+                                        update_status(&statuses, &account_clone.name, |s| {
+                                            mark_connecting(s, None);
+                                            s.unavailable = true;
+                                        });
+                                        tokio::time::sleep(wait).await;
+                                    }
                                 }
                                 Err(e) => {
                                     eprintln!("[{}] Connection failed: {}", account_clone.name, e);
+                                    update_status(&statuses, &account_clone.name, |s| mark_connecting(s, Some(e.to_string())));
                                     consecutive_failures += 1;
+                                    // 上限まで失敗したら設定が変わるまで接続しない
+                                    if give_up_if_exhausted(&statuses, &account_clone.name, consecutive_failures, max_reconnect_attempts) {
+                                        return;
+                                    }
                                     
                                     // 指数バックオフ
                                     let wait_secs = std::cmp::min(2u64.pow(consecutive_failures.saturating_sub(1)), 5);
@@ -944,14 +2018,8 @@ impl MisskeyViewerApp {
         });
 
         // 設定ファイルの初期タイムスタンプを取得
-        let config_path = if let Ok(exe_path) = std::env::current_exe() {
-            exe_path.parent().map(|p| p.join("config.toml"))
-        } else {
-            Some(std::path::PathBuf::from("config.toml"))
-        };
-        let config_last_modified = config_path
-            .as_ref()
-            .and_then(|p| std::fs::metadata(p).ok())
+        let config_last_modified = std::fs::metadata(config_path())
+            .ok()
             .and_then(|m| m.modified().ok());
 
         Self {
@@ -964,12 +2032,280 @@ impl MisskeyViewerApp {
             window_configured: false,
             config: config.clone(),
             is_connected,
-            emoji_cache: EmojiCache::new(),
-            preview_image_cache: PreviewImageCache::new(),
+            connection_statuses,
+            note_subscriptions,
+            emoji_cache: EmojiCache::new(download_pool.clone()),
+            preview_image_cache: PreviewImageCache::new(download_pool),
             config_last_modified,
+            parse_failures,
+            counters,
+            emoji_backoff,
+            last_counters_log: std::time::Instant::now(),
+            visible: true,
+            toasts: Toasts::new(),
+            stats: StatsRecorder::new(std::time::Duration::from_secs(config.stats_window_mins * 60)),
+            show_stats: false,
+            activity_log,
+            seen_notes: seen_notes_outer,
+            last_state_save: std::time::Instant::now(),
+            solo_account: None,
+            sound_player: None,
+            sound_cooldown: SoundCooldown::new(std::time::Duration::from_secs_f32(config.sound_cooldown_secs.max(0.0))),
         }
     }
 
+    /// 統計用に投稿者と本文で使われた絵文字を記録する
+    fn record_stats(&mut self, comment: &Comment) {
+        let emojis = segment_text(&comment.text, &comment.emojis)
+            .into_iter()
+            .filter_map(|segment| match segment {
+                TextSegment::Emoji(emoji) => Some(emoji.name),
+                TextSegment::Text(_) => None,
+            })
+            .collect();
+        self.stats.record(&comment.username, &comment.host, emojis);
+    }
+    
+    /// プロファイルを読み込んで現在の設定にし、全アカウントを接続し直す
+    ///
+    /// 設定ウィンドウや次回の起動でも同じ設定になるように、読み込んだ内容は設定ファイルにも保存する。
+    /// 上書きする前に、今の設定（アカウントやトークンを含む）を元のプロファイルに書き戻しておく。
+    fn switch_profile(&mut self, name: &str) {
+        let current = self.config.active_profile.clone().unwrap_or_else(|| DEFAULT_PROFILE_NAME.to_string());
+        if let Err(e) = self.config.save_profile(&current) {
+            eprintln!("[PROFILE] Failed to back up the current config to {:?}, not switching: {}", current, e);
+            return;
+        }
+        let mut profile = match AppConfig::load_profile(name) {
+            Ok(profile) => profile,
+            Err(e) => {
+                eprintln!("[PROFILE] Failed to load {:?}: {}", name, e);
+                return;
+            }
+        };
+        println!("[PROFILE] Switching from {:?} to {:?}", current, name);
+        profile.active_profile = Some(name.to_string());
+        // 仮名はプロファイルをまたいでも同じにする
+        if profile.anonymize_salt.is_empty() {
+            profile.anonymize_salt = self.config.anonymize_salt.clone();
+        }
+        if let Err(e) = profile.save() {
+            eprintln!("[PROFILE] Failed to save config: {}", e);
+        }
+        // 保存による設定ファイルの変更で二重に再接続しないようにする
+        self.config_last_modified = std::fs::metadata(config_path()).and_then(|m| m.modified()).ok();
+        self.config = profile.clone();
+        self.stats.set_window(std::time::Duration::from_secs(self.config.stats_window_mins * 60));
+        let _ = self.reconnect_tx.send(profile);
+    }
+    
+    /// 流れているコメントをすべて消す（ノートのキャプチャも外す）
+    fn clear_comments(&mut self) {
+        for comment in self.comments.drain(..) {
+            self.note_subscriptions.release(&comment);
+        }
+    }
+    
+    /// `keep`が`false`のコメントを消す（ノートのキャプチャも外す）
+    fn retain_comments(&mut self, mut keep: impl FnMut(&Comment) -> bool) {
+        let note_subscriptions = &self.note_subscriptions;
+        self.comments.retain(|comment| {
+            let kept = keep(comment);
+            if !kept {
+                note_subscriptions.release(comment);
+            }
+            kept
+        });
+    }
+    
+    /// 流れているコメントと表示済みIDを状態ファイルに書き出す
+    fn save_state(&mut self) {
+        self.last_state_save = std::time::Instant::now();
+        let state = SavedState {
+            comments: self.comments.iter().map(Comment::to_saved).collect(),
+            seen_ids: self.seen_notes.lock().unwrap().iter().map(|id| id.to_string()).collect(),
+        };
+        if let Err(e) = state.save(&state_path()) {
+            eprintln!("[STATE] Failed to save state: {}", e);
+        }
+    }
+    
+    /// 本文がキーワードに一致したら効果音を鳴らす（同じ効果音は待ち時間を空ける）
+    fn play_sound_trigger(&mut self, text: &str) {
+        if self.config.sound_triggers.is_empty() {
+            return;
+        }
+        self.sound_cooldown.set_cooldown(std::time::Duration::from_secs_f32(self.config.sound_cooldown_secs.max(0.0)));
+        if let Some(sound) = self.sound_cooldown.trigger(&self.config.sound_triggers, text) {
+            self.sound_player.get_or_insert_with(SoundPlayer::new).play(sound);
+        }
+    }
+    
+    /// 画面の右上に短い通知を出す
+    fn push_toast(&mut self, message: impl Into<String>) {
+        self.toasts.push(message, TOAST_COLOR);
+    }
+    
+    /// 通知を右上から下に積んで描き、期限が近いものは薄くする
+    fn draw_toasts(&mut self, ctx: &egui::Context) {
+        if self.toasts.is_empty() {
+            return;
+        }
+        let painter = ctx.layer_painter(egui::LayerId::new(egui::Order::Foreground, egui::Id::new("toasts")));
+        let right = ctx.viewport_rect().right() - 16.0;
+        let mut y = 16.0;
+        let now = std::time::Instant::now();
+        for toast in self.toasts.visible_at(now) {
+            let opacity = toast.opacity_at(now);
+            let [r, g, b] = toast.color;
+            let galley = painter.layout_no_wrap(
+                toast.message.clone(),
+                egui::FontId::proportional(16.0),
+                egui::Color32::from_rgb(r, g, b).gamma_multiply(opacity),
+            );
+            let rect = egui::Align2::RIGHT_TOP
+                .anchor_size(egui::pos2(right, y), galley.size())
+                .expand2(egui::vec2(10.0, 6.0));
+            painter.rect_filled(rect, egui::CornerRadius::same(6), egui::Color32::from_black_alpha(180).gamma_multiply(opacity));
+            painter.galley(rect.min + egui::vec2(10.0, 6.0), galley, egui::Color32::WHITE);
+            y = rect.bottom() + 6.0;
+        }
+    }
+    
+    /// 流れたノートの記録を設定ファイルと同じフォルダにCSVで書き出し、フォルダを開く
+    fn export_activity_csv(&mut self) {
+        let dir = config_path().parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let path = dir.join(format!("activity_{}.csv", secs));
+        let result = {
+            let log = self.activity_log.lock().unwrap();
+            log.write_csv(&path).map(|()| log.len())
+        };
+        match result {
+            Ok(count) => {
+                println!("[CSV] Exported {} notes to {:?}", count, path);
+                self.push_toast(format!("{}件のノートをCSVに保存しました", count));
+                let _ = open::that(&dir);
+            }
+            Err(e) => {
+                eprintln!("[CSV] Failed to export {:?}: {}", path, e);
+                self.toasts.push(format!("CSVに保存できませんでした: {}", e), TOAST_ERROR_COLOR);
+            }
+        }
+    }
+    
+    /// 直近の投稿数や多く使われた絵文字などを別ウィンドウで表示する
+    fn show_stats_window(&mut self, ctx: &egui::Context) {
+        self.stats.set_window(std::time::Duration::from_secs(self.config.stats_window_mins * 60));
+        let summary = self.stats.summary(STATS_TOP_COUNT);
+        let window_mins = self.config.stats_window_mins;
+        
+        let close_requested = ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("stats"),
+            egui::ViewportBuilder::default()
+                .with_title("統計")
+                .with_inner_size([320.0, 420.0]),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    ui.heading(format!("直近{}分", window_mins));
+                    ui.label(format!("投稿数: {}  ({:.1} 件/分)", summary.total_posts, summary.posts_per_minute));
+                    for (title, ranking) in [
+                        ("よく使われた絵文字", &summary.top_emojis),
+                        ("よく投稿したユーザー", &summary.top_users),
+                        ("サーバー", &summary.top_hosts),
+                    ] {
+                        ui.separator();
+                        ui.label(title);
+                        if ranking.is_empty() {
+                            ui.weak("まだありません");
+                        }
+                        for (name, count) in ranking {
+                            ui.label(format!("{}  {}", count, name));
+                        }
+                    }
+                });
+                ctx.input(|i| i.viewport().close_requested())
+            },
+        );
+        if close_requested {
+            self.show_stats = false;
+        }
+    }
+    
+    /// コメントがまだ流れていないときに出す表示の種類
+    fn overlay_state(&self) -> OverlayState {
+        if !self.config.accounts.iter().any(|a| a.enabled) {
+            return OverlayState::NoAccounts;
+        }
+        let statuses = self.connection_statuses.lock().unwrap();
+        if !statuses.is_empty() && statuses.values().all(|s| s.gave_up) {
+            OverlayState::AllFailed
+        } else {
+            OverlayState::Connecting
+        }
+    }
+    
+    /// 接続中の画面。スピナーとアカウントごとの経過時間・直近のエラーを表示する
+    fn draw_connecting(&self, ctx: &egui::Context, state: OverlayState) {
+        let painter = ctx.layer_painter(egui::LayerId::background());
+        let rect = ctx.viewport_rect();
+        let center = rect.center();
+        let time = ctx.input(|i| i.time) as f32;
+        
+        // 接続するものがなければ「接続中...」ではなく案内を出す（設定で何も出さないこともできる）
+        if state == OverlayState::NoAccounts {
+            if self.config.hide_idle_overlay {
+                return;
+            }
+            let title = if self.config.accounts.is_empty() { "アカウントが設定されていません" } else { "有効なアカウントがありません" };
+            painter.text(center, egui::Align2::CENTER_CENTER, title, egui::FontId::proportional(48.0), egui::Color32::WHITE);
+            painter.text(
+                egui::pos2(center.x, center.y + 40.0),
+                egui::Align2::CENTER_TOP,
+                "トレイアイコンの「設定」からアカウントを追加・有効化してください",
+                egui::FontId::proportional(18.0),
+                egui::Color32::from_gray(220),
+            );
+            return;
+        }
+        
+        if state == OverlayState::Connecting {
+            draw_spinner(&painter, center - egui::vec2(0.0, 64.0), 16.0, time);
+        }
+        painter.text(
+            center,
+            egui::Align2::CENTER_CENTER,
+            if state == OverlayState::AllFailed { "接続できませんでした" } else { "接続中..." },
+            egui::FontId::proportional(48.0),
+            egui::Color32::WHITE,
+        );
+        
+        let statuses = self.connection_statuses.lock().unwrap();
+        let mut y = center.y + 40.0;
+        for (name, status) in statuses.iter() {
+            let mut line = match status.connecting_since {
+                Some(since) if status.unavailable => format!("{}: サーバーを利用できません（メンテナンス中かもしれません、{}秒）", name, since.elapsed().as_secs()),
+                Some(since) => format!("{}: 接続中 ({}秒)", name, since.elapsed().as_secs()),
+                None if status.gave_up => format!("{}: 再接続を中止しました（設定を変更すると再試行します）", name),
+                None => format!("{}: 接続済み", name),
+            };
+            if let Some(error) = &status.last_error {
+                line.push_str(&format!(" - {}", error));
+            }
+            painter.text(
+                egui::pos2(center.x, y),
+                egui::Align2::CENTER_TOP,
+                line,
+                egui::FontId::proportional(18.0),
+                egui::Color32::from_gray(220),
+            );
+            y += 24.0;
+        }
+    }
+    
     fn configure_window_clickthrough(&mut self, frame: &eframe::Frame) {
         if let Ok(handle) = frame.window_handle() {
              if let RawWindowHandle::Win32(handle) = handle.as_raw() {
@@ -1001,13 +2337,6 @@ impl eframe::App for MisskeyViewerApp {
     #[allow(deprecated)]
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         let debug_mode = self.config.debug;
-        // デルタタイムを取得
-        let dt = ctx.input(|i| i.stable_dt);
-        
-        // アニメーション絵文字を更新
-        let dt_ms = (dt * 1000.0) as u32;
-        self.emoji_cache.update_animations(dt_ms);
-        
         // フラグをチェックしてイベント処理をトリガー
         if let Ok(mut flag) = self.tray_event_flag.try_lock() {
             if *flag {
@@ -1018,20 +2347,20 @@ impl eframe::App for MisskeyViewerApp {
         }
         
         // 設定ファイルの変更をチェック
-        let config_path = if let Ok(exe_path) = std::env::current_exe() {
-            exe_path.parent().map(|p| p.join("config.toml"))
-        } else {
-            Some(std::path::PathBuf::from("config.toml"))
-        };
-        
-        if let Some(path) = config_path {
-            if let Ok(metadata) = std::fs::metadata(&path) {
+        {
+            if let Ok(metadata) = std::fs::metadata(config_path()) {
                 if let Ok(modified) = metadata.modified() {
                     if self.config_last_modified.is_none() || 
                        self.config_last_modified.as_ref().map(|last| modified > *last).unwrap_or(false) {
                         // 設定ファイルが更新された
                         println!("[CONFIG] Configuration file changed, reloading...");
-                        if let Ok(new_config) = AppConfig::new() {
+                        if let Ok(mut new_config) = AppConfig::new() {
+                            // 塩のない設定ファイルに置き換えられたら作り直して保存する（保存でもう一度読み込まれる）
+                            if new_config.ensure_anonymize_salt() {
+                                if let Err(e) = new_config.save() {
+                                    eprintln!("[CONFIG] Failed to save anonymize salt: {}", e);
+                                }
+                            }
                             self.config = new_config.clone();
                             self.config_last_modified = Some(modified);
                             // 再接続シグナルを送信
@@ -1050,18 +2379,66 @@ impl eframe::App for MisskeyViewerApp {
                     println!("Opening settings window in separate process...");
                     // 別プロセスで設定ウィンドウを起動
                     if let Ok(exe_path) = std::env::current_exe() {
+                        // 同じ設定ファイルを開くようにパスも渡す
                         let _ = std::process::Command::new(exe_path)
                             .arg("--settings")
+                            .arg("--config")
+                            .arg(config_path())
                             .spawn();
                     }
                 }
+                TrayEvent::ToggleVisible => {
+                    self.visible = !self.visible;
+                    println!("[TRAY] Overlay {}", if self.visible { "shown" } else { "hidden" });
+                    if !self.visible {
+                        self.clear_comments();
+                    }
+                }
+                TrayEvent::Stats => {
+                    self.show_stats = !self.show_stats;
+                }
+                TrayEvent::ExportCsv => self.export_activity_csv(),
+                TrayEvent::SwitchProfile(name) => self.switch_profile(&name),
+                TrayEvent::Solo(account_name) => {
+                    println!("[TRAY] Solo account: {:?}", account_name);
+                    // ほかのアカウントのコメントは流れている途中でも消す
+                    if let Some(name) = &account_name {
+                        self.retain_comments(|c| &c.account_name == name);
+                    }
+                    self.solo_account = account_name;
+                }
+                TrayEvent::OpenProfilesDir => {
+                    let dir = profiles_dir();
+                    if let Err(e) = std::fs::create_dir_all(&dir) {
+                        eprintln!("[PROFILE] Failed to create {:?}: {}", dir, e);
+                    }
+                    let _ = open::that(&dir);
+                }
+                TrayEvent::ClearComments => {
+                    // 接続や重複チェックの状態はそのままで、流れているものだけ消す
+                    println!("[TRAY] Cleared {} comments", self.comments.len());
+                    self.clear_comments();
+                    self.push_toast("コメントを消去しました");
+                }
                 TrayEvent::Quit => {
                     println!("Quitting...");
+                    // 正常に終了したときは次回の起動で戻さない
+                    if self.config.persist_state {
+                        let _ = std::fs::remove_file(state_path());
+                    }
                     ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                 }
             }
         }
         
+        if self.show_stats {
+            self.show_stats_window(ctx);
+        }
+        
+        if self.config.persist_state && self.last_state_save.elapsed() >= STATE_SAVE_INTERVAL {
+            self.save_state();
+        }
+        
         // クリックスルーを有効化
         // eguiの入力処理を完全に無効化
         ctx.input_mut(|i| {
@@ -1081,20 +2458,104 @@ impl eframe::App for MisskeyViewerApp {
         self.preview_image_cache.process_downloads(ctx, debug_mode);
         
         // 新しいコメントを受信
-        while let Ok(mut comment) = self.rx.try_recv() {
+        while let Ok(signal) = self.rx.try_recv() {
+            let mut comment = match signal {
+                CommentSignal::Add(comment) => {
+                    self.counters.received.fetch_add(1, Ordering::Relaxed);
+                    self.record_stats(&comment);
+                    if !self.visible || self.solo_account.as_ref().is_some_and(|solo| *solo != comment.account_name) {
+                        self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                        self.note_subscriptions.release(&comment);
+                        continue;
+                    }
+                    self.play_sound_trigger(&comment.text);
+                    *comment
+                }
+                CommentSignal::Remove(note_id) => {
+                    // 削除されたノートのコメントを取り除く
+                    self.retain_comments(|c| c.note_id != note_id);
+                    continue;
+                }
+                CommentSignal::Notice(message) => {
+                    self.toasts.push(message, TOAST_ERROR_COLOR);
+                    continue;
+                }
+                CommentSignal::Update { note_id, text, emojis } => {
+                    // 編集されたノートの本文を差し替える（純粋なリノートは元投稿の表示なので対象外）
+                    for comment in self.comments.iter_mut() {
+                        if comment.note_id == note_id && (comment.renote_info.is_none() || comment.is_quote) {
+                            comment.text = comment_body(&text, comment.reply_marker, self.config.emoji_mode);
+                            for emoji in &emojis {
+                                if !comment.emojis.contains(emoji) {
+                                    comment.emojis.push(emoji.clone());
+                                }
+                            }
+                            comment.limit_emojis(self.config.max_emoji_per_comment);
+                        }
+                    }
+                    continue;
+                }
+            };
+            
             // 画面サイズに合わせて初期X座標を調整
             let rect = ctx.viewport_rect();
             comment.x = rect.width();
-
-            // Y座標も画面内に収まるように再調整（簡易的）
-            if comment.y > rect.height() - 50.0 {
-                comment.y = rect.height() / 2.0;
+            
+            if self.config.high_contrast {
+                comment.scale *= HIGH_CONTRAST_SCALE;
+            }
+            
+            // 描画前なので、幅は描画と同じフォントで組んで測り、高さは行数から見積もる
+            let font_size = 24.0 * comment.scale;
+            let line_height = font_size * LINE_HEIGHT_RATIO;
+            let mut lines = comment.text.split('\n').count();
+            if self.config.max_lines > 0 {
+                lines = lines.min(self.config.max_lines);
             }
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            comment.width = measure_comment_width(&painter, &self.config, &comment);
+            comment.height = lines as f32 * line_height;
+            let leading_icons = comment.instance_icon_url.iter().chain(&comment.avatar_url).count();
+            comment.width += leading_icons as f32 * (font_size + 4.0);
+            if self.config.show_username == UsernameMode::Above {
+                comment.height += 14.0 * comment.scale * LINE_HEIGHT_RATIO;
+            }
+            let quote_lines = usize::from(comment.is_quote) + comment.quote_chain.len();
+            comment.height += quote_lines as f32 * 16.0 * comment.scale * LINE_HEIGHT_RATIO;
+            if self.config.width_based_speed {
+                comment.speed *= width_speed_factor(comment.width);
+            }
+            
+            // 前のコメントと重ならないレーンに配置
+            let mut layout = LaneLayout {
+                area_top: 50.0,
+                area_bottom: rect.height() - 50.0,
+                lane_height: 24.0 * LINE_HEIGHT_RATIO,
+                spawn_x: rect.width(),
+                min_gap_px: self.config.min_gap_px,
+            };
+            // アカウントごとに分ける場合は、有効なアカウントの並び順で上から帯を割り当てる
+            if self.config.layout == StreamLayout::SplitByAccount {
+                let enabled: Vec<&str> = self.config.accounts.iter()
+                    .filter(|a| a.enabled)
+                    .map(|a| a.name.as_str())
+                    .collect();
+                if let Some(index) = enabled.iter().position(|name| *name == comment.account_name) {
+                    layout = layout.band(index, enabled.len());
+                }
+            }
+            let occupants: Vec<LaneOccupant> = self.comments.iter()
+                .map(|c| LaneOccupant { top: c.y, bottom: c.y + c.height, tail_x: c.x + c.width })
+                .collect();
+            comment.y = allocate_lane(&layout, &occupants, comment.height);
             self.comments.push_back(comment);
+            self.counters.displayed.fetch_add(1, Ordering::Relaxed);
         }
 
         // コメントの位置更新と描画
-        let dt = ctx.input(|i| i.stable_dt).min(0.1); // デルタタイム
+        // デルタタイム（止まっていた後に一気に進まないよう抑えるが、低いフレームレートでは2フレーム分まで許す）
+        let max_step = active_repaint_interval(self.config.target_fps).map_or(0.0, |d| d.as_secs_f32() * 2.0);
+        let dt = ctx.input(|i| i.stable_dt).min(max_step.max(0.1));
         let _screen_rect = ctx.screen_rect();
 
         // デバッグ: 先頭コメントが画面に入るとき
@@ -1104,208 +2565,238 @@ impl eframe::App for MisskeyViewerApp {
             }
         }
 
-        // 接続状態をチェック
-        if !*self.is_connected.lock().unwrap() {
-            // 接続中メッセージを表示
-            let painter = ctx.layer_painter(egui::LayerId::background());
-            let rect = ctx.screen_rect();
-            let center = rect.center();
-            let font_id = egui::FontId::proportional(48.0);
-            let text = "接続中...";
-            painter.text(
-                center,
-                egui::Align2::CENTER_CENTER,
-                text,
-                font_id.clone(),
-                egui::Color32::WHITE,
-            );
-            ctx.request_repaint();
+        // 接続状態をチェック（コメントが流れていれば一部の再接続中でもそのまま表示を続ける）
+        let all_connecting = self.connection_statuses.lock().unwrap()
+            .values()
+            .all(|s| s.connecting_since.is_some() || s.gave_up);
+        self.draw_toasts(ctx);
+        if !*self.is_connected.lock().unwrap() || (all_connecting && self.comments.is_empty()) {
+            let state = self.overlay_state();
+            self.draw_connecting(ctx, state);
+            // スピナーが動いて見える程度に間隔を空けて再描画（スピナーを出さない間はゆっくりでよい）
+            ctx.request_repaint_after(match state {
+                OverlayState::Connecting => CONNECTING_REPAINT_INTERVAL,
+                OverlayState::NoAccounts | OverlayState::AllFailed => IDLE_REPAINT_INTERVAL,
+            });
             return;
         }
         
         // 絵文字を事前にロード
-        let emoji_urls: Vec<String> = self.comments.iter()
+        let emoji_urls: HashSet<String> = self.comments.iter()
             .flat_map(|c| c.emojis.iter().map(|e| e.url.clone()))
             .collect();
-        for url in emoji_urls {
-            self.emoji_cache.load_emoji(ctx, &url, debug_mode);
+        for url in &emoji_urls {
+            self.emoji_cache.load_emoji(ctx, url, debug_mode);
+        }
+        
+        // アニメーション絵文字は画面に流れているコメントのものだけ進める（非表示中は進めない）
+        if self.visible {
+            let dt_ms = (ctx.input(|i| i.stable_dt) * 1000.0) as u32;
+            self.emoji_cache.set_animation_speed(self.config.emoji_anim_speed);
+            self.emoji_cache.update_animations(dt_ms, &emoji_urls);
         }
         
         // レイヤーペインターを使って直接描画
         let painter = ctx.layer_painter(egui::LayerId::background());
+        let time = ctx.input(|i| i.time) as f32; // MFMの効果用
+        let text_style = TextStyle::from_config(&self.config);
 
+        // 表示時間の上限を超えたものは画面外に出る前でも消す
+        if let Some(max_lifetime) = self.config.max_lifetime_secs {
+            let before = self.comments.len();
+            self.retain_comments(|c| c.age <= max_lifetime);
+            self.counters.expired.fetch_add(before - self.comments.len(), Ordering::Relaxed);
+        }
+        
         let mut retain_indices = Vec::new();
-        for (i, comment) in self.comments.iter_mut().enumerate() {
+        // 後に描いたものが上に重なるので、古いものを上にするときは新しい方から描く
+        let draw_order: Vec<usize> = match self.config.z_order_mode {
+            ZOrderMode::NewestOnTop => (0..self.comments.len()).collect(),
+            ZOrderMode::OldestOnTop => (0..self.comments.len()).rev().collect(),
+        };
+        for i in draw_order {
+            let comment = &mut self.comments[i];
             comment.x -= comment.speed * 60.0 * dt; // 60fps基準で速度調整
+            comment.age += dt;
 
             // 描画
-            // [アカウント名] 名前(@id)の形式で表示（リノートの場合は元投稿情報も含む）
-            let text = if let Some((orig_name, orig_username, orig_host, _)) = &comment.renote_info {
-                // リノートの場合
-                let orig_display = if orig_host.is_empty() {
-                    format!("{}(@{})", orig_name, orig_username)
-                } else {
-                    format!("{}(@{}@{})", orig_name, orig_username, orig_host)
-                };
-                let user_display = if let Some(host) = &comment.user_host {
-                    format!("{}(@{}@{})", comment.name, comment.username, host)
-                } else {
-                    format!("{}(@{})", comment.name, comment.username)
-                };
-                format!("[{}] {}: Rn({}): {}", comment.account_name, user_display, orig_display, comment.text)
-            } else {
-                // 通常の投稿
-                let user_display = if let Some(host) = &comment.user_host {
-                    format!("{}(@{}@{})", comment.name, comment.username, host)
-                } else {
-                    format!("{}(@{})", comment.name, comment.username)
-                };
-                format!("[{}] {}: {}", comment.account_name, user_display, comment.text)
-            };
-            
-            // 絵文字を含むテキストを処理
-            // テキストを分割して、テキスト部分と絵文字部分を識別
-            let mut segments = Vec::new(); // (is_emoji, content, emoji_info)
-            let mut current_text = String::new();
-            let mut chars = text.chars().peekable();
-            
-            while let Some(ch) = chars.next() {
-                if ch == ':' {
-                    // 絵文字タグの可能性をチェック
-                    let mut emoji_name = String::new();
-                    let mut temp_chars = chars.clone();
-                    let mut found_emoji = false;
-                    
-                    while let Some(&next_ch) = temp_chars.peek() {
-                        if next_ch == ':' {
-                            // 絵文字が存在するかチェック
-                            if let Some(emoji_info) = comment.emojis.iter().find(|e| e.name == emoji_name) {
-                                // テキスト部分を保存
-                                if !current_text.is_empty() {
-                                    segments.push((false, current_text.clone(), None));
-                                    current_text.clear();
-                                }
-                                // 絵文字部分を保存
-                                segments.push((true, emoji_name.clone(), Some(emoji_info.clone())));
-                                // チャーイテレータを進める
-                                for _ in 0..emoji_name.len() + 1 {
-                                    chars.next();
-                                }
-                                found_emoji = true;
-                                break;
-                            } else {
-                                break;
-                            }
-                        } else if next_ch.is_alphanumeric() || next_ch == '_' || next_ch == '-' {
-                            emoji_name.push(next_ch);
-                            temp_chars.next();
-                        } else {
-                            break;
-                        }
-                    }
-                    
-                    if !found_emoji {
-                        current_text.push(ch);
-                    }
-                } else {
-                    current_text.push(ch);
+            // 注目するサーバー以外の投稿者のコメントは全体を薄く描く
+            let mut painter = painter.clone();
+            if let Some(focus) = self.config.focus_host.as_deref().map(str::trim).filter(|h| !h.is_empty()) {
+                if !comment.host.eq_ignore_ascii_case(focus) {
+                    painter.multiply_opacity(UNFOCUSED_OPACITY);
                 }
             }
             
-            if !current_text.is_empty() {
-                segments.push((false, current_text, None));
-            }
+            // ハイコントラスト表示ではアカウントごとの色を使わず一色にする
+            let rgb = if self.config.high_contrast { self.config.high_contrast_color } else { comment.account_color };
+            let text_color = egui::Color32::from_rgb(rgb[0], rgb[1], rgb[2]);
             
-            // セグメントごとに描画（改行を考慮）
-            let font_id = egui::FontId::proportional(24.0);
-            let line_height = 28.0; // 行の高さ
-            let mut current_x = comment.x;
-            let mut current_line = 0;
+            let (header, body) = comment_text_parts(&self.config, comment);
             
-            for (is_emoji, content, emoji_info) in segments {
-                if is_emoji {
-                    // 絵文字を画像として描画
-                    if let Some(emoji_info) = emoji_info {
-                        // アニメーション絵文字をチェック
-                        let texture = if let Some(anim) = self.emoji_cache.animated_cache.get(&emoji_info.url) {
-                            Some(&anim.textures[anim.current_frame])
-                        } else {
-                            // 静止画絵文字をチェック
-                            self.emoji_cache.static_cache.get(&emoji_info.url).and_then(|opt| opt.as_ref())
-                        };
-                        
-                        if let Some(texture) = texture {
-                            let emoji_height = 24.0;
-                            let texture_size = texture.size();
-                            let aspect_ratio = texture_size[0] as f32 / texture_size[1] as f32;
-                            let emoji_width = emoji_height * aspect_ratio;
-                            
-                            // テキストのベースラインに合わせるため、少し下にオフセット
-                            let emoji_y_offset = 3.0; // フォントのディセンダーを考慮した調整
-                            
-                            let emoji_rect = egui::Rect::from_min_size(
-                                egui::pos2(current_x, comment.y + (current_line as f32 * line_height) + emoji_y_offset),
-                                egui::vec2(emoji_width, emoji_height)
-                            );
-                            painter.image(
-                                texture.id(),
-                                emoji_rect,
-                                egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                                egui::Color32::WHITE
-                            );
-                            current_x += emoji_width;
-                        }
-                    }
-                } else {
-                    // テキストを改行ごとに分割して描画
-                    for (line_idx, line) in content.split('\n').enumerate() {
-                        if line_idx > 0 {
-                            // 改行があった場合
-                            current_line += 1;
-                            current_x = comment.x; // X座標をリセット
-                        }
-                        
-                        if !line.is_empty() {
-                            let current_y = comment.y + (current_line as f32 * line_height);
-                            
-                            // アカウントの色を取得
-                            let text_color = egui::Color32::from_rgb(
-                                comment.account_color[0],
-                                comment.account_color[1],
-                                comment.account_color[2],
-                            );
-                            
-                            // 影
-                            painter.text(
-                                egui::pos2(current_x, current_y) + egui::vec2(2.0, 2.0),
-                                egui::Align2::LEFT_TOP,
-                                line,
-                                font_id.clone(),
-                                egui::Color32::BLACK,
-                            );
-                            // 本体
-                            let galley = painter.layout_no_wrap(
-                                line.to_string(),
-                                font_id.clone(),
-                                text_color
-                            );
-                            painter.text(
-                                egui::pos2(current_x, current_y),
-                                egui::Align2::LEFT_TOP,
-                                line,
-                                font_id.clone(),
-                                text_color,
-                            );
-                            current_x += galley.rect.width();
-                        }
+            // 背景の帯は文字の大きさが決まってから描くので、ここでは文字より奥に場所だけ取っておく
+            let background_shape = self.config.text_background.map(|rgba| (painter.add(egui::Shape::Noop), rgba));
+            
+            // アバターと投稿者のサーバーのアイコンを先頭に表示（読み込み中も場所は空けておく）
+            let mut text_x = comment.x;
+            let icon_size = if header.is_some() { 14.0 } else { 24.0 } * comment.scale;
+            let leading_icons = [
+                (&comment.avatar_url, ImageShape::Circle),
+                (&comment.instance_icon_url, ImageShape::Fit),
+            ];
+            for (url, shape) in leading_icons {
+                let Some(url) = url else { continue };
+                if let Some(texture) = self.preview_image_cache.load_image_sized(url, ICON_TEXTURE_SIZE, shape, self.config.debug) {
+                    painter.image(
+                        texture.id(),
+                        egui::Rect::from_min_size(egui::pos2(text_x, comment.y), egui::vec2(icon_size, icon_size)),
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                }
+                text_x += icon_size + 4.0;
+            }
+            
+            // 名前を本文の上に小さく表示
+            let mut text_top = comment.y;
+            let mut header_end = text_x;
+            // 画像で描く絵文字の残りの数（名前・本文・引用元で合わせて数える）
+            let mut remaining_emojis = match self.config.max_emoji_per_comment {
+                0 => usize::MAX,
+                max => max,
+            };
+            if let Some(header) = header {
+                let header_size = 14.0 * comment.scale;
+                let header_segments = limit_emoji(segment_text(&header, &comment.emojis), &mut remaining_emojis);
+                let mut header_cursor = DrawCursor::new(egui::pos2(text_x, comment.y));
+                draw_segments(
+                    &painter,
+                    &self.emoji_cache,
+                    &header_segments,
+                    &mut header_cursor,
+                    header_size,
+                    text_color,
+                    &[],
+                    time,
+                    &text_style,
+                    self.config.max_emoji_width,
+                );
+                header_end = header_cursor.right;
+                text_top += header_size * LINE_HEIGHT_RATIO;
+            }
+            
+            // MFMの効果ごとに分けてから、絵文字を含むテキストを分割して描画（改行を考慮）
+            let spans = if self.config.render_mfm == MfmMode::Render {
+                parse_mfm_spans(&body)
+            } else {
+                vec![MfmSpan { text: body, effects: Vec::new() }]
+            };
+            let font_size = 24.0 * comment.scale;
+            let line_height = font_size * LINE_HEIGHT_RATIO; // 行の高さ
+            let mut cursor = DrawCursor::new(egui::pos2(text_x, text_top));
+            let mut remaining_lines = self.config.max_lines;
+            for span in &spans {
+                let segments = limit_emoji(segment_text(&span.text, &comment.emojis), &mut remaining_emojis);
+                let segments = if self.config.max_lines > 0 {
+                    limit_lines(segments, remaining_lines)
+                } else {
+                    segments
+                };
+                draw_segments(
+                    &painter,
+                    &self.emoji_cache,
+                    &segments,
+                    &mut cursor,
+                    font_size,
+                    text_color,
+                    &span.effects,
+                    time,
+                    &text_style,
+                    self.config.max_emoji_width,
+                );
+                if self.config.max_lines > 0 {
+                    // 残りの行数を使い切ったら以降の断片は描かない
+                    let newlines = span.text.matches('\n').count();
+                    if newlines >= remaining_lines {
+                        break;
                     }
+                    remaining_lines -= newlines;
                 }
             }
             
+            // リノート数などを本文の最後の行の後ろに小さく表示
+            if let Some(badges) = comment.counts.as_ref().and_then(count_badges) {
+                let badge_size = 16.0 * comment.scale;
+                let badge_top = text_top + cursor.line as f32 * line_height + (font_size - badge_size);
+                let mut badge_cursor = DrawCursor::new(egui::pos2(cursor.x + 8.0, badge_top));
+                let badge_color = if self.config.high_contrast { text_color } else { text_color.gamma_multiply(0.7) };
+                draw_text_run(&painter, &mut badge_cursor, &badges, badge_size, badge_color, &[], time, &text_style);
+                cursor.x = badge_cursor.x;
+                cursor.right = cursor.right.max(badge_cursor.right);
+            }
+            let mut content_bottom = text_top + (cursor.line + 1) as f32 * line_height;
+            let mut current_x = cursor.right.max(header_end);
+            
+            // 引用元と、その先の引用の引用…は本文の下に小さく薄く表示（深いほど`>`を重ねる）
+            let quote_lines = comment.renote_info.iter()
+                .filter(|_| comment.is_quote)
+                .map(|quoted| ("QT ".to_string(), quoted))
+                .chain(comment.quote_chain.iter().enumerate().map(|(level, quoted)| {
+                    let depth = level + if comment.is_quote { 2 } else { 1 };
+                    ("> ".repeat(depth), quoted)
+                }));
+            for (prefix, (orig_name, orig_username, orig_host, orig_text)) in quote_lines {
+                let quote_size = 16.0 * comment.scale;
+                let quote_color = if self.config.high_contrast { text_color } else { text_color.gamma_multiply(0.7) };
+                let host = if orig_host.is_empty() { String::new() } else { format!("@{}", orig_host) };
+                let quote = format!("{}{}(@{}{}): {}", prefix, orig_name, orig_username, host, orig_text);
+                let mut quote_cursor = DrawCursor::new(egui::pos2(text_x, content_bottom));
+                draw_segments(
+                    &painter,
+                    &self.emoji_cache,
+                    &limit_emoji(segment_text(&quote, &comment.emojis), &mut remaining_emojis),
+                    &mut quote_cursor,
+                    quote_size,
+                    quote_color,
+                    &[],
+                    time,
+                    &text_style,
+                    self.config.max_emoji_width,
+                );
+                content_bottom += (quote_cursor.line + 1) as f32 * quote_size * LINE_HEIGHT_RATIO;
+                current_x = current_x.max(quote_cursor.right);
+            }
+            
+            if let Some((shape_index, [r, g, b, a])) = background_shape {
+                let background_rect = egui::Rect::from_min_max(
+                    egui::pos2(comment.x - 4.0, comment.y - 2.0),
+                    egui::pos2(current_x + 4.0, content_bottom + 2.0),
+                );
+                painter.set(shape_index, egui::epaint::RectShape::filled(
+                    background_rect,
+                    egui::CornerRadius::same(6),
+                    egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+                ));
+            }
+            
+            // 自分宛てのメンションは枠で囲んで強調
+            if comment.mentioned {
+                let mention_rect = egui::Rect::from_min_max(
+                    egui::pos2(comment.x - 4.0, comment.y - 2.0),
+                    egui::pos2(current_x + 4.0, content_bottom + 2.0),
+                );
+                painter.rect_stroke(
+                    mention_rect,
+                    egui::CornerRadius::same(4),
+                    egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 200, 0)),
+                    egui::StrokeKind::Outside,
+                );
+            }
+            
             // URLプレビューを表示
             if let Some(preview) = &comment.url_preview {
                 // プレビューカードをすべての行の下に表示
-                let card_y = comment.y + ((current_line + 1) as f32 * line_height); // 最終行の下に表示
+                let card_y = content_bottom; // 最終行の下に表示
                 let card_x = comment.x; // テキストの開始位置と同じX座標
                 let thumbnail_size = 80.0; // サムネイルのサイズ
                 
@@ -1356,7 +2847,7 @@ impl eframe::App for MisskeyViewerApp {
                     );
                     
                     // 画像をロードして表示
-                    if let Some(texture) = self.preview_image_cache.load_image(image_url, self.config.debug) {
+                    if let Some(texture) = self.preview_image_cache.load_image_sized(image_url, THUMBNAIL_TEXTURE_SIZE, ImageShape::Fit, self.config.debug) {
                         // アスペクト比を維持してサムネイルに収める
                         let img_size = texture.size_vec2();
                         let aspect = img_size.x / img_size.y;
@@ -1556,6 +3047,7 @@ impl eframe::App for MisskeyViewerApp {
             // テキストの幅を推定して、完全に画面外に出てから削除
             // current_xが最終的な右端位置なので、それを使用
             let total_width = current_x - comment.x;
+            comment.width = total_width;
             if comment.x + total_width > -10.0 { // テキストが完全に左に出たら消す
                 retain_indices.push(i);
             }
@@ -1571,16 +3063,51 @@ impl eframe::App for MisskeyViewerApp {
                      + front.username.chars().count() as f32 * 15.0 
                      + 200.0; // ユーザー情報の追加分
                  if front.x + estimated_width < -10.0 {
-                     self.comments.pop_front();
+                     if let Some(comment) = self.comments.pop_front() {
+                         self.note_subscriptions.release(&comment);
+                     }
                  } else {
                      break;
                  }
              }
         }
 
-        // アニメーションのために常時再描画をリクエスト
-        // バックグラウンドでもイベントを処理できるように短い間隔で再描画
-        ctx.request_repaint_after(std::time::Duration::from_millis(16)); // 約60fps
+        // デバッグ情報を左上に表示
+        if debug_mode {
+            let mut hud_text = format!(
+                "comments: {}  parse failures: {}\n{}\n{}",
+                self.comments.len(),
+                self.parse_failures.load(Ordering::Relaxed),
+                self.counters.summary(),
+                self.counters.expired_summary(),
+            );
+            for (host, remaining) in self.emoji_backoff.limited_hosts() {
+                hud_text.push_str(&format!("\nemoji API rate limited: {} ({}s)", host, remaining.as_secs() + 1));
+            }
+            // 取りこぼしを後から追えるように一定間隔でログにも出す
+            if self.last_counters_log.elapsed() >= COUNTERS_LOG_INTERVAL {
+                println!("[METRICS] {}  {}", self.counters.summary(), self.counters.expired_summary());
+                self.last_counters_log = std::time::Instant::now();
+            }
+            painter.text(
+                egui::pos2(10.0, 10.0),
+                egui::Align2::LEFT_TOP,
+                hud_text,
+                egui::FontId::monospace(14.0),
+                egui::Color32::from_rgb(0, 255, 0),
+            );
+        }
+
+        // コメントが流れている間はアニメーションのために`target_fps`で再描画する
+        // 何も表示していないときは間隔を空けて負荷を下げる（新着は受信タスクが起こすので遅れない）
+        if self.comments.is_empty() && self.toasts.is_empty() {
+            ctx.request_repaint_after(IDLE_REPAINT_INTERVAL);
+        } else {
+            match active_repaint_interval(self.config.target_fps) {
+                Some(interval) => ctx.request_repaint_after(interval),
+                None => ctx.request_repaint(),
+            }
+        }
     }
 
     fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
@@ -1589,6 +3116,418 @@ impl eframe::App for MisskeyViewerApp {
     }
 }
 
+// アバター・サーバーアイコンとプレビューのサムネイルのテクスチャの大きさ（高DPIでも粗くならないよう表示より大きめ）
+const ICON_TEXTURE_SIZE: u32 = 64;
+const THUMBNAIL_TEXTURE_SIZE: u32 = 160;
+
+// トレイのプロファイル切り替え項目のIDの接頭辞（後ろにプロファイル名が付く）
+const PROFILE_MENU_PREFIX: &str = "profile:";
+// トレイの「このアカウントのみ表示」の項目のIDの接頭辞（後ろにアカウント名が付く）
+const SOLO_MENU_PREFIX: &str = "solo:";
+
+// HTTPでコメントを受け付けるときの既定のポート
+const DEFAULT_HTTP_SOURCE_PORT: u16 = 8931;
+
+// 設定画面でコメントの消去のホットキーを有効にしたときの初期値
+const DEFAULT_CLEAR_HOTKEY: &str = "ctrl+shift+KeyX";
+
+// 他のサーバーの絵文字を問い合わせるときのタイムアウト
+const REMOTE_EMOJI_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
+// 絵文字APIがレート制限中のとき、解除を待ってから問い合わせる最大の時間（これより長ければ諦める）
+const MAX_EMOJI_RATE_LIMIT_WAIT: std::time::Duration = std::time::Duration::from_secs(3);
+
+// `persist_state`で流れているコメントを保存する間隔
+const STATE_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+// デバッグモードで件数をログに出す間隔
+const COUNTERS_LOG_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// CSV書き出し用に覚えておくノートの最大件数
+const ACTIVITY_LOG_CAPACITY: usize = 50_000;
+
+// 統計ウィンドウに表示する各ランキングの件数
+const STATS_TOP_COUNT: usize = 5;
+
+// 接続中画面の再描画間隔
+const CONNECTING_REPAINT_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// 幅に合わせて速度を変えるときの基準の幅と、速度の倍率の範囲
+const WIDTH_SPEED_REFERENCE_PX: f32 = 600.0;
+const WIDTH_SPEED_MIN_FACTOR: f32 = 0.4;
+const WIDTH_SPEED_MAX_FACTOR: f32 = 1.5;
+
+/// コメントの幅に反比例する速度の倍率（基準の幅で1.0）
+///
+/// 極端に長いコメントがいつまでも画面に残らないように範囲を制限する。
+fn width_speed_factor(width: f32) -> f32 {
+    (WIDTH_SPEED_REFERENCE_PX / width.max(1.0)).clamp(WIDTH_SPEED_MIN_FACTOR, WIDTH_SPEED_MAX_FACTOR)
+}
+
+// 待機中の再描画間隔
+const IDLE_REPAINT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// コメント表示中の再描画間隔（`target_fps`が0なら制限せず毎フレーム描画するのでNone）
+fn active_repaint_interval(target_fps: u32) -> Option<std::time::Duration> {
+    (target_fps > 0).then(|| std::time::Duration::from_secs_f64(1.0 / target_fps as f64))
+}
+
+// 重複表示を防ぐために覚えておくノートIDの数
+const SEEN_NOTES_CAPACITY: usize = 2000;
+
+// サーバー側のブロック・ミュート一覧を取り直す間隔
+const BLOCKLIST_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+// 行の高さとフォントサイズの比率（24px → 28px）
+const LINE_HEIGHT_RATIO: f32 = 28.0 / 24.0;
+
+// 設定画面でコメントの後ろの帯を有効にしたときの色（半透明の黒）
+const DEFAULT_TEXT_BACKGROUND: [u8; 4] = [0, 0, 0, 128];
+
+// 絵文字の倍率の範囲（極端な値で行が重なったり見えなくなったりしないように）
+const MIN_EMOJI_SCALE: f32 = 0.5;
+const MAX_EMOJI_SCALE: f32 = 2.0;
+
+// チャンネルを拒否されたときに接続し直すまでの最長の待ち時間（秒）
+const CHANNEL_RETRY_MAX_SECS: u64 = 60;
+
+// 通知の文字色（通常とエラー）
+const TOAST_COLOR: [u8; 3] = [255, 255, 255];
+const TOAST_ERROR_COLOR: [u8; 3] = [255, 120, 120];
+
+// 購読してからこの時間内に届いた古い投稿を再送分とみなす（`skip_backfill`）
+const BACKFILL_WINDOW: std::time::Duration = std::time::Duration::from_secs(2);
+
+// 引用の引用…を表示する最大の段数（設定の上限）
+const MAX_QUOTE_CHAIN_DEPTH: usize = 3;
+
+// 設定画面に出すMisskeyのチャンネルのパラメータ（キー, 表示名, サーバーの既定値）
+// （withRenotesは`server_hide_renotes`の専用のチェックボックスで設定する）
+const CHANNEL_PARAM_TOGGLES: [(&str, &str, bool); 2] = [
+    ("withReplies", "リプライも受け取る", false),
+    ("withFiles", "ファイル付きの投稿だけ受け取る", false),
+];
+
+// 注目するサーバー以外のコメントの不透明度
+const UNFOCUSED_OPACITY: f32 = 0.35;
+
+// ハイコントラスト表示での文字の拡大率
+const HIGH_CONTRAST_SCALE: f32 = 1.25;
+
+// ハイコントラスト表示で縁取りを描く位置（周囲を囲む）
+const OUTLINE_OFFSETS: [egui::Vec2; 8] = [
+    egui::vec2(-2.0, -2.0), egui::vec2(0.0, -2.0), egui::vec2(2.0, -2.0),
+    egui::vec2(-2.0, 0.0), egui::vec2(2.0, 0.0),
+    egui::vec2(-2.0, 2.0), egui::vec2(0.0, 2.0), egui::vec2(2.0, 2.0),
+];
+
+/// `draw_segments`の描画位置（続けて呼ぶと前回の続きから描く）
+struct DrawCursor {
+    origin: egui::Pos2,
+    x: f32,
+    line: usize,
+    right: f32, // これまでに描いた中で一番右の位置（複数行のときの幅）
+}
+
+impl DrawCursor {
+    fn new(origin: egui::Pos2) -> Self {
+        Self { origin, x: origin.x, line: 0, right: origin.x }
+    }
+    
+    fn advance(&mut self, width: f32) {
+        self.x += width;
+        self.right = self.right.max(self.x);
+    }
+}
+
+/// 点を円形に並べ、明るい点が回っていくスピナーを描く
+fn draw_spinner(painter: &egui::Painter, center: egui::Pos2, radius: f32, time: f32) {
+    const DOTS: usize = 8;
+    let head = (time * DOTS as f32) as usize % DOTS;
+    for i in 0..DOTS {
+        let angle = i as f32 / DOTS as f32 * std::f32::consts::TAU;
+        let pos = center + radius * egui::vec2(angle.cos(), angle.sin());
+        // 先頭から遅れるほど薄くする
+        let behind = (head + DOTS - i) % DOTS;
+        let alpha = 1.0 - behind as f32 / DOTS as f32;
+        painter.circle_filled(pos, 3.0, egui::Color32::WHITE.gamma_multiply(alpha));
+    }
+}
+
+/// MFMの効果から、その時刻での (位置のずれ, 回転角, 拡大率) を求める
+fn effect_transform(effects: &[MfmEffect], time: f32, seed: f32, font_size: f32) -> (egui::Vec2, f32, f32) {
+    let mut offset = egui::Vec2::ZERO;
+    let mut angle = 0.0;
+    let mut scale = 1.0;
+    
+    for effect in effects {
+        match effect {
+            MfmEffect::Shake => {
+                let amount = font_size / 12.0;
+                offset += egui::vec2((time * 40.0 + seed).sin(), (time * 53.0 + seed).cos()) * amount;
+            }
+            MfmEffect::Spin => {
+                angle += (time * std::f32::consts::TAU / 1.5) % std::f32::consts::TAU;
+            }
+            MfmEffect::Jelly => {
+                // 本来は横方向だけの伸縮だが、テキストは等倍でしか拡大できないので近似
+                scale *= 1.0 + 0.1 * (time * 10.0).sin();
+            }
+            MfmEffect::Tada => {
+                // 1秒ごとに少し大きくなって揺れる
+                let phase = time % 1.0;
+                if phase < 0.3 {
+                    scale *= 1.0 + 0.2 * (phase / 0.3 * std::f32::consts::PI).sin();
+                    angle += 0.1 * (phase * 40.0).sin();
+                }
+            }
+        }
+    }
+    
+    (offset, angle, scale)
+}
+
+/// 名前の表示位置に応じて、コメントのヘッダー行（名前を上に出すときだけ）と本文を組み立てる
+///
+/// [アカウント名] 名前(@id)の形式で表示（リノートの場合は元投稿情報も含む）し、投稿時刻を本文の前後に付ける。
+fn comment_text_parts(config: &AppConfig, comment: &Comment) -> (Option<String>, String) {
+    let fields = CommentFields {
+        account: &comment.account_name,
+        name: &comment.name,
+        user: &comment.username,
+        host: comment.user_host.as_deref(),
+        text: &comment.text,
+        renote: comment.renote_info.as_ref()
+            .filter(|_| !comment.is_quote)
+            .map(|(orig_name, orig_username, orig_host, _)| (orig_name.as_str(), orig_username.as_str(), orig_host.as_str())),
+    };
+    let (header, body) = match config.show_username {
+        UsernameMode::Inline => (None, render_comment(&config.comment_format, &fields)),
+        UsernameMode::Above => (
+            Some(render_comment(USERNAME_HEADER_FORMAT, &fields)),
+            render_comment(BODY_ONLY_FORMAT, &fields),
+        ),
+        UsernameMode::Hidden => (None, render_comment(BODY_ONLY_FORMAT, &fields)),
+    };
+    
+    let timestamp = comment.created_at
+        .filter(|_| config.show_timestamp)
+        .and_then(|time| format_timestamp(&time, &config.timestamp_format));
+    let body = match (timestamp, config.timestamp_position) {
+        (Some(time), TimestampPosition::Prefix) => format!("[{}] {}", time, body),
+        (Some(time), TimestampPosition::Suffix) => format!("{} [{}]", body, time),
+        (None, _) => body,
+    };
+    (header, body)
+}
+
+/// コメントの文字の部分を、描画と同じフォントで組んだときの幅（絵文字は高さと同じ幅とみなす）
+///
+/// 画像を読み込む前に測るので、絵文字の縦横比と`max_emoji_width`は考えない。
+fn measure_comment_width(painter: &egui::Painter, config: &AppConfig, comment: &Comment) -> f32 {
+    let (header, body) = comment_text_parts(config, comment);
+    let body = if config.render_mfm == MfmMode::Render {
+        parse_mfm_spans(&body).into_iter().map(|span| span.text).collect()
+    } else {
+        body
+    };
+    let emoji_scale = TextStyle::from_config(config).emoji_scale;
+    let max_lines = if config.max_lines > 0 { config.max_lines } else { usize::MAX };
+    let measure = |text: &str, font_size: f32| {
+        text.split('\n')
+            .take(max_lines)
+            .map(|line| {
+                segment_text(line, &comment.emojis).iter().map(|segment| match segment {
+                    TextSegment::Text(text) => painter.layout_no_wrap(
+                        text.clone(),
+                        egui::FontId::proportional(font_size),
+                        egui::Color32::WHITE,
+                    ).rect.width(),
+                    TextSegment::Emoji(_) => font_size * emoji_scale,
+                }).sum::<f32>()
+            })
+            .fold(0.0, f32::max)
+    };
+    let header_width = header.map_or(0.0, |header| measure(&header, 14.0 * comment.scale));
+    measure(&body, 24.0 * comment.scale).max(header_width)
+}
+
+// セグメントを描画し、`cursor`を描き終えた位置（右端のX座標と最終行のインデックス）まで進める
+#[allow(clippy::too_many_arguments)]
+fn draw_segments(
+    painter: &egui::Painter,
+    emoji_cache: &EmojiCache,
+    segments: &[TextSegment],
+    cursor: &mut DrawCursor,
+    font_size: f32,
+    text_color: egui::Color32,
+    effects: &[MfmEffect],
+    time: f32,
+    style: &TextStyle,
+    max_emoji_width: Option<f32>,
+) {
+    let line_height = font_size * LINE_HEIGHT_RATIO;
+    
+    for segment in segments {
+        match segment {
+            TextSegment::Emoji(emoji_info) => {
+                // アニメーション絵文字をチェック
+                let texture = if let Some(anim) = emoji_cache.animated_cache.get(&emoji_info.url) {
+                    Some(&anim.textures[anim.current_frame])
+                } else {
+                    // 静止画絵文字をチェック
+                    emoji_cache.static_cache.get(&emoji_info.url).and_then(|opt| opt.as_ref())
+                };
+                
+                if let Some(texture) = texture {
+                    let mut emoji_height = font_size * style.emoji_scale;
+                    let texture_size = texture.size();
+                    let aspect_ratio = texture_size[0] as f32 / texture_size[1] as f32;
+                    let mut emoji_width = emoji_height * aspect_ratio;
+                    
+                    // 横長すぎる絵文字は縦横比を保ったまま縮める
+                    if let Some(max_width) = max_emoji_width.filter(|&max_width| emoji_width > max_width) {
+                        emoji_width = max_width;
+                        emoji_height = max_width / aspect_ratio;
+                    }
+                    
+                    // テキストのベースラインに合わせるため少し下にずらし、文字と大きさが違えば行の中で上下中央に置く
+                    let emoji_y_offset = font_size / 8.0 + (font_size - emoji_height) / 2.0; // フォントのディセンダーを考慮した調整
+                    
+                    let emoji_rect = egui::Rect::from_min_size(
+                        egui::pos2(cursor.x, cursor.origin.y + (cursor.line as f32 * line_height) + emoji_y_offset),
+                        egui::vec2(emoji_width, emoji_height)
+                    );
+                    let uv = egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0));
+                    if effects.is_empty() {
+                        painter.image(texture.id(), emoji_rect, uv, egui::Color32::WHITE);
+                    } else {
+                        let (offset, angle, scale) = effect_transform(effects, time, cursor.x, font_size);
+                        let center = emoji_rect.center() + offset;
+                        let mut mesh = egui::Mesh::with_texture(texture.id());
+                        mesh.add_rect_with_uv(
+                            egui::Rect::from_center_size(center, emoji_rect.size() * scale),
+                            uv,
+                            egui::Color32::WHITE,
+                        );
+                        mesh.rotate(egui::emath::Rot2::from_angle(angle), center);
+                        painter.add(mesh);
+                    }
+                    cursor.advance(emoji_width);
+                } else if let Some(None) = emoji_cache.static_cache.get(&emoji_info.url) {
+                    // 取得に失敗した絵文字は代わりの記号を表示
+                    draw_text_run(painter, cursor, "□", font_size, text_color, effects, time, style);
+                } else {
+                    // 読み込み中はショートコードを薄く表示
+                    let shortcode = format!(":{}:", emoji_info.name);
+                    let color = if style.high_contrast { text_color } else { text_color.gamma_multiply(0.5) };
+                    draw_text_run(painter, cursor, &shortcode, font_size, color, effects, time, style);
+                }
+            }
+            TextSegment::Text(content) => {
+                // テキストを改行ごとに分割して描画
+                for (line_idx, line) in content.split('\n').enumerate() {
+                    if line_idx > 0 {
+                        // 改行があった場合
+                        cursor.line += 1;
+                        cursor.x = cursor.origin.x; // X座標をリセット
+                    }
+                    
+                    if !line.is_empty() {
+                        // 右から左に書く部分は表示順に並べ替える
+                        let line = visual_order(line);
+                        draw_text_run(painter, cursor, &line, font_size, text_color, effects, time, style);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 文字の影や縁取り、絵文字の大きさなどの描き方
+struct TextStyle {
+    high_contrast: bool, // 影の代わりに黒い太い縁取りを付ける
+    shadow_color: egui::Color32,
+    shadow_offset: egui::Vec2,
+    emoji_scale: f32, // 文字の大きさに対する絵文字の高さ
+}
+
+impl TextStyle {
+    fn from_config(config: &AppConfig) -> Self {
+        let [r, g, b, a] = config.shadow_color;
+        TextStyle {
+            high_contrast: config.high_contrast,
+            shadow_color: egui::Color32::from_rgba_unmultiplied(r, g, b, a),
+            shadow_offset: egui::vec2(config.shadow_offset[0], config.shadow_offset[1]),
+            emoji_scale: config.emoji_scale.clamp(MIN_EMOJI_SCALE, MAX_EMOJI_SCALE),
+        }
+    }
+}
+
+/// 改行を含まないテキストを影付きでカーソル位置に描画し、カーソルを進める
+///
+/// ハイコントラスト表示なら影の代わりに太い縁取りを付ける。
+#[allow(clippy::too_many_arguments)]
+fn draw_text_run(
+    painter: &egui::Painter,
+    cursor: &mut DrawCursor,
+    text: &str,
+    font_size: f32,
+    text_color: egui::Color32,
+    effects: &[MfmEffect],
+    time: f32,
+    style: &TextStyle,
+) {
+    let single_shadow = [style.shadow_offset];
+    let (shadow_offsets, shadow_color): (&[egui::Vec2], _) = if style.high_contrast {
+        (&OUTLINE_OFFSETS, egui::Color32::BLACK)
+    } else {
+        (&single_shadow, style.shadow_color)
+    };
+    let line_height = font_size * LINE_HEIGHT_RATIO;
+    let current_y = cursor.origin.y + (cursor.line as f32 * line_height);
+    let galley = painter.layout_no_wrap(
+        text.to_string(),
+        egui::FontId::proportional(font_size),
+        text_color
+    );
+    let advance = galley.rect.width();
+    
+    if effects.is_empty() {
+        // 影
+        for &shadow_offset in shadow_offsets {
+            painter.text(
+                egui::pos2(cursor.x, current_y) + shadow_offset,
+                egui::Align2::LEFT_TOP,
+                text,
+                egui::FontId::proportional(font_size),
+                shadow_color,
+            );
+        }
+        // 本体
+        painter.galley(egui::pos2(cursor.x, current_y), galley, text_color);
+    } else {
+        // 効果は元の大きさの中心を基準にかけ、送り幅は変えない
+        let (offset, angle, scale) = effect_transform(effects, time, cursor.x, font_size);
+        let center = egui::pos2(cursor.x, current_y) + galley.rect.size() / 2.0 + offset;
+        let rotation = egui::emath::Rot2::from_angle(angle);
+        let layers = shadow_offsets
+            .iter()
+            .map(|&shadow_offset| (shadow_color, shadow_offset))
+            .chain(std::iter::once((text_color, egui::Vec2::ZERO)));
+        for (color, shadow_offset) in layers {
+            let scaled = painter.layout_no_wrap(
+                text.to_string(),
+                egui::FontId::proportional(font_size * scale),
+                color,
+            );
+            let top_left = center + shadow_offset - rotation * (scaled.rect.size() / 2.0);
+            painter.add(egui::epaint::TextShape::new(top_left, scaled, color).with_angle(angle));
+        }
+    }
+    cursor.advance(advance);
+}
+
 fn trigger_window_update() {
     use windows::Win32::Foundation::{WPARAM, LPARAM};
     use windows::Win32::UI::WindowsAndMessaging::{ShowWindow, SW_SHOW};
@@ -1607,23 +3546,143 @@ fn trigger_window_update() {
     }
 }
 
-// URLを検出する（軽量な処理）
-fn detect_url(text: &str) -> Option<String> {
-    use regex::Regex;
-    let url_regex = Regex::new(r"https?://[^\s]+").ok()?;
-    url_regex.find(text).map(|m| m.as_str().to_string())
+// テキストに@usernameのメンションが含まれるか（リモートの同名ユーザーは除外）
+fn text_mentions(text: &str, username: &str) -> bool {
+    let needle = format!("@{}", username);
+    text.match_indices(&needle).any(|(pos, _)| {
+        let before_ok = text[..pos].chars().next_back()
+            .map(|c| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(true);
+        let after_ok = text[pos + needle.len()..].chars().next()
+            .map(|c| !(c.is_alphanumeric() || c == '_' || c == '@'))
+            .unwrap_or(true);
+        before_ok && after_ok
+    })
+}
+
+/// `emojis`にまだない絵文字の画像URLをAPIで調べて追加する
+///
+/// `:emoji@host:`は`remote_hosts`（ノートの投稿者・リノート元のサーバー）のものだけを問い合わせる。
+#[allow(clippy::too_many_arguments)]
+async fn resolve_shortcodes(
+    api_client: &reqwest::Client,
+    remote_client: &reqwest::Client,
+    account: &Account,
+    remote_hosts: &[String],
+    emoji_names: Vec<String>,
+    emojis: &mut Vec<EmojiInfo>,
+    backoff: &HostBackoff,
+    debug_mode: bool,
+) {
+    for emoji_name in emoji_names {
+        if emojis.iter().any(|e| e.name == emoji_name) {
+            continue;
+        }
+        if let Some(url) = fetch_shortcode_url(api_client, remote_client, account, remote_hosts, &emoji_name, backoff, debug_mode).await {
+            emojis.push(EmojiInfo { name: emoji_name, url });
+        }
+    }
+}
+
+/// APIで問い合わせる絵文字の名前を、重複を除いて最初の`max`種類までにする（0なら無制限）
+fn limit_emoji_names(names: impl Iterator<Item = String>, max: usize) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for name in names {
+        if max > 0 && unique.len() >= max {
+            break;
+        }
+        if !unique.contains(&name) {
+            unique.push(name);
+        }
+    }
+    unique
+}
+
+/// `ctrl+shift+KeyX`のような文字列のホットキーを登録し、イベントで届くIDを返す
+fn register_hotkey(manager: &GlobalHotKeyManager, keys: &str) -> Option<u32> {
+    let hotkey = match keys.parse::<HotKey>() {
+        Ok(hotkey) => hotkey,
+        Err(e) => {
+            eprintln!("[HOTKEY] Invalid hotkey {:?}: {}", keys, e);
+            return None;
+        }
+    };
+    match manager.register(hotkey) {
+        Ok(()) => {
+            println!("[HOTKEY] Registered {}", keys);
+            Some(hotkey.id())
+        }
+        Err(e) => {
+            eprintln!("[HOTKEY] Failed to register {}: {}", keys, e);
+            None
+        }
+    }
+}
+
+/// `/api/emoji`でカスタム絵文字の画像URLを調べる
+///
+/// サーバーにレート制限（429）されている間は解除を待ってから問い合わせ、
+/// 長く待つ必要があるときは問い合わせずに諦める（ショートコードのまま表示される）。
+async fn fetch_emoji_url(
+    api_client: &reqwest::Client,
+    api_base: &str,
+    host: &str,
+    emoji_name: &str,
+    backoff: &HostBackoff,
+    debug_mode: bool,
+) -> Option<String> {
+    if let Some(wait) = backoff.remaining(host) {
+        if wait > MAX_EMOJI_RATE_LIMIT_WAIT {
+            return None;
+        }
+        tokio::time::sleep(wait).await;
+    }
+    
+    let response = api_client.get(format!("{}/emoji", api_base)).query(&[("name", emoji_name)]).send().await.ok()?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after);
+        let wait = backoff.rate_limited(host, retry_after);
+        if debug_mode {
+            eprintln!("[EMOJI] {} is rate limiting emoji lookups, backing off for {}s", host, wait.as_secs());
+        }
+        return None;
+    }
+    backoff.succeeded(host);
+    
+    let emoji_data = response.json::<serde_json::Value>().await.ok()?;
+    emoji_data.get("url").and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// ショートコードの絵文字の画像URLを調べる
+///
+/// `:emoji@host:` は絵文字のあるサーバーに`remote_client`で（常にhttpsで）、それ以外はアカウントのサーバーに問い合わせる。
+/// 本文に書くだけで好きなサーバーに問い合わせさせられないよう、`remote_hosts`にないサーバーには問い合わせない
+/// （その絵文字はノートに含まれる`emojis`だけを使う）。
+async fn fetch_shortcode_url(
+    api_client: &reqwest::Client,
+    remote_client: &reqwest::Client,
+    account: &Account,
+    remote_hosts: &[String],
+    emoji_name: &str,
+    backoff: &HostBackoff,
+    debug_mode: bool,
+) -> Option<String> {
+    match split_emoji_host(emoji_name) {
+        (_, Some(host)) if !remote_hosts.iter().any(|h| h.eq_ignore_ascii_case(host)) => None,
+        (name, Some(host)) => {
+            let api_base = format!("{}/api", misskey_post_viewer::http::base_url(host, true));
+            fetch_emoji_url(remote_client, &api_base, host, name, backoff, debug_mode).await
+        }
+        (name, None) => fetch_emoji_url(api_client, &account.api_base(), &account.host, name, backoff, debug_mode).await,
+    }
 }
 
 // OGPメタデータを非同期で取得
-async fn fetch_ogp_metadata(url: &str, _debug_mode: bool) -> Option<UrlPreview> {
+async fn fetch_ogp_metadata(client: &reqwest::Client, url: &str, _debug_mode: bool) -> Option<UrlPreview> {
     use scraper::{Html, Selector};
-    use std::time::Duration;
-    
-    let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(5))
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36")
-        .build()
-        .ok()?;
     
     let response = client.get(url).send().await.ok()?;
     
@@ -1735,14 +3794,47 @@ async fn fetch_ogp_metadata(url: &str, _debug_mode: bool) -> Option<UrlPreview>
     })
 }
 
+/// アカウントのサーバーにストリーミングで接続できるか、トークンがあれば自分の情報を取得して有効か確かめる
+///
+/// 成功時は設定画面に表示するメッセージを返す。
+async fn test_account(account: &Account) -> Result<String, String> {
+    let options = ConnectOptions {
+        secure: account.secure,
+        accept_invalid_certs: account.danger_accept_invalid_certs,
+    };
+    let mut client = StreamClient::connect(account, options)
+        .await
+        .map_err(|e| format!("接続できませんでした: {}", e))?;
+    if account.token.is_none() {
+        return Ok("接続できました（トークンなし）".to_string());
+    }
+    let me = client.whoami().await.map_err(|e| format!("トークンを確認できませんでした: {}", e))?;
+    Ok(format!("@{} として接続できました", me.username))
+}
+
+/// リノート・リアクション・リプライの数の表示（すべて0なら表示しない）
+fn count_badges(counts: &NoteCounts) -> Option<String> {
+    let badges: Vec<String> = [("🔁", counts.renotes), ("⭐", counts.reactions), ("💬", counts.replies)]
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(icon, count)| format!("{} {}", icon, count))
+        .collect();
+    (!badges.is_empty()).then(|| badges.join(" "))
+}
+
+/// 設定の描画バックエンドをeframeのものに変換する
+fn native_renderer(choice: RendererChoice) -> eframe::Renderer {
+    match choice {
+        RendererChoice::Glow => eframe::Renderer::Glow,
+        RendererChoice::Wgpu => eframe::Renderer::Wgpu,
+    }
+}
+
 fn run_settings_window() -> Result<(), Box<dyn std::error::Error>> {
     // 設定読み込み
-    let config = AppConfig::new().unwrap_or_else(|_| AppConfig {
-        accounts: vec![],
-        active_account_index: 0,
-        debug: false,
-        fallback_font: None,
-    });
+    let config = AppConfig::new().unwrap_or_default();
+    set_user_agent(config.user_agent.clone());
+    set_extra_root_cert(config.extra_root_cert.as_deref());
     
     let (reconnect_tx, _reconnect_rx) = tokio::sync::mpsc::unbounded_channel::<AppConfig>();
     
@@ -1752,7 +3844,7 @@ fn run_settings_window() -> Result<(), Box<dyn std::error::Error>> {
             .with_resizable(true)
             .with_decorations(true)
             .with_transparent(false),
-        renderer: eframe::Renderer::Glow,
+        renderer: native_renderer(config.renderer),
         ..Default::default()
     };
     
@@ -1793,20 +3885,170 @@ fn run_settings_window() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// `--ogp`: オーバーレイと同じ方法でURLのプレビューを取得し、各項目を表示する
+fn print_ogp_preview(url: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let runtime = Runtime::new()?;
+    let client = misskey_post_viewer::http::client();
+    let preview = runtime.block_on(fetch_ogp_metadata(&client, url, true))
+        .ok_or_else(|| format!("{} のプレビューを取得できませんでした", url))?;
+    let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "(なし)".to_string());
+    println!("url:         {}", preview.url);
+    println!("title:       {}", preview.title);
+    println!("description: {}", or_none(&preview.description));
+    println!("image_url:   {}", or_none(&preview.image_url));
+    println!("site_name:   {}", or_none(&preview.site_name));
+    println!("favicon_url: {}", or_none(&preview.favicon_url));
+    Ok(())
+}
+
+/// `--emojis`: サーバーの`/api/emojis`を取得し、絵文字の名前とURLをタブ区切りで名前順に表示する
+fn print_emoji_catalog(host: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let runtime = Runtime::new()?;
+    let url = format!("{}/api/emojis", misskey_post_viewer::http::base_url(host, true));
+    let body: serde_json::Value = runtime.block_on(async {
+        misskey_post_viewer::http::client()
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+    })?;
+    let mut emojis = parse_emoji_catalog(&body);
+    emojis.sort_by(|a, b| a.name.cmp(&b.name));
+    for emoji in &emojis {
+        println!("{}\t{}", emoji.name, emoji.url);
+    }
+    eprintln!("[EMOJI] {} emojis on {}", emojis.len(), host);
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // コマンドライン引数をチェック
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 && args[1] == "--settings" {
+    let mut settings_mode = false;
+    let mut read_stdin = false;
+    let mut ogp_url = None;
+    let mut emojis_host = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--settings" => settings_mode = true,
+            // 標準入力の各行（またはJSON）をコメントとして流す
+            "--stdin" => read_stdin = true,
+            // URLのプレビューを取得して表示するだけで終了する（プレビューの不具合の調査用）
+            "--ogp" => match args.next() {
+                Some(url) => ogp_url = Some(url),
+                None => eprintln!("[WARN] --ogp にはURLを指定してください"),
+            },
+            // サーバーのカスタム絵文字の一覧を表示するだけで終了する
+            "--emojis" => match args.next() {
+                Some(host) => emojis_host = Some(host),
+                None => eprintln!("[WARN] --emojis にはサーバーのホスト名を指定してください"),
+            },
+            "--config" => match args.next() {
+                Some(path) => set_config_path(std::path::PathBuf::from(path)),
+                None => eprintln!("[WARN] --config にはパスを指定してください"),
+            },
+            _ => eprintln!("[WARN] Unknown argument: {}", arg),
+        }
+    }
+    migrate_legacy_config();
+    println!("[CONFIG] Using {:?}", config_path());
+    if settings_mode {
         return run_settings_window();
     }
     
+    // 設定読み込み
+    let mut config = match AppConfig::new() {
+        Ok(c) => {
+            if let Some(account) = c.get_active_account() {
+                println!("Loaded configuration: {} ({})", account.name, account.host);
+            }
+            c
+        },
+        Err(e) => {
+            eprintln!("Failed to load configuration: {}", e);
+            eprintln!("Using default configuration (misskey.io)");
+            AppConfig {
+                accounts: vec![Account::new(
+                    "Default".to_string(),
+                    "misskey.io".to_string(),
+                    None,
+                    TimelineType::default(),
+                    true,
+                    [255, 255, 255],
+                )],
+                ..Default::default()
+            }
+        }
+    };
+    set_user_agent(config.user_agent.clone());
+    set_extra_root_cert(config.extra_root_cert.as_deref());
+    if let Some(url) = ogp_url {
+        return print_ogp_preview(&url);
+    }
+    if let Some(host) = emojis_host {
+        return print_emoji_catalog(&host);
+    }
+    // 仮名の塩はインストールごとに一度だけ作って保存する
+    if config.ensure_anonymize_salt() {
+        if let Err(e) = config.save() {
+            eprintln!("[CONFIG] Failed to save anonymize salt: {}", e);
+        }
+    }
+
     // トレイアイコンのメニュー作成
     let tray_menu = Menu::new();
     let settings_item = MenuItem::with_id("settings", "設定", true, None);
+    let visible_item = CheckMenuItem::with_id("visible", "表示 ON/OFF", true, true, None);
+    let stats_item = MenuItem::with_id("stats", "統計", true, None);
+    let export_item = MenuItem::with_id("export_csv", "流れたノートをCSVに保存", true, None);
+    let clear_item = MenuItem::with_id("clear", "コメントを消去", true, None);
+    // プロファイルの切り替え（起動時に`profiles`フォルダにあるものを並べる）
+    let profile_menu = Submenu::new("プロファイル", true);
+    let profiles = list_profiles();
+    let profile_items: Vec<MenuItem> = profiles
+        .iter()
+        .map(|name| MenuItem::with_id(format!("{}{}", PROFILE_MENU_PREFIX, name), name, true, None))
+        .collect();
+    for item in &profile_items {
+        profile_menu.append(item)?;
+    }
+    if profile_items.is_empty() {
+        profile_menu.append(&MenuItem::new("（プロファイルがありません）", false, None))?;
+    }
+    let profiles_dir_item = MenuItem::with_id("profiles_dir", "プロファイルのフォルダを開く", true, None);
+    // 一時的に1つのアカウントのコメントだけを流す（各アカウントの有効/無効は変えない）
+    let solo_menu = Submenu::new("このアカウントのみ表示", true);
+    let solo_items: Vec<MenuItem> = config.accounts
+        .iter()
+        .filter(|a| a.enabled)
+        .map(|a| MenuItem::with_id(format!("{}{}", SOLO_MENU_PREFIX, a.name), &a.name, true, None))
+        .collect();
+    for item in &solo_items {
+        solo_menu.append(item)?;
+    }
+    let solo_clear_item = MenuItem::with_id("solo_clear", "すべてのアカウントを表示", true, None);
+    solo_menu.append(&PredefinedMenuItem::separator())?;
+    solo_menu.append(&solo_clear_item)?;
+    profile_menu.append(&PredefinedMenuItem::separator())?;
+    profile_menu.append(&profiles_dir_item)?;
     let quit_item = MenuItem::with_id("quit", "終了", true, None);
     let settings_id = settings_item.id().clone();
+    let visible_id = visible_item.id().clone();
+    let stats_id = stats_item.id().clone();
+    let export_id = export_item.id().clone();
+    let clear_id = clear_item.id().clone();
+    let profiles_dir_id = profiles_dir_item.id().clone();
+    let solo_clear_id = solo_clear_item.id().clone();
     let quit_id = quit_item.id().clone();
     tray_menu.append(&settings_item)?;
+    tray_menu.append(&visible_item)?;
+    tray_menu.append(&stats_item)?;
+    tray_menu.append(&export_item)?;
+    tray_menu.append(&clear_item)?;
+    tray_menu.append(&profile_menu)?;
+    tray_menu.append(&solo_menu)?;
     tray_menu.append(&quit_item)?;
 
     // トレイイベント用のチャネルとフラグを作成
@@ -1814,6 +4056,37 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let tray_event_flag = Arc::new(Mutex::new(false));
     let tray_event_flag_clone = tray_event_flag.clone();
     
+    // グローバルホットキー（マネージャーを捨てると登録が外れるので終了まで持っておく）
+    let hotkey_manager = match GlobalHotKeyManager::new() {
+        Ok(manager) => Some(manager),
+        Err(e) => {
+            eprintln!("[HOTKEY] Failed to initialize hotkeys: {}", e);
+            None
+        }
+    };
+    let clear_hotkey_id = hotkey_manager.as_ref()
+        .zip(config.clear_hotkey.as_deref())
+        .and_then(|(manager, keys)| register_hotkey(manager, keys));
+    if clear_hotkey_id.is_some() {
+        let hotkey_tx = tray_tx.clone();
+        let hotkey_event_flag = tray_event_flag.clone();
+        std::thread::spawn(move || {
+            let receiver = GlobalHotKeyEvent::receiver();
+            while let Ok(event) = receiver.recv() {
+                if event.state() != HotKeyState::Pressed || Some(event.id()) != clear_hotkey_id {
+                    continue;
+                }
+                if hotkey_tx.send(TrayEvent::ClearComments).is_err() {
+                    break;
+                }
+                if let Ok(mut flag) = hotkey_event_flag.lock() {
+                    *flag = true;
+                }
+                trigger_window_update();
+            }
+        });
+    }
+    
     // 別スレッドでトレイアイコンのイベントを監視
     std::thread::spawn(move || {
         let menu_receiver = tray_icon::menu::MenuEvent::receiver();
@@ -1823,6 +4096,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 let tray_event = if event.id == settings_id {
                     println!("Sending Settings event...");
                     TrayEvent::Settings
+                } else if event.id == visible_id {
+                    TrayEvent::ToggleVisible
+                } else if event.id == stats_id {
+                    TrayEvent::Stats
+                } else if event.id == export_id {
+                    TrayEvent::ExportCsv
+                } else if event.id == clear_id {
+                    TrayEvent::ClearComments
+                } else if event.id == profiles_dir_id {
+                    TrayEvent::OpenProfilesDir
+                } else if let Some(name) = event.id.0.strip_prefix(PROFILE_MENU_PREFIX) {
+                    TrayEvent::SwitchProfile(name.to_string())
+                } else if event.id == solo_clear_id {
+                    TrayEvent::Solo(None)
+                } else if let Some(name) = event.id.0.strip_prefix(SOLO_MENU_PREFIX) {
+                    TrayEvent::Solo(Some(name.to_string()))
                 } else if event.id == quit_id {
                     println!("Sending Quit event...");
                     TrayEvent::Quit
@@ -1863,33 +4152,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .with_tooltip("Misskey Post Viewer")
         .with_icon(icon)
         .build()?;
-    
-    // 設定読み込み
-    let config = match AppConfig::new() {
-        Ok(c) => {
-            if let Some(account) = c.get_active_account() {
-                println!("Loaded configuration: {} ({})", account.name, account.host);
-            }
-            c
-        },
-        Err(e) => {
-            eprintln!("Failed to load configuration: {}", e);
-            eprintln!("Using default configuration (misskey.io)");
-            AppConfig {
-                accounts: vec![Account::new(
-                    "Default".to_string(),
-                    "misskey.io".to_string(),
-                    None,
-                    TimelineType::default(),
-                    true,
-                    [255, 255, 255],
-                )],
-                active_account_index: 0,
-                debug: false,
-                fallback_font: None,
-            }
-        }
-    };
 
     // ウィンドウアイコン用の画像を読み込み
     let window_icon = {
@@ -1924,7 +4186,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     height: 32,
                 }
             })),
-        renderer: eframe::Renderer::Glow,
+        renderer: native_renderer(config.renderer),
         ..Default::default()
     };
 
@@ -1932,7 +4194,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     eframe::run_native(
         "Misskey Post Viewer",
         options,
-        Box::new(move |cc| Ok(Box::new(MisskeyViewerApp::new(cc, config_clone, tray_rx, tray_event_flag)))),
+        Box::new(move |cc| Ok(Box::new(MisskeyViewerApp::new(cc, config_clone, tray_rx, tray_event_flag, read_stdin)))),
     )?;
 
     Ok(())
@@ -0,0 +1,338 @@
+use crate::config::TimelineType;
+use crate::emoji::{EmojiInfo, MAX_NOTE_EMOJIS};
+use crate::misskey::{ConnectOptions, MiUser};
+use crate::note::{classify, clean_text, error_reason, NoteCounts, ParseError, ParseOptions, ParsedNote, TimelineEvent};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{connect_async_tls_with_config, Connector, tungstenite::client::IntoClientRequest, tungstenite::http::HeaderValue, tungstenite::protocol::Message};
+use url::Url;
+
+/// Mastodonのストリーミング API (`/api/v1/streaming`) のクライアント
+///
+/// `MisskeyClient`と同じ使い方ができるようにしてあり、受信したイベントは
+/// `parse_event`でMisskeyと同じ`TimelineEvent`/`ParsedNote`に変換する。
+pub struct MastodonClient {
+    write: mpsc::UnboundedSender<Message>,
+    read: futures::stream::SplitStream<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>,
+    base_url: String, // APIなどのURLの起点（`https://{host}`）
+    token: Option<String>,
+    me: Option<MiUser>, // whoami()の結果をセッション中キャッシュ
+    http: reqwest::Client, // このインスタンスのAPI用
+}
+
+impl MastodonClient {
+    pub async fn connect_with(host: &str, token: Option<String>, options: ConnectOptions) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let protocol = if options.secure { "wss" } else { "ws" };
+        let mut url = Url::parse(&format!("{}://{}/api/v1/streaming", protocol, host))?;
+        if let Some(t) = &token {
+            url.query_pairs_mut().append_pair("access_token", t);
+        }
+
+        println!("Connecting to {}://{}/api/v1/streaming...", protocol, host);
+        let mut request = url.as_str().into_client_request()?;
+        request.headers_mut().insert("User-Agent", HeaderValue::from_str(crate::http::user_agent())?);
+        let connector = crate::http::tls_connector(options.accept_invalid_certs)?.map(Connector::NativeTls);
+
+        let (ws_stream, _) = tokio::time::timeout(
+            tokio::time::Duration::from_secs(2),
+            connect_async_tls_with_config(request, None, false, connector)
+        ).await??;
+        println!("Connected!");
+
+        let (mut write_stream, read_stream) = ws_stream.split();
+
+        // メッセージ送信用のチャネル（サーバーからのPingには読み込み側で応答するのでハートビートは不要）
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(msg) = rx.recv().await {
+                if write_stream.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(MastodonClient {
+            write: tx,
+            read: read_stream,
+            base_url: crate::http::base_url(host, options.secure),
+            token,
+            me: None,
+            http: crate::http::api_client(options.accept_invalid_certs),
+        })
+    }
+
+    /// ログイン中のユーザー情報を取得（`/api/v1/accounts/verify_credentials`、以降はキャッシュを返す）
+    pub async fn whoami(&mut self) -> Result<&MiUser, Box<dyn std::error::Error + Send + Sync>> {
+        match self.me {
+            Some(ref me) => Ok(me),
+            None => {
+                let token = self.token.as_ref().ok_or("トークンが設定されていません")?;
+                let response = self.http.get(format!("{}/api/v1/accounts/verify_credentials", self.base_url))
+                    .bearer_auth(token)
+                    .send()
+                    .await?;
+
+                if !response.status().is_success() {
+                    return Err(format!("Failed to fetch verify_credentials: HTTP {}", response.status()).into());
+                }
+
+                let account: Value = response.json().await?;
+                let get_str = |key: &str| account.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+                let user = MiUser {
+                    id: get_str("id").ok_or("id がありません")?,
+                    username: get_str("username").ok_or("username がありません")?,
+                    name: get_str("display_name").filter(|n| !n.is_empty()),
+                    host: None,
+                    avatar_url: get_str("avatar"),
+                };
+                Ok(self.me.insert(user))
+            }
+        }
+    }
+
+    /// ブロック・ミュートしているユーザーのIDを取得する（`/api/v1/blocks`と`/api/v1/mutes`）
+    pub async fn fetch_hidden_user_ids(&self) -> Result<HashSet<String>, Box<dyn std::error::Error + Send + Sync>> {
+        const MAX_PAGES: usize = 50;
+
+        let token = self.token.as_ref().ok_or("トークンが設定されていません")?;
+        let mut ids = HashSet::new();
+        for endpoint in ["blocks", "mutes"] {
+            let mut next = Some(format!("{}/api/v1/{}?limit=80", self.base_url, endpoint));
+            for _ in 0..MAX_PAGES {
+                let Some(url) = next.take() else { break };
+                let response = self.http.get(&url).bearer_auth(token).send().await?;
+                if !response.status().is_success() {
+                    return Err(format!("Failed to fetch /api/v1/{}: HTTP {}", endpoint, response.status()).into());
+                }
+                // 続きは`Link: <...>; rel="next"`で示される
+                next = response.headers()
+                    .get(reqwest::header::LINK)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(next_page_url);
+                let accounts: Vec<Value> = response.json().await?;
+                ids.extend(accounts.iter().filter_map(|a| a.get("id")?.as_str().map(|s| s.to_string())));
+            }
+        }
+        Ok(ids)
+    }
+
+    /// タイムラインに対応するストリームを購読する
+    pub fn subscribe(&self, timeline: &TimelineType) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let stream = stream_name(timeline);
+        let msg = json!({ "type": "subscribe", "stream": stream });
+        self.write.send(Message::Text(msg.to_string()))?;
+        println!("Subscribed to {}", stream);
+        Ok(())
+    }
+
+    /// このインスタンスのAPIを呼ぶためのHTTPクライアント（接続時のTLS設定を引き継ぐ）
+    pub fn api_client(&self) -> reqwest::Client {
+        self.http.clone()
+    }
+
+    pub async fn next_message(&mut self) -> Option<Result<Message, tokio_tungstenite::tungstenite::Error>> {
+        self.read.next().await
+    }
+
+    pub fn close(self) {
+        drop(self.write);
+        println!("[CLOSE] WebSocket connection closed");
+    }
+}
+
+/// タイムラインの種類に対応するストリーム名
+///
+/// Mastodonにはソーシャル（ハイブリッド）タイムラインがないため、ホームで代用する。
+pub fn stream_name(timeline: &TimelineType) -> &'static str {
+    match timeline {
+        TimelineType::Hybrid | TimelineType::Home => "user",
+        TimelineType::Local => "public:local",
+        TimelineType::Global => "public",
+    }
+}
+
+// `Link`ヘッダーから`rel="next"`のURLを取り出す
+fn next_page_url(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params.contains("rel=\"next\"").then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// ストリーミングのメッセージを`TimelineEvent`として解析する
+///
+/// `update`は新しい投稿、`delete`は削除、`status.update`は編集として扱う。
+/// `payload`は投稿のJSONを文字列にしたもの（`delete`では投稿ID）。
+pub fn parse_event(text: &str, options: &ParseOptions) -> Result<TimelineEvent, ParseError> {
+    let parsed: Value = serde_json::from_str(text)
+        .map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+    // 購読できなかったときは`{"error": "..."}`だけが届く
+    if parsed.get("error").is_some() {
+        return Ok(TimelineEvent::ChannelError { reason: error_reason(parsed.get("error")) });
+    }
+    let event = parsed.get("event").and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
+    let payload = || {
+        parsed.get("payload")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ParseError::MissingField(event.clone(), "payload"))
+    };
+    let status = || -> Result<Value, ParseError> {
+        serde_json::from_str(payload()?).map_err(|e| ParseError::InvalidJson(e.to_string()))
+    };
+
+    match event.as_str() {
+        "update" => parse_status(&status()?, options).map(|note| TimelineEvent::Note(Box::new(note))),
+        "delete" => Ok(TimelineEvent::Deleted { note_id: payload()?.to_string() }),
+        "status.update" => {
+            let note = parse_status(&status()?, options)?;
+            Ok(TimelineEvent::Updated { note_id: note.id, text: note.text, cw: note.cw })
+        }
+        _ => Ok(TimelineEvent::Other { event_type: event.clone() }),
+    }
+}
+
+/// Mastodonの投稿 (Status) を`ParsedNote`に変換する
+pub fn parse_status(status: &Value, options: &ParseOptions) -> Result<ParsedNote, ParseError> {
+    if !status.is_object() {
+        return Err(ParseError::MissingField("status".to_string(), "payload"));
+    }
+
+    let account = status.get("account");
+    let get_str = |v: Option<&Value>, key: &str| {
+        v.and_then(|v| v.get(key)).and_then(|v| v.as_str()).map(|s| s.to_string())
+    };
+
+    // ブースト（リノート）と引用（Mastodon 4.5以降は`quote.quoted_status`）
+    let reblog = status.get("reblog").filter(|r| r.is_object());
+    let quote = status.get("quote")
+        .map(|q| q.get("quoted_status").unwrap_or(q))
+        .filter(|q| q.get("id").is_some());
+    let renote = match reblog.or(quote) {
+        Some(original) => Some(Box::new(parse_status(original, options)?)),
+        None => None,
+    };
+
+    // 絵文字は投稿本体と投稿者の表示名の両方に付く
+    let mut emojis: Vec<EmojiInfo> = Vec::new();
+    let emoji_lists = [status.get("emojis"), account.and_then(|a| a.get("emojis"))];
+    for emoji in emoji_lists.into_iter().flatten().filter_map(|v| v.as_array()).flatten() {
+        if emojis.len() >= MAX_NOTE_EMOJIS {
+            break;
+        }
+        let (Some(name), Some(url)) = (get_str(Some(emoji), "shortcode"), get_str(Some(emoji), "url")) else { continue };
+        if !emojis.iter().any(|e| e.name == name) {
+            emojis.push(EmojiInfo { name, url });
+        }
+    }
+
+    // acctはローカルユーザーなら`username`、リモートなら`username@host`
+    let username = get_str(account, "username").unwrap_or_else(|| "Unknown".to_string());
+    let user_host = get_str(account, "acct").and_then(|acct| acct.split_once('@').map(|(_, host)| host.to_string()));
+    // ブーストでは本文が空で届くので、元の投稿の本文だけを使う
+    let text = get_str(Some(status), "content")
+        .map(|html| html_to_text(&html))
+        .filter(|t| !t.is_empty() && reblog.is_none())
+        .map(|t| clean_text(&t, options));
+    let cw = get_str(Some(status), "spoiler_text")
+        .filter(|c| !c.is_empty())
+        .map(|c| clean_text(&c, options));
+    let has_poll = status.get("poll").is_some_and(|p| p.is_object());
+    let has_files = status.get("media_attachments").and_then(|v| v.as_array()).is_some_and(|files| !files.is_empty());
+    let get_u64 = |key: &str| status.get(key).and_then(|v| v.as_u64()).unwrap_or(0);
+
+    let mut parsed = ParsedNote {
+        id: get_str(Some(status), "id").unwrap_or_default(),
+        created_at: get_str(Some(status), "created_at"),
+        user_id: get_str(account, "id"),
+        name: get_str(account, "display_name").filter(|n| !n.is_empty()).unwrap_or_else(|| username.clone()),
+        username,
+        user_host,
+        avatar_url: get_str(account, "avatar"),
+        text,
+        cw,
+        emojis,
+        mentions: status.get("mentions")
+            .and_then(|v| v.as_array())
+            .map(|mentions| mentions.iter().filter_map(|m| get_str(Some(m), "id")).collect())
+            .unwrap_or_default(),
+        renote,
+        reply_id: get_str(Some(status), "in_reply_to_id"),
+        reply: None,
+        note_types: Vec::new(),
+        counts: NoteCounts {
+            reactions: get_u64("favourites_count"),
+            renotes: get_u64("reblogs_count"),
+            replies: get_u64("replies_count"),
+        },
+    };
+    parsed.note_types = classify(&parsed, has_poll, has_files);
+    Ok(parsed)
+}
+
+/// 投稿本文のHTMLをプレーンテキストにする
+///
+/// `<br>`と段落の区切りを改行にし、それ以外のタグは取り除いて文字参照を戻す。
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + end].trim().to_ascii_lowercase();
+        let name = tag.split(|c: char| c.is_whitespace() || c == '/').find(|s| !s.is_empty()).unwrap_or("");
+        match name {
+            "br" => text.push('\n'),
+            "p" if tag.starts_with('/') => text.push_str("\n\n"),
+            _ => {}
+        }
+        rest = &rest[start + end + 1..];
+    }
+    text.push_str(rest);
+    decode_entities(text.trim_end())
+}
+
+// `&amp;`などの文字参照を戻す（タグを取り除いた後に行うので`&lt;`がタグとして解釈されることはない）
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let decoded = after.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &after[..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                        None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                    };
+                    code.and_then(char::from_u32)
+                }
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,281 @@
+/// MFM (Misskey Flavored Markdown) の装飾を取り除いてプレーンテキストにする
+///
+/// `$[shake ...]` などの関数、`**太字**`・`~~打消し~~`・`<center>` などの装飾、
+/// リンク `[ラベル](URL)`、行頭の引用 `> ` を取り除き、中身の文字だけを残す。
+/// `<plain>`とインラインコードの中身はエスケープとしてそのまま残す。
+pub fn strip_mfm(text: &str) -> String {
+    let stripped: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            // 行頭の引用
+            match line.strip_prefix('>') {
+                Some(quoted) => quoted.strip_prefix(' ').unwrap_or(quoted).to_string(),
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    let chars: Vec<char> = stripped.join("\n").chars().collect();
+    strip_chars(&chars)
+}
+
+// 装飾だけのタグ（中身は残す）
+const STYLE_TAGS: &[&str] = &["b", "i", "s", "small", "center"];
+
+// 対になる装飾記号（長いものから順に判定）
+const STYLE_MARKERS: &[&str] = &["***", "**", "__", "~~"];
+
+fn strip_chars(chars: &[char]) -> String {
+    let mut out = String::with_capacity(chars.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        // <plain>...</plain> は中身をそのまま
+        if starts_with(chars, i, "<plain>") {
+            if let Some(end) = find(chars, i + 7, "</plain>") {
+                out.extend(&chars[i + 7..end]);
+                i = end + 8;
+                continue;
+            }
+        }
+
+        // コードブロック
+        if starts_with(chars, i, "```") {
+            if let Some(end) = find(chars, i + 3, "```") {
+                let inner: String = chars[i + 3..end].iter().collect();
+                // 先頭行の言語指定は捨てる
+                let code = match inner.split_once('\n') {
+                    Some((_lang, code)) => code,
+                    None => inner.as_str(),
+                };
+                out.push_str(code.trim_end_matches('\n'));
+                i = end + 3;
+                continue;
+            }
+        }
+
+        // インラインコード
+        if chars[i] == '`' {
+            if let Some(len) = chars[i + 1..].iter().position(|&c| c == '`' || c == '\n') {
+                if chars[i + 1 + len] == '`' {
+                    out.extend(&chars[i + 1..i + 1 + len]);
+                    i += len + 2;
+                    continue;
+                }
+            }
+        }
+
+        // $[name.args 中身]
+        if starts_with(chars, i, "$[") {
+            if let Some((content_start, end)) = find_mfm_function(chars, i) {
+                out.push_str(&strip_chars(&chars[content_start..end]));
+                i = end + 1;
+                continue;
+            }
+        }
+
+        // <b> <i> <small> <center> など
+        if chars[i] == '<' {
+            if let Some(tag_len) = style_tag_len(chars, i) {
+                i += tag_len;
+                continue;
+            }
+        }
+
+        // **太字** ~~打消し~~ など
+        if let Some(marker) = STYLE_MARKERS.iter().find(|m| starts_with(chars, i, m)) {
+            let marker_len = marker.chars().count();
+            if let Some(end) = find(chars, i + marker_len, marker) {
+                if end > i + marker_len {
+                    out.push_str(&strip_chars(&chars[i + marker_len..end]));
+                    i = end + marker_len;
+                    continue;
+                }
+            }
+        }
+
+        // 数式 \( ... \)
+        if starts_with(chars, i, "\\(") {
+            if let Some(end) = find(chars, i + 2, "\\)") {
+                out.extend(&chars[i + 2..end]);
+                i = end + 2;
+                continue;
+            }
+        }
+
+        // リンク [ラベル](URL) / ?[ラベル](URL)
+        let link_start = if chars[i] == '?' && chars.get(i + 1) == Some(&'[') { i + 1 } else { i };
+        if chars[link_start] == '[' {
+            if let Some((label_end, url_end)) = find_link(chars, link_start) {
+                out.push_str(&strip_chars(&chars[link_start + 1..label_end]));
+                i = url_end + 1;
+                continue;
+            }
+        }
+
+        out.push(chars[i]);
+        i += 1;
+    }
+
+    out
+}
+
+fn starts_with(chars: &[char], at: usize, pattern: &str) -> bool {
+    pattern
+        .chars()
+        .enumerate()
+        .all(|(offset, p)| chars.get(at + offset) == Some(&p))
+}
+
+fn find(chars: &[char], from: usize, pattern: &str) -> Option<usize> {
+    (from..chars.len()).find(|&idx| starts_with(chars, idx, pattern))
+}
+
+/// `$[` の位置から関数を読み、(中身の開始位置, 閉じ`]`の位置) を返す
+///
+/// 中身のない `$[name]` や閉じていないものは `None`。
+pub(crate) fn find_mfm_function(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let name_start = start + 2;
+    let name_len = chars[name_start..]
+        .iter()
+        .position(|&c| c == ' ' || c == ']' || c == '\n')?;
+    if name_len == 0 || chars[name_start + name_len] != ' ' {
+        return None;
+    }
+
+    let content_start = name_start + name_len + 1;
+    let mut depth = 0;
+    for (idx, &c) in chars.iter().enumerate().skip(content_start) {
+        match c {
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some((content_start, idx)),
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// `[ラベル](URL)` の (ラベル終わりの`]`の位置, URL終わりの`)`の位置) を返す
+fn find_link(chars: &[char], start: usize) -> Option<(usize, usize)> {
+    let label_end = start + 1 + chars[start + 1..].iter().position(|&c| c == ']' || c == '\n')?;
+    if chars[label_end] != ']' || chars.get(label_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = label_end + 2 + chars[label_end + 2..].iter().position(|&c| c == ')' || c.is_whitespace())?;
+    if chars[url_end] != ')' {
+        return None;
+    }
+    Some((label_end, url_end))
+}
+
+/// `<b>` `</center>` などの装飾タグならその長さを返す
+fn style_tag_len(chars: &[char], start: usize) -> Option<usize> {
+    let closing = chars.get(start + 1) == Some(&'/');
+    let name_start = if closing { start + 2 } else { start + 1 };
+    STYLE_TAGS.iter().find_map(|tag| {
+        let tag_len = tag.chars().count();
+        if starts_with(chars, name_start, tag) && chars.get(name_start + tag_len) == Some(&'>') {
+            Some(name_start + tag_len + 1 - start)
+        } else {
+            None
+        }
+    })
+}
+
+/// 描画時に再現するMFMのアニメーション効果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MfmEffect {
+    Shake, // 位置が小刻みに揺れる
+    Spin,  // 回転する
+    Jelly, // 横に伸び縮みする
+    Tada,  // 拡大縮小を繰り返す
+}
+
+impl MfmEffect {
+    fn from_function_name(name: &str) -> Option<Self> {
+        // `spin.speed=1s` のような引数は無視
+        match name.split('.').next().unwrap_or("") {
+            "shake" | "twitch" => Some(MfmEffect::Shake),
+            "spin" => Some(MfmEffect::Spin),
+            "jelly" | "rubberband" => Some(MfmEffect::Jelly),
+            "tada" | "bounce" => Some(MfmEffect::Tada),
+            _ => None,
+        }
+    }
+}
+
+/// 同じ効果がかかる連続したテキスト
+#[derive(Debug, Clone, PartialEq)]
+pub struct MfmSpan {
+    pub text: String,
+    pub effects: Vec<MfmEffect>,
+}
+
+/// MFMを効果付きのテキスト断片に分解する
+///
+/// 対応している効果（`MfmEffect`）以外の関数や装飾は`strip_mfm`と同様に取り除く。
+pub fn parse_mfm_spans(text: &str) -> Vec<MfmSpan> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans = Vec::new();
+    collect_spans(&chars, &mut Vec::new(), &mut spans);
+    spans
+}
+
+fn collect_spans(chars: &[char], effects: &mut Vec<MfmEffect>, spans: &mut Vec<MfmSpan>) {
+    let mut plain: Vec<char> = Vec::new();
+    let mut i = 0;
+
+    let flush = |plain: &mut Vec<char>, effects: &[MfmEffect], spans: &mut Vec<MfmSpan>| {
+        if plain.is_empty() {
+            return;
+        }
+        let text = strip_mfm(&plain.iter().collect::<String>());
+        plain.clear();
+        if text.is_empty() {
+            return;
+        }
+        // 直前と同じ効果ならまとめる
+        match spans.last_mut() {
+            Some(last) if last.effects == effects => last.text.push_str(&text),
+            _ => spans.push(MfmSpan { text, effects: effects.to_vec() }),
+        }
+    };
+
+    while i < chars.len() {
+        // エスケープ部分は中の`$[`を解釈しないようにそのまま渡す
+        let escape_end = if starts_with(chars, i, "<plain>") {
+            find(chars, i + 7, "</plain>").map(|end| end + 8)
+        } else if chars[i] == '`' {
+            chars[i + 1..].iter().position(|&c| c == '`').map(|len| i + len + 2)
+        } else {
+            None
+        };
+        if let Some(end) = escape_end {
+            plain.extend(&chars[i..end]);
+            i = end;
+            continue;
+        }
+
+        if starts_with(chars, i, "$[") {
+            if let Some((content_start, end)) = find_mfm_function(chars, i) {
+                flush(&mut plain, effects, spans);
+                let name: String = chars[i + 2..content_start - 1].iter().collect();
+                let effect = MfmEffect::from_function_name(&name);
+                if let Some(effect) = effect {
+                    effects.push(effect);
+                }
+                collect_spans(&chars[content_start..end], effects, spans);
+                if effect.is_some() {
+                    effects.pop();
+                }
+                i = end + 1;
+                continue;
+            }
+        }
+
+        plain.push(chars[i]);
+        i += 1;
+    }
+
+    flush(&mut plain, effects, spans);
+}
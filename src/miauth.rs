@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 pub struct MiAuthSession {
     pub session_id: String,
     pub url: String,
-    pub host: String,
+    pub base_url: String, // サーバーのURLの起点（`https://{host}`）
 }
 
 #[derive(Debug, Deserialize)]
@@ -15,7 +15,9 @@ pub struct MiAuthCheckResponse {
 
 impl MiAuthSession {
     /// 新しいMiAuthセッションを作成
-    pub fn new(host: &str, app_name: &str, description: Option<&str>, permissions: &[&str]) -> Self {
+    ///
+    /// `base_url`は`https://misskey.io`のようなサーバーのURL（`http::base_url`で作る）。
+    pub fn new(base_url: &str, app_name: &str, description: Option<&str>, permissions: &[&str]) -> Self {
         use rand::Rng;
         
         // セッションIDを生成（ランダムな16文字の英数字）
@@ -30,8 +32,8 @@ impl MiAuthSession {
         
         // MiAuth URLを構築
         let mut url = format!(
-            "https://{}/miauth/{}?name={}",
-            host,
+            "{}/miauth/{}?name={}",
+            base_url,
             session_id,
             urlencoding::encode(app_name)
         );
@@ -47,19 +49,19 @@ impl MiAuthSession {
         Self {
             session_id,
             url,
-            host: host.to_string(),
+            base_url: base_url.to_string(),
         }
     }
     
     /// 認証が完了したかチェックし、トークンとユーザー情報を取得
     pub async fn check(&self) -> Result<(String, Option<String>), Box<dyn std::error::Error>> {
         let check_url = format!(
-            "https://{}/api/miauth/{}/check",
-            self.host,
+            "{}/api/miauth/{}/check",
+            self.base_url,
             self.session_id
         );
         
-        let client = reqwest::Client::new();
+        let client = crate::http::client();
         let response = client.post(&check_url)
             .header("Content-Type", "application/json")
             .body("{}")
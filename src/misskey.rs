@@ -1,17 +1,59 @@
-use futures::{SinkExt, StreamExt};
+use crate::note::{parse_event, ParseOptions, TimelineEvent};
+use futures::{SinkExt, Stream, StreamExt};
+use serde::Deserialize;
 use serde_json::json;
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
 use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::{connect_async_tls_with_config, Connector, tungstenite::client::IntoClientRequest, tungstenite::http::HeaderValue, tungstenite::protocol::Message};
 use url::Url;
 
+/// ログイン中のアカウント自身のユーザー情報 (`/api/i`)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MiUser {
+    pub id: String,
+    pub username: String,
+    pub name: Option<String>,
+    pub host: Option<String>,
+    #[serde(rename = "avatarUrl")]
+    pub avatar_url: Option<String>,
+}
+
+/// 接続時のオプション
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectOptions {
+    /// `wss://`・`https://`で接続する（`false`ならローカル開発用に`ws://`・`http://`）
+    pub secure: bool,
+    /// サーバー証明書を検証しない（自己署名証明書のローカル開発用。通信内容は保護されない）
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for ConnectOptions {
+    fn default() -> Self {
+        Self {
+            secure: true,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
 pub struct MisskeyClient {
     write: mpsc::UnboundedSender<Message>,
     read: futures::stream::SplitStream<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>,
+    base_url: String, // APIなどのURLの起点（`https://{host}`）
+    token: Option<String>,
+    me: Option<MiUser>, // whoami()の結果をセッション中キャッシュ
+    http: reqwest::Client, // このインスタンスのAPI用
 }
 
 impl MisskeyClient {
     pub async fn connect(host: &str, token: Option<String>) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        let protocol = "wss";
+        Self::connect_with(host, token, ConnectOptions::default()).await
+    }
+    
+    pub async fn connect_with(host: &str, token: Option<String>, options: ConnectOptions) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let protocol = if options.secure { "wss" } else { "ws" };
         let mut url_str = format!("{}://{}/streaming", protocol, host);
         
         if let Some(t) = &token {
@@ -20,11 +62,14 @@ impl MisskeyClient {
 
         let url = Url::parse(&url_str)?;
         println!("Connecting to {}...", url);
+        let mut request = url.as_str().into_client_request()?;
+        request.headers_mut().insert("User-Agent", HeaderValue::from_str(crate::http::user_agent())?);
+        let connector = crate::http::tls_connector(options.accept_invalid_certs)?.map(Connector::NativeTls);
 
         // タイムアウトを2秒に短縮
         let (ws_stream, _) = tokio::time::timeout(
             tokio::time::Duration::from_secs(2),
-            connect_async(url)
+            connect_async_tls_with_config(request, None, false, connector)
         ).await??;
         println!("Connected!");
 
@@ -58,9 +103,75 @@ impl MisskeyClient {
         Ok(MisskeyClient {
             write: tx,
             read: read_stream,
+            base_url: crate::http::base_url(host, options.secure),
+            token,
+            me: None,
+            http: crate::http::api_client(options.accept_invalid_certs),
         })
     }
 
+    /// ログイン中のユーザー情報を取得（初回のみ`/api/i`を呼び、以降はキャッシュを返す）
+    pub async fn whoami(&mut self) -> Result<&MiUser, Box<dyn std::error::Error + Send + Sync>> {
+        match self.me {
+            Some(ref me) => Ok(me),
+            None => {
+                let token = self.token.as_ref().ok_or("トークンが設定されていません")?;
+                let response = self.http.post(format!("{}/api/i", self.base_url))
+                    .json(&json!({ "i": token }))
+                    .send()
+                    .await?;
+            
+                if !response.status().is_success() {
+                    return Err(format!("Failed to fetch /api/i: HTTP {}", response.status()).into());
+                }
+            
+                let user = response.json::<MiUser>().await?;
+                Ok(self.me.insert(user))
+            }
+        }
+    }
+
+    /// サーバー側でブロック・ミュートしているユーザーのIDを取得する
+    /// （`/api/blocking/list`と`/api/mute/list`、トークンが必要）
+    pub async fn fetch_hidden_user_ids(&self) -> Result<HashSet<String>, Box<dyn std::error::Error + Send + Sync>> {
+        let mut ids = HashSet::new();
+        for (endpoint, id_key) in [("blocking/list", "blockeeId"), ("mute/list", "muteeId")] {
+            ids.extend(self.fetch_user_id_list(endpoint, id_key).await?);
+        }
+        Ok(ids)
+    }
+    
+    /// 一覧APIをページをたどって最後まで読み、各項目の`id_key`を集める
+    async fn fetch_user_id_list(&self, endpoint: &str, id_key: &str) -> Result<Vec<String>, Box<dyn std::error::Error + Send + Sync>> {
+        const PAGE_SIZE: usize = 100;
+        const MAX_PAGES: usize = 50;
+        
+        let token = self.token.as_ref().ok_or("トークンが設定されていません")?;
+        let mut ids = Vec::new();
+        let mut until_id: Option<String> = None;
+        for _ in 0..MAX_PAGES {
+            let mut body = json!({ "i": token, "limit": PAGE_SIZE });
+            if let Some(until_id) = &until_id {
+                body["untilId"] = json!(until_id);
+            }
+            let response = self.http.post(format!("{}/api/{}", self.base_url, endpoint))
+                .json(&body)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                return Err(format!("Failed to fetch /api/{}: HTTP {}", endpoint, response.status()).into());
+            }
+            
+            let items: Vec<serde_json::Value> = response.json().await?;
+            ids.extend(items.iter().filter_map(|item| item.get(id_key)?.as_str().map(|s| s.to_string())));
+            until_id = items.last().and_then(|item| item.get("id")?.as_str().map(|s| s.to_string()));
+            if items.len() < PAGE_SIZE || until_id.is_none() {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
     pub fn subscribe(&self, channel: &str, id: &str, params: serde_json::Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         let connect_msg = json!({
             "type": "connect",
@@ -76,10 +187,43 @@ impl MisskeyClient {
         Ok(())
     }
 
+    /// ノートをキャプチャして、リアクションや削除などの`noteUpdated`イベントを受け取る
+    pub fn sub_note(&self, note_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let msg = json!({
+            "type": "subNote",
+            "body": { "id": note_id }
+        });
+        self.write.send(Message::Text(msg.to_string()))?;
+        Ok(())
+    }
+
+    /// `sub_note`でキャプチャしたノートのイベントを受け取るのをやめる
+    pub fn unsub_note(&self, note_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let msg = json!({
+            "type": "unsubNote",
+            "body": { "id": note_id }
+        });
+        self.write.send(Message::Text(msg.to_string()))?;
+        Ok(())
+    }
+
+    /// このインスタンスのAPIを呼ぶためのHTTPクライアント（接続時のTLS設定を引き継ぐ）
+    pub fn api_client(&self) -> reqwest::Client {
+        self.http.clone()
+    }
+
     pub async fn next_message(&mut self) -> Option<Result<Message, tokio_tungstenite::tungstenite::Error>> {
         self.read.next().await
     }
     
+    /// 受信メッセージを解析済みの`TimelineEvent`として読むストリーム
+    ///
+    /// `while let Some(event) = client.events(options).next().await` のように使う。
+    /// テキスト以外のフレーム (Ping/Pongなど) は読み飛ばし、Closeで終了する。
+    pub fn events(&mut self, options: ParseOptions) -> EventStream<'_> {
+        EventStream { client: self, options }
+    }
+    
     pub fn close(self) {
         // MisskeyClientをドロップすることで、writeチャネルが閉じられ、
         // 書き込みタスクとハートビートタスクが自動的に終了する
@@ -87,3 +231,28 @@ impl MisskeyClient {
         println!("[CLOSE] WebSocket connection closed");
     }
 }
+
+/// `MisskeyClient::events`が返すストリーム
+pub struct EventStream<'a> {
+    client: &'a mut MisskeyClient,
+    options: ParseOptions,
+}
+
+impl Stream for EventStream<'_> {
+    type Item = Result<TimelineEvent, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            return match this.client.read.poll_next_unpin(cx) {
+                Poll::Pending => Poll::Pending,
+                Poll::Ready(None) | Poll::Ready(Some(Ok(Message::Close(_)))) => Poll::Ready(None),
+                Poll::Ready(Some(Err(e))) => Poll::Ready(Some(Err(e.into()))),
+                Poll::Ready(Some(Ok(Message::Text(text)))) => {
+                    Poll::Ready(Some(parse_event(&text, &this.options).map_err(|e| e.into())))
+                }
+                Poll::Ready(Some(Ok(_))) => continue,
+            };
+        }
+    }
+}
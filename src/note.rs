@@ -0,0 +1,409 @@
+use crate::emoji::{EmojiInfo, MAX_NOTE_EMOJIS, parse_emoji_list};
+use crate::mfm::strip_mfm;
+use crate::segment::strip_emoji_shortcodes;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// ノートの本文・CWを読み込む最大の文字数（これを超える分は捨てる）
+pub const MAX_NOTE_TEXT_CHARS: usize = 3000;
+
+/// 引用の引用やリプライ先のリプライ先…をたどって読み込む最大の深さ（これより奥のリノート元・リプライ先は捨てる）
+pub const MAX_RENOTE_DEPTH: usize = 5;
+
+/// ストリーミングで受信したノートのうち、表示に必要な部分だけを取り出したもの
+#[derive(Debug, Clone, Default)]
+pub struct ParsedNote {
+    pub id: String,
+    pub created_at: Option<String>, // 作成日時（ISO 8601）
+    pub user_id: Option<String>, // 投稿者のユーザーID
+    pub name: String,
+    pub username: String,
+    pub user_host: Option<String>,
+    pub avatar_url: Option<String>,
+    pub text: Option<String>,
+    pub cw: Option<String>,
+    pub emojis: Vec<EmojiInfo>, // ノートに同梱されていた絵文字
+    pub mentions: Vec<String>, // メンションされたユーザーID
+    pub renote: Option<Box<ParsedNote>>,
+    pub reply_id: Option<String>, // リプライ先のノートID
+    pub reply: Option<Box<ParsedNote>>, // リプライ先（含まれている場合のみ）
+    pub note_types: Vec<NoteType>, // 非表示の判定に使う分類（複数当てはまることがある）
+    pub counts: NoteCounts,
+}
+
+/// ノートへのリアクション・リノート・リプライの数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NoteCounts {
+    pub reactions: u64,
+    pub renotes: u64,
+    pub replies: u64,
+}
+
+impl NoteCounts {
+    fn parse(note: &Value) -> Self {
+        let get_u64 = |key: &str| note.get(key).and_then(|v| v.as_u64());
+        // `reactionCount`がないサーバーではリアクションごとの数を合計する
+        let reactions = get_u64("reactionCount").unwrap_or_else(|| {
+            note.get("reactions")
+                .and_then(|v| v.as_object())
+                .map(|reactions| reactions.values().filter_map(|v| v.as_u64()).sum())
+                .unwrap_or(0)
+        });
+        NoteCounts {
+            reactions,
+            renotes: get_u64("renoteCount").unwrap_or(0),
+            replies: get_u64("repliesCount").unwrap_or(0),
+        }
+    }
+}
+
+/// 表示しないように選べるノートの種類
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum NoteType {
+    #[serde(rename = "reply")]
+    Reply, // リプライ
+    #[serde(rename = "renote")]
+    Renote, // コメントなしのリノート
+    #[serde(rename = "quote")]
+    Quote, // コメント付きのリノート
+    #[serde(rename = "poll")]
+    Poll, // アンケート付き
+    #[serde(rename = "media_only")]
+    MediaOnly, // 本文がなく添付ファイルだけ
+    #[serde(rename = "emoji_only")]
+    EmojiOnly, // 本文が絵文字だけ
+}
+
+impl NoteType {
+    pub const ALL: [NoteType; 6] = [
+        NoteType::Reply,
+        NoteType::Renote,
+        NoteType::Quote,
+        NoteType::Poll,
+        NoteType::MediaOnly,
+        NoteType::EmojiOnly,
+    ];
+    
+    pub fn to_config_str(&self) -> &str {
+        match self {
+            NoteType::Reply => "reply",
+            NoteType::Renote => "renote",
+            NoteType::Quote => "quote",
+            NoteType::Poll => "poll",
+            NoteType::MediaOnly => "media_only",
+            NoteType::EmojiOnly => "emoji_only",
+        }
+    }
+    
+    pub fn display_name(&self) -> &str {
+        match self {
+            NoteType::Reply => "リプライ",
+            NoteType::Renote => "リノート",
+            NoteType::Quote => "引用",
+            NoteType::Poll => "アンケート",
+            NoteType::MediaOnly => "画像・ファイルのみ",
+            NoteType::EmojiOnly => "絵文字のみ",
+        }
+    }
+}
+
+impl ParsedNote {
+    /// 本文もCWもないリノート（いわゆる純粋なリノート）か
+    pub fn is_pure_renote(&self) -> bool {
+        self.renote.is_some() && !self.has_own_text()
+    }
+    
+    /// 自分のコメント付きのリノート（引用）か
+    pub fn is_quote(&self) -> bool {
+        self.renote.is_some() && self.has_own_text()
+    }
+    
+    fn has_own_text(&self) -> bool {
+        let non_empty = |s: &Option<String>| s.as_deref().is_some_and(|s| !s.trim().is_empty());
+        non_empty(&self.text) || non_empty(&self.cw)
+    }
+}
+
+/// ノート解析時のオプション
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// 本文とCWの空白を正規化する（`normalize_whitespace`を参照）
+    pub normalize_whitespace: bool,
+    /// 本文とCWからMFMの装飾を取り除く（`mfm::strip_mfm`を参照）
+    pub strip_mfm: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            normalize_whitespace: true,
+            strip_mfm: true,
+        }
+    }
+}
+
+/// メッセージをノートとして解釈できなかった理由
+#[derive(Debug)]
+pub enum ParseError {
+    /// JSONとして不正
+    InvalidJson(String),
+    /// 必要なフィールドがない (message_type, field)
+    MissingField(String, &'static str),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidJson(e) => write!(f, "invalid JSON: {}", e),
+            ParseError::MissingField(message_type, field) => {
+                write!(f, "missing `{}` in message of type `{}`", field, message_type)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// ストリーミングで受信したイベント
+#[derive(Debug, Clone)]
+pub enum TimelineEvent {
+    /// タイムラインに流れてきたノート
+    Note(Box<ParsedNote>),
+    /// キャプチャ中のノートへのリアクション
+    Reacted {
+        note_id: String,
+        reaction: String,
+        user_id: Option<String>,
+    },
+    /// キャプチャ中のノートが削除された
+    Deleted { note_id: String },
+    /// キャプチャ中のノートが編集された（フォークのみ）
+    Updated {
+        note_id: String,
+        text: Option<String>,
+        cw: Option<String>,
+    },
+    /// サーバーがチャンネルへの接続などを拒否した（待っていてもノートは届かない）
+    ChannelError { reason: String },
+    /// 上記以外のイベント (message_type/event_type)
+    Other { event_type: String },
+}
+
+/// WebSocketのテキストメッセージをイベントとして解析する
+///
+/// 形が想定外なら`Err`を返す。知らないイベントは`TimelineEvent::Other`。
+pub fn parse_event(text: &str, options: &ParseOptions) -> Result<TimelineEvent, ParseError> {
+    let parsed: Value = serde_json::from_str(text)
+        .map_err(|e| ParseError::InvalidJson(e.to_string()))?;
+    let message_type = parsed.get("type").and_then(|v| v.as_str()).unwrap_or("unknown").to_string();
+    // `channelError`などはbodyの形が決まっていないので先に見る
+    if matches!(message_type.as_str(), "channelError" | "error") {
+        return Ok(TimelineEvent::ChannelError { reason: error_reason(parsed.get("body")) });
+    }
+
+    let body = parsed.get("body")
+        .ok_or_else(|| ParseError::MissingField(message_type.clone(), "body"))?;
+    let event_type = body.get("type")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| ParseError::MissingField(message_type.clone(), "body.type"))?;
+    let full_type = format!("{}/{}", message_type, event_type);
+
+    match (message_type.as_str(), event_type) {
+        (_, "note") => {
+            let note_body = body.get("body")
+                .ok_or_else(|| ParseError::MissingField(full_type.clone(), "body.body"))?;
+            parse_note(note_body, options).map(|note| TimelineEvent::Note(Box::new(note)))
+        }
+        ("channel", "error") => Ok(TimelineEvent::ChannelError { reason: error_reason(body.get("body")) }),
+        ("noteUpdated", "reacted") => {
+            let inner = body.get("body");
+            Ok(TimelineEvent::Reacted {
+                note_id: note_id_of(body, &full_type)?,
+                reaction: inner.and_then(|b| b.get("reaction")).and_then(|v| v.as_str())
+                    .ok_or_else(|| ParseError::MissingField(full_type.clone(), "body.body.reaction"))?
+                    .to_string(),
+                user_id: inner.and_then(|b| b.get("userId")).and_then(|v| v.as_str()).map(|s| s.to_string()),
+            })
+        }
+        ("noteUpdated", "deleted") => Ok(TimelineEvent::Deleted {
+            note_id: note_id_of(body, &full_type)?,
+        }),
+        ("noteUpdated", "updated") => {
+            let inner = body.get("body");
+            let get_text = |key: &str| {
+                inner.and_then(|b| b.get(key)).and_then(|v| v.as_str()).map(|t| clean_text(t, options))
+            };
+            Ok(TimelineEvent::Updated {
+                note_id: note_id_of(body, &full_type)?,
+                text: get_text("text"),
+                cw: get_text("cw"),
+            })
+        }
+        _ => Ok(TimelineEvent::Other { event_type: full_type }),
+    }
+}
+
+// エラーのメッセージ。`message`や`error`などの文字列を探し、なければJSONのまま返す
+pub(crate) fn error_reason(body: Option<&Value>) -> String {
+    let Some(body) = body else { return "理由不明".to_string() };
+    if let Some(text) = body.as_str() {
+        return text.to_string();
+    }
+    ["message", "error", "reason", "code"]
+        .iter()
+        .find_map(|key| {
+            let value = body.get(key)?;
+            value.as_str().map(|s| s.to_string()).or_else(|| value.get("message")?.as_str().map(|s| s.to_string()))
+        })
+        .unwrap_or_else(|| body.to_string())
+}
+
+// noteUpdated系イベントの対象ノートID (body.id)
+fn note_id_of(body: &Value, full_type: &str) -> Result<String, ParseError> {
+    body.get("id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .ok_or_else(|| ParseError::MissingField(full_type.to_string(), "body.id"))
+}
+
+/// WebSocketのテキストメッセージをノートとして解析する
+///
+/// ノート以外のイベントは`Ok(None)`、ノートのはずなのに形が想定外なら`Err`を返す。
+pub fn parse_message(text: &str, options: &ParseOptions) -> Result<Option<ParsedNote>, ParseError> {
+    match parse_event(text, options)? {
+        TimelineEvent::Note(note) => Ok(Some(*note)),
+        _ => Ok(None),
+    }
+}
+
+/// ノート本体 (`body.body`) を解析する
+///
+/// Misskey本家とフォーク (Firefish/Sharkey/Iceshrimp) のペイロードの差異はここで吸収する。
+pub fn parse_note(note: &Value, options: &ParseOptions) -> Result<ParsedNote, ParseError> {
+    parse_note_at(note, options, 0)
+}
+
+// `depth`はリノート元・リプライ先をたどった段数
+fn parse_note_at(note: &Value, options: &ParseOptions, depth: usize) -> Result<ParsedNote, ParseError> {
+    if !note.is_object() {
+        return Err(ParseError::MissingField("note".to_string(), "body.body"));
+    }
+
+    let user = note.get("user");
+    let get_str = |v: Option<&Value>, key: &str| {
+        v.and_then(|v| v.get(key)).and_then(|v| v.as_str()).map(|s| s.to_string())
+    };
+
+    let renote = match note.get("renote") {
+        Some(renote) if renote.is_object() && depth < MAX_RENOTE_DEPTH => Some(Box::new(parse_note_at(renote, options, depth + 1)?)),
+        _ => None,
+    };
+
+    // 絵文字はノート本体とユーザー名の両方に付く（フォークによってはユーザー側のみ）
+    let mut emojis = note.get("emojis").map(parse_emoji_list).unwrap_or_default();
+    let user_emojis = user.and_then(|u| u.get("emojis")).map(parse_emoji_list).unwrap_or_default();
+    for emoji in user_emojis {
+        if emojis.len() >= MAX_NOTE_EMOJIS {
+            break;
+        }
+        if !emojis.iter().any(|e| e.name == emoji.name) {
+            emojis.push(emoji);
+        }
+    }
+
+    let text = get_str(Some(note), "text").map(|t| clean_text(&t, options));
+    let cw = get_str(Some(note), "cw").map(|c| clean_text(&c, options));
+    let has_poll = note.get("poll").is_some_and(|p| p.is_object());
+    let has_files = note.get("files").and_then(|v| v.as_array()).is_some_and(|files| !files.is_empty())
+        || note.get("fileIds").and_then(|v| v.as_array()).is_some_and(|ids| !ids.is_empty());
+
+    let mut parsed = ParsedNote {
+        id: get_str(Some(note), "id").unwrap_or_default(),
+        created_at: get_str(Some(note), "createdAt"),
+        user_id: get_str(Some(note), "userId").or_else(|| get_str(user, "id")),
+        name: get_str(user, "name").unwrap_or_else(|| "Unknown".to_string()),
+        username: get_str(user, "username").unwrap_or_else(|| "Unknown".to_string()),
+        user_host: get_str(user, "host"),
+        avatar_url: get_str(user, "avatarUrl"),
+        text,
+        cw,
+        emojis,
+        mentions: note.get("mentions")
+            .and_then(|v| v.as_array())
+            .map(|ids| ids.iter().filter_map(|id| id.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default(),
+        renote,
+        reply_id: get_str(Some(note), "replyId"),
+        // リプライ先は表示に必須ではないので、解析できなくてもノート自体は受け付ける
+        reply: note.get("reply")
+            .filter(|r| r.is_object() && depth < MAX_RENOTE_DEPTH)
+            .and_then(|r| parse_note_at(r, options, depth + 1).ok())
+            .map(Box::new),
+        note_types: Vec::new(),
+        counts: NoteCounts::parse(note),
+    };
+    parsed.note_types = classify(&parsed, has_poll, has_files);
+    Ok(parsed)
+}
+
+// ノートの種類を判定する
+pub(crate) fn classify(note: &ParsedNote, has_poll: bool, has_files: bool) -> Vec<NoteType> {
+    let mut types = Vec::new();
+    if note.reply_id.is_some() {
+        types.push(NoteType::Reply);
+    }
+    if note.is_pure_renote() {
+        types.push(NoteType::Renote);
+    }
+    if note.is_quote() {
+        types.push(NoteType::Quote);
+    }
+    if has_poll {
+        types.push(NoteType::Poll);
+    }
+    if has_files && note.renote.is_none() && !note.has_own_text() {
+        types.push(NoteType::MediaOnly);
+    }
+    if note.text.as_deref().is_some_and(is_emoji_only) {
+        types.push(NoteType::EmojiOnly);
+    }
+    types
+}
+
+// カスタム絵文字と絵文字らしい記号（ASCII以外の文字・数字でないもの）と空白だけか
+fn is_emoji_only(text: &str) -> bool {
+    let rest = strip_emoji_shortcodes(text);
+    let has_emoji = rest.len() < text.len() || rest.chars().any(|c| !c.is_whitespace());
+    has_emoji && rest.chars().all(|c| c.is_whitespace() || (!c.is_ascii() && !c.is_alphanumeric()))
+}
+
+// 本文とCWにオプションの整形を適用する
+pub(crate) fn clean_text(text: &str, options: &ParseOptions) -> String {
+    // 表示するのは先頭だけなので、巨大な本文は整形する前に切り詰める
+    let text = match text.char_indices().nth(MAX_NOTE_TEXT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    };
+    let mut text = text.to_string();
+    if options.strip_mfm {
+        text = strip_mfm(&text);
+    }
+    if options.normalize_whitespace {
+        text = normalize_whitespace(&text);
+    }
+    text
+}
+
+/// 本文の空白を整える
+///
+/// 改行をLFに揃え、各行の前後の空白を削り、空行を取り除く。
+pub fn normalize_whitespace(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .split('\n')
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,132 @@
+use crate::emoji::EmojiInfo;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+
+/// 外部のツールから流すコメント
+///
+/// HTTPで受け取るJSONは`{"name": "...", "text": "...", "color": "#ff8800"}`の形で、`text`以外は省略できる。
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExternalComment {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub text: String,
+    #[serde(default, deserialize_with = "deserialize_color")]
+    pub color: Option<[u8; 3]>,
+    #[serde(default)]
+    pub emojis: Vec<EmojiInfo>,
+}
+
+impl ExternalComment {
+    /// 標準入力の1行をコメントにする
+    ///
+    /// `{`で始まり`ExternalComment`として読めるJSONならその内容を、それ以外は行全体を本文にする。
+    /// 空行は`None`。
+    pub fn from_line(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if line.starts_with('{') {
+            if let Ok(comment) = serde_json::from_str::<ExternalComment>(line) {
+                return Some(comment);
+            }
+        }
+        Some(ExternalComment {
+            name: None,
+            text: line.to_string(),
+            color: None,
+            emojis: Vec::new(),
+        })
+    }
+}
+
+/// `#rrggbb`（`#`は省略可）を色にする
+pub fn parse_color(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+// 色は`"#rrggbb"`と`[r, g, b]`のどちらでも受け付ける
+fn deserialize_color<'de, D>(deserializer: D) -> Result<Option<[u8; 3]>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Color {
+        Hex(String),
+        Rgb([u8; 3]),
+    }
+    match Option::<Color>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Color::Rgb(rgb)) => Ok(Some(rgb)),
+        Some(Color::Hex(hex)) => parse_color(&hex)
+            .map(Some)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid color `{}`", hex))),
+    }
+}
+
+struct PushState {
+    secret: String,
+    on_comment: Box<dyn Fn(ExternalComment) + Send + Sync>,
+}
+
+/// コメントを受け付けるHTTPサーバーを`127.0.0.1:{port}`で動かす
+///
+/// `POST /comment`に`ExternalComment`のJSONを送るとコメントとして流れる。
+/// `Authorization: Bearer {secret}`が一致しないリクエストは拒否する。
+/// 同じPCの他のアプリから勝手に流されないように、`secret`が空なら起動しない。
+pub async fn serve(
+    port: u16,
+    secret: Option<String>,
+    on_comment: impl Fn(ExternalComment) + Send + Sync + 'static,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let secret = secret
+        .filter(|s| !s.trim().is_empty())
+        .ok_or("合言葉 (http_source_secret) が設定されていないので起動しません")?;
+    let state = Arc::new(PushState {
+        secret,
+        on_comment: Box::new(on_comment),
+    });
+    let app = Router::new()
+        .route("/comment", post(receive_comment))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).await?;
+    println!("[PUSH] Listening on http://127.0.0.1:{}/comment", port);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+async fn receive_comment(
+    State(state): State<Arc<PushState>>,
+    headers: HeaderMap,
+    Json(comment): Json<ExternalComment>,
+) -> StatusCode {
+    let token = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| constant_time_eq(token.as_bytes(), state.secret.as_bytes())) {
+        return StatusCode::UNAUTHORIZED;
+    }
+    if comment.text.trim().is_empty() {
+        return StatusCode::BAD_REQUEST;
+    }
+    (state.on_comment)(comment);
+    StatusCode::NO_CONTENT
+}
+
+// 一致するまでの時間から合言葉を推測されないよう、常に全体を比べる
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// `Retry-After`がないときの最初の待ち時間
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(5);
+
+/// 続けて制限されたときに延ばす待ち時間の上限
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+struct HostState {
+    until: Instant,
+    backoff: Duration,
+}
+
+/// サーバーごとのレート制限（HTTP 429）の状態
+///
+/// 制限されたサーバーへのリクエストは`Retry-After`の時間が過ぎるまで控える。
+/// `Retry-After`がなければ待ち時間を倍々に延ばし、成功したら元に戻す。
+#[derive(Default)]
+pub struct HostBackoff {
+    hosts: Mutex<HashMap<String, HostState>>,
+}
+
+impl HostBackoff {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 次にリクエストしてよいまでの残り時間（制限されていなければNone）
+    pub fn remaining(&self, host: &str) -> Option<Duration> {
+        self.remaining_at(host, Instant::now())
+    }
+
+    /// `remaining`の現在時刻を指定できる版（テスト用）
+    pub fn remaining_at(&self, host: &str, now: Instant) -> Option<Duration> {
+        let hosts = self.hosts.lock().unwrap();
+        let state = hosts.get(host)?;
+        (state.until > now).then(|| state.until - now)
+    }
+
+    /// 429を受けたことを記録し、待つ時間を返す
+    pub fn rate_limited(&self, host: &str, retry_after: Option<Duration>) -> Duration {
+        self.rate_limited_at(host, retry_after, Instant::now())
+    }
+
+    /// `rate_limited`の現在時刻を指定できる版（テスト用）
+    pub fn rate_limited_at(&self, host: &str, retry_after: Option<Duration>, now: Instant) -> Duration {
+        let mut hosts = self.hosts.lock().unwrap();
+        let backoff = match (retry_after, hosts.get(host)) {
+            (Some(retry_after), _) => retry_after,
+            (None, Some(state)) => (state.backoff * 2).min(MAX_BACKOFF),
+            (None, None) => DEFAULT_BACKOFF,
+        };
+        hosts.insert(host.to_string(), HostState { until: now + backoff, backoff });
+        backoff
+    }
+
+    /// リクエストが通ったら待ち時間の延長をやめる
+    pub fn succeeded(&self, host: &str) {
+        self.hosts.lock().unwrap().remove(host);
+    }
+
+    /// 制限中のサーバーと残り時間（デバッグ表示用、残り時間の長い順）
+    pub fn limited_hosts(&self) -> Vec<(String, Duration)> {
+        let now = Instant::now();
+        let hosts = self.hosts.lock().unwrap();
+        let mut limited: Vec<(String, Duration)> = hosts
+            .iter()
+            .filter(|(_, state)| state.until > now)
+            .map(|(host, state)| (host.clone(), state.until - now))
+            .collect();
+        limited.sort_by_key(|(_, remaining)| std::cmp::Reverse(*remaining));
+        limited
+    }
+}
+
+/// `Retry-After`ヘッダーの秒数を読む（HTTP日付の形式は扱わない）
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+/// これより短い時間で切れた接続は「すぐ切られた」とみなす
+pub const SHORT_CONNECTION: Duration = Duration::from_secs(3);
+
+/// 続けてこの回数すぐ切られたら、サーバーがメンテナンス中などで使えないとみなす
+pub const UNAVAILABLE_AFTER: u32 = 3;
+
+/// サーバーが使えないとみなしたときの待ち時間の最初と上限
+const UNAVAILABLE_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_UNAVAILABLE_BACKOFF: Duration = Duration::from_secs(600);
+
+/// 続けてすぐ切られた回数から、再接続の前に待つ時間を決める（まだ使えないとみなさないならNone）
+pub fn unavailable_backoff(short_connections: u32) -> Option<Duration> {
+    let over = short_connections.checked_sub(UNAVAILABLE_AFTER)?;
+    let backoff = UNAVAILABLE_BACKOFF.saturating_mul(2u32.saturating_pow(over));
+    Some(backoff.min(MAX_UNAVAILABLE_BACKOFF))
+}
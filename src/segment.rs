@@ -0,0 +1,223 @@
+use crate::emoji::EmojiInfo;
+
+/// 描画用に分割したテキストの断片
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextSegment {
+    Text(String),
+    Emoji(EmojiInfo),
+}
+
+/// 分割するテキストの最大の文字数（これを超える分は描画しない）
+pub const MAX_SEGMENT_CHARS: usize = 1000;
+
+/// ショートコードとみなす絵文字名の最大の長さ（バイト数）
+const MAX_EMOJI_NAME_LEN: usize = 128;
+
+/// テキストを通常の文字列部分と `:emoji:` 部分に分割する
+///
+/// `emojis` に含まれない名前のショートコードはそのまま文字列として残す。
+/// 他のサーバーの絵文字 `:emoji@host:` は`@host`まで含めた名前で探す。
+/// 巨大なテキストでも処理が重くならないよう、`MAX_SEGMENT_CHARS`文字より後ろは捨てる。
+pub fn segment_text(text: &str, emojis: &[EmojiInfo]) -> Vec<TextSegment> {
+    let text = match text.char_indices().nth(MAX_SEGMENT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    };
+    let mut segments = Vec::new();
+    let mut current_text = String::new();
+    let mut rest = text;
+    
+    while let Some(start) = rest.find(':') {
+        current_text.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = shortcode_len(after);
+        // 閉じる `:` があり、絵文字が存在するものだけを絵文字にする
+        let emoji = (name_len > 0 && name_len <= MAX_EMOJI_NAME_LEN && after[name_len..].starts_with(':'))
+            .then(|| find_emoji(emojis, &after[..name_len]))
+            .flatten();
+        match emoji {
+            Some(emoji_info) => {
+                if !current_text.is_empty() {
+                    segments.push(TextSegment::Text(std::mem::take(&mut current_text)));
+                }
+                segments.push(TextSegment::Emoji(emoji_info.clone()));
+                rest = &after[name_len + 1..];
+            }
+            None => {
+                current_text.push(':');
+                rest = after;
+            }
+        }
+    }
+    current_text.push_str(rest);
+    
+    if !current_text.is_empty() {
+        segments.push(TextSegment::Text(current_text));
+    }
+    
+    segments
+}
+
+/// `:emoji@host:` の名前を絵文字名とサーバーに分ける
+///
+/// `@`がなければサーバーはNone。`@.`はMisskeyでの「このサーバー」の書き方なので、これもNoneにする。
+pub fn split_emoji_host(name: &str) -> (&str, Option<&str>) {
+    match name.split_once('@') {
+        Some((base, ".")) => (base, None),
+        Some((base, host)) => (base, Some(host)),
+        None => (name, None),
+    }
+}
+
+// 名前に一致する絵文字を探す（`name@.`は`name`としても探す）
+fn find_emoji<'a>(emojis: &'a [EmojiInfo], name: &str) -> Option<&'a EmojiInfo> {
+    emojis.iter().find(|e| e.name == name).or_else(|| {
+        let (base, host) = split_emoji_host(name);
+        if host.is_none() && base != name {
+            emojis.iter().find(|e| e.name == base)
+        } else {
+            None
+        }
+    })
+}
+
+// `:`の直後から、ショートコードとして読める部分のバイト数（`name`または`name@host`）
+fn shortcode_len(s: &str) -> usize {
+    let name_len = s.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')).unwrap_or(s.len());
+    if name_len == 0 {
+        return 0;
+    }
+    let Some(host) = s[name_len..].strip_prefix('@') else { return name_len };
+    let host_len = host.find(|c: char| !(c.is_alphanumeric() || c == '.' || c == '-')).unwrap_or(host.len());
+    if host_len == 0 {
+        name_len
+    } else {
+        name_len + 1 + host_len
+    }
+}
+
+/// テキストから `:emoji:` 形式のショートコードを取り除く
+///
+/// 絵文字を表示しない設定のときに使う。閉じていない `:` はそのまま残す。
+pub fn strip_emoji_shortcodes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    
+    while let Some(start) = rest.find(':') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = shortcode_len(after);
+        if name_len > 0 && after[name_len..].starts_with(':') {
+            rest = &after[name_len + 1..];
+        } else {
+            out.push(':');
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    
+    out
+}
+
+/// テキストに出てくる`:emoji:`・`:emoji@host:`の名前を出てくる順に返す
+///
+/// 名前に使える文字は`segment_text`と同じ（日本語などの名前も含む）。
+pub fn shortcode_names(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    
+    while let Some(start) = rest.find(':') {
+        let after = &rest[start + 1..];
+        let name_len = shortcode_len(after);
+        if name_len > 0 && name_len <= MAX_EMOJI_NAME_LEN && after[name_len..].starts_with(':') {
+            names.push(after[..name_len].to_string());
+            rest = &after[name_len + 1..];
+        } else {
+            rest = after;
+        }
+    }
+    
+    names
+}
+
+/// 表示する行数を`max_lines`までに制限し、切り詰めた場合は最終行の末尾に「…」を付ける
+///
+/// `max_lines`が0なら制限しない。
+pub fn limit_lines(segments: Vec<TextSegment>, max_lines: usize) -> Vec<TextSegment> {
+    if max_lines == 0 {
+        return segments;
+    }
+    
+    let mut limited = Vec::with_capacity(segments.len());
+    let mut line_count = 1;
+    
+    for segment in segments {
+        match segment {
+            TextSegment::Text(content) => {
+                let mut kept = String::new();
+                for (line_idx, line) in content.split('\n').enumerate() {
+                    if line_idx > 0 {
+                        if line_count == max_lines {
+                            // 次の行に入る前に打ち切る
+                            kept.push('…');
+                            limited.push(TextSegment::Text(kept));
+                            return limited;
+                        }
+                        line_count += 1;
+                        kept.push('\n');
+                    }
+                    kept.push_str(line);
+                }
+                limited.push(TextSegment::Text(kept));
+            }
+            emoji => limited.push(emoji),
+        }
+    }
+    
+    limited
+}
+
+/// 1つのコメントで画像として描く絵文字の数を制限する
+///
+/// `remaining`は残りの個数で、超えた分は`:name:`の文字列に戻す。
+/// 名前と本文などの複数の断片にまたがって数えられるよう、呼び出し側で持ち回す。
+pub fn limit_emoji(segments: Vec<TextSegment>, remaining: &mut usize) -> Vec<TextSegment> {
+    let mut limited: Vec<TextSegment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        let segment = match segment {
+            TextSegment::Emoji(emoji) if *remaining == 0 => TextSegment::Text(format!(":{}:", emoji.name)),
+            TextSegment::Emoji(emoji) => {
+                *remaining -= 1;
+                TextSegment::Emoji(emoji)
+            }
+            text => text,
+        };
+        // 文字列に戻した絵文字は前後の文字列とまとめる
+        match (limited.last_mut(), segment) {
+            (Some(TextSegment::Text(last)), TextSegment::Text(text)) => last.push_str(&text),
+            (_, segment) => limited.push(segment),
+        }
+    }
+    limited
+}
+
+/// テキストに出てくる順に、最初の`max`種類の絵文字だけを残す（`max`が0なら全部）
+///
+/// 使われない絵文字や上限を超えた絵文字をダウンロードしないために使う。
+pub fn first_emojis(text: &str, emojis: &[EmojiInfo], max: usize) -> Vec<EmojiInfo> {
+    if max == 0 {
+        return emojis.to_vec();
+    }
+    let mut used: Vec<EmojiInfo> = Vec::new();
+    for segment in segment_text(text, emojis) {
+        if used.len() >= max {
+            break;
+        }
+        if let TextSegment::Emoji(emoji) = segment {
+            if !used.contains(&emoji) {
+                used.push(emoji);
+            }
+        }
+    }
+    used
+}
@@ -0,0 +1,91 @@
+use crate::config::SoundTrigger;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::time::{Duration, Instant};
+
+/// 効果音を鳴らす
+///
+/// 出力デバイスはスレッドをまたいで持てないので、専用のスレッドで開いておき
+/// 鳴らすファイルのパスをチャネルで渡す。デバイスが開けなかった場合は何も鳴らさない。
+pub struct SoundPlayer {
+    tx: Sender<PathBuf>,
+}
+
+impl SoundPlayer {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel::<PathBuf>();
+        std::thread::spawn(move || {
+            let (_stream, handle) = match rodio::OutputStream::try_default() {
+                Ok(output) => output,
+                Err(e) => {
+                    eprintln!("[SOUND] Failed to open output device: {}", e);
+                    return;
+                }
+            };
+            while let Ok(path) = rx.recv() {
+                if let Err(e) = play_file(&handle, &path) {
+                    eprintln!("[SOUND] Failed to play {:?}: {}", path, e);
+                }
+            }
+        });
+        Self { tx }
+    }
+
+    /// ファイルを再生する（再生の終わりは待たない）
+    pub fn play(&self, path: &Path) {
+        let _ = self.tx.send(path.to_path_buf());
+    }
+}
+
+impl Default for SoundPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn play_file(handle: &rodio::OutputStreamHandle, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let file = std::io::BufReader::new(std::fs::File::open(path)?);
+    let source = rodio::Decoder::new(file)?;
+    let sink = rodio::Sink::try_new(handle)?;
+    sink.append(source);
+    sink.detach();
+    Ok(())
+}
+
+/// 同じ効果音が立て続けに鳴らないように、最後に鳴らした時刻を効果音ごとに覚えておく
+#[derive(Debug)]
+pub struct SoundCooldown {
+    cooldown: Duration,
+    last_played: HashMap<PathBuf, Instant>,
+}
+
+impl SoundCooldown {
+    pub fn new(cooldown: Duration) -> Self {
+        Self {
+            cooldown,
+            last_played: HashMap::new(),
+        }
+    }
+
+    pub fn set_cooldown(&mut self, cooldown: Duration) {
+        self.cooldown = cooldown;
+    }
+
+    /// テキストに一致した最初のトリガーの効果音を返す（待ち時間中ならNone）
+    pub fn trigger<'a>(&mut self, triggers: &'a [SoundTrigger], text: &str) -> Option<&'a Path> {
+        self.trigger_at(triggers, text, Instant::now())
+    }
+
+    /// `trigger`の現在時刻を指定できる版（テスト用）
+    pub fn trigger_at<'a>(&mut self, triggers: &'a [SoundTrigger], text: &str, now: Instant) -> Option<&'a Path> {
+        let trigger = triggers.iter().find(|t| t.matches(text))?;
+        if let Some(last) = self.last_played.get(&trigger.sound) {
+            if now.duration_since(*last) < self.cooldown {
+                return None;
+            }
+        }
+        self.last_played.insert(trigger.sound.clone(), now);
+        Some(&trigger.sound)
+    }
+}
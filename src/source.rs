@@ -0,0 +1,123 @@
+use crate::config::{Account, SourceType};
+use crate::bluesky::BlueskyClient;
+use crate::mastodon::MastodonClient;
+use crate::misskey::{ConnectOptions, MiUser, MisskeyClient};
+use crate::note::{ParseError, ParseOptions, ParsedNote, TimelineEvent};
+use std::collections::HashSet;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// アカウントの接続先の種類ごとのストリーミングクライアント
+///
+/// 受信タスクからはどの種類でも同じように扱えるよう、共通の操作をここで振り分ける。
+pub enum StreamClient {
+    Misskey(MisskeyClient),
+    Mastodon(MastodonClient),
+    Bluesky(BlueskyClient),
+}
+
+impl StreamClient {
+    pub async fn connect(account: &Account, options: ConnectOptions) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let token = account.token.clone();
+        Ok(match account.source {
+            SourceType::Misskey => StreamClient::Misskey(MisskeyClient::connect_with(&account.host, token, options).await?),
+            SourceType::Mastodon => StreamClient::Mastodon(MastodonClient::connect_with(&account.host, token, options).await?),
+            SourceType::Bluesky => StreamClient::Bluesky(BlueskyClient::connect_with(&account.host, &account.wanted_dids(), options).await?),
+        })
+    }
+
+    pub async fn whoami(&mut self) -> Result<&MiUser, Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            StreamClient::Misskey(client) => client.whoami().await,
+            StreamClient::Mastodon(client) => client.whoami().await,
+            StreamClient::Bluesky(client) => client.whoami().await,
+        }
+    }
+
+    pub async fn fetch_hidden_user_ids(&self) -> Result<HashSet<String>, Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            StreamClient::Misskey(client) => client.fetch_hidden_user_ids().await,
+            StreamClient::Mastodon(client) => client.fetch_hidden_user_ids().await,
+            StreamClient::Bluesky(client) => client.fetch_hidden_user_ids().await,
+        }
+    }
+
+    /// アカウントのタイムラインを購読する（Blueskyは接続時に決まるので何もしない）
+    pub fn subscribe(&self, account: &Account) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            StreamClient::Misskey(client) => {
+                let channel = account.timeline.to_channel_name();
+                let id = format!("{}-{}", channel, account.name);
+                client.subscribe(channel, &id, account.subscribe_params())
+            }
+            StreamClient::Mastodon(client) => client.subscribe(&account.timeline),
+            StreamClient::Bluesky(_) => Ok(()),
+        }
+    }
+
+    /// 流れている間の削除・編集を受け取れるようにノートをキャプチャする
+    ///
+    /// Mastodon・Blueskyは購読中のストリームに削除・編集が流れてくるので何もしない。
+    pub fn sub_note(&self, note_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            StreamClient::Misskey(client) => client.sub_note(note_id),
+            StreamClient::Mastodon(_) | StreamClient::Bluesky(_) => Ok(()),
+        }
+    }
+
+    /// `sub_note`でキャプチャしたノートのイベントを受け取るのをやめる
+    pub fn unsub_note(&self, note_id: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        match self {
+            StreamClient::Misskey(client) => client.unsub_note(note_id),
+            StreamClient::Mastodon(_) | StreamClient::Bluesky(_) => Ok(()),
+        }
+    }
+    
+    /// ストリームに含まれない投稿者の情報を補い、表示してよくなったノートを返す
+    ///
+    /// BlueskyのハンドルとアバターのみAPIで調べる。まとめて調べるためにノートを
+    /// しばらく預かることがあり、そのときは空を返して後でまとめて返す。
+    pub async fn resolve_authors(&mut self, note: ParsedNote) -> Vec<ParsedNote> {
+        match self {
+            StreamClient::Bluesky(client) => client.resolve_authors(note).await,
+            _ => vec![note],
+        }
+    }
+
+    pub fn api_client(&self) -> reqwest::Client {
+        match self {
+            StreamClient::Misskey(client) => client.api_client(),
+            StreamClient::Mastodon(client) => client.api_client(),
+            StreamClient::Bluesky(client) => client.api_client(),
+        }
+    }
+
+    pub async fn next_message(&mut self) -> Option<Result<Message, tokio_tungstenite::tungstenite::Error>> {
+        match self {
+            StreamClient::Misskey(client) => client.next_message().await,
+            StreamClient::Mastodon(client) => client.next_message().await,
+            StreamClient::Bluesky(client) => client.next_message().await,
+        }
+    }
+
+    /// 受信したテキストメッセージを接続先の形式に合わせて解析する
+    pub fn parse_event(&self, text: &str, options: &ParseOptions) -> Result<TimelineEvent, ParseError> {
+        match self {
+            StreamClient::Misskey(_) => crate::note::parse_event(text, options),
+            StreamClient::Mastodon(_) => crate::mastodon::parse_event(text, options),
+            StreamClient::Bluesky(_) => crate::bluesky::parse_event(text, options),
+        }
+    }
+
+    /// `/api/emoji`でカスタム絵文字を名前から調べられるか（Misskeyのみ）
+    pub fn has_emoji_api(&self) -> bool {
+        matches!(self, StreamClient::Misskey(_))
+    }
+
+    pub fn close(self) {
+        match self {
+            StreamClient::Misskey(client) => client.close(),
+            StreamClient::Mastodon(client) => client.close(),
+            StreamClient::Bluesky(_) => println!("[CLOSE] WebSocket connection closed"),
+        }
+    }
+}
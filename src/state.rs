@@ -0,0 +1,63 @@
+use crate::config::config_path;
+use crate::emoji::EmojiInfo;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 異常終了から再起動したときに流れていたコメントを戻すための状態
+///
+/// 表示に最低限必要な項目だけを持つ。URLプレビューやリノート元などは戻さない。
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedState {
+    pub comments: Vec<SavedComment>,
+    pub seen_ids: Vec<String>, // 表示済みのノートID（古い順）
+}
+
+/// 保存するコメント
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedComment {
+    pub note_id: String,
+    pub text: String,
+    pub name: String,
+    pub username: String,
+    pub user_host: Option<String>,
+    pub host: String,
+    pub account_name: String,
+    pub account_color: [u8; 3],
+    #[serde(default)]
+    pub emojis: Vec<EmojiInfo>,
+    pub speed: f32,
+    pub scale: f32,
+}
+
+/// 状態ファイルの場所（設定ファイルと同じフォルダの`state.json`）
+pub fn state_path() -> PathBuf {
+    config_path()
+        .parent()
+        .map(|dir| dir.join("state.json"))
+        .unwrap_or_else(|| PathBuf::from("state.json"))
+}
+
+impl SavedState {
+    /// 保存された状態を読み込む（ファイルがない・壊れている場合はNone）
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        match serde_json::from_str(&content) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                eprintln!("[STATE] Ignoring broken state file {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// 状態を書き出す（書き込み途中で落ちても壊れないように一時ファイルから置き換える）
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+}
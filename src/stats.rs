@@ -0,0 +1,107 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// 集計対象の1件の投稿
+#[derive(Debug, Clone)]
+struct PostRecord {
+    at: Instant,
+    user: String, // username@host
+    host: String,
+    emojis: Vec<String>, // 本文で使われた絵文字名（重複あり）
+}
+
+/// 直近の投稿の集計結果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsSummary {
+    pub total_posts: usize,
+    pub posts_per_minute: f32,
+    pub top_emojis: Vec<(String, usize)>,
+    pub top_users: Vec<(String, usize)>,
+    pub top_hosts: Vec<(String, usize)>,
+}
+
+/// 直近`window`の間に流れた投稿を覚えておき、投稿数や多く使われた絵文字などを集計する
+///
+/// 古い記録は追加・集計のたびに捨てるので、保持する件数は期間内の投稿数までに収まる。
+#[derive(Debug)]
+pub struct StatsRecorder {
+    window: Duration,
+    posts: VecDeque<PostRecord>,
+}
+
+impl StatsRecorder {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            posts: VecDeque::new(),
+        }
+    }
+
+    /// 集計期間を変える（短くした場合は次の集計で古い記録が捨てられる）
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// 投稿を記録する。`host`はローカルユーザーなら接続先のサーバー
+    pub fn record(&mut self, username: &str, host: &str, emojis: Vec<String>) {
+        self.record_at(Instant::now(), username, host, emojis);
+    }
+
+    pub fn record_at(&mut self, at: Instant, username: &str, host: &str, emojis: Vec<String>) {
+        self.posts.push_back(PostRecord {
+            at,
+            user: format!("{}@{}", username, host),
+            host: host.to_string(),
+            emojis,
+        });
+        self.prune(at);
+    }
+
+    /// 現在の集計結果（それぞれ上位`top`件）
+    pub fn summary(&mut self, top: usize) -> StatsSummary {
+        self.summary_at(Instant::now(), top)
+    }
+
+    pub fn summary_at(&mut self, now: Instant, top: usize) -> StatsSummary {
+        self.prune(now);
+
+        let mut emojis: HashMap<&str, usize> = HashMap::new();
+        let mut users: HashMap<&str, usize> = HashMap::new();
+        let mut hosts: HashMap<&str, usize> = HashMap::new();
+        for post in &self.posts {
+            for emoji in &post.emojis {
+                *emojis.entry(emoji).or_default() += 1;
+            }
+            *users.entry(&post.user).or_default() += 1;
+            *hosts.entry(&post.host).or_default() += 1;
+        }
+
+        let minutes = (self.window.as_secs_f32() / 60.0).max(1.0 / 60.0);
+        StatsSummary {
+            total_posts: self.posts.len(),
+            posts_per_minute: self.posts.len() as f32 / minutes,
+            top_emojis: ranking(emojis, top),
+            top_users: ranking(users, top),
+            top_hosts: ranking(hosts, top),
+        }
+    }
+
+    // 集計期間より古い記録を捨てる
+    fn prune(&mut self, now: Instant) {
+        while let Some(oldest) = self.posts.front() {
+            if now.saturating_duration_since(oldest.at) > self.window {
+                self.posts.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+// 回数の多い順（同数なら名前順）に上位`top`件を返す
+fn ranking(counts: HashMap<&str, usize>, top: usize) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().map(|(name, count)| (name.to_string(), count)).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked.truncate(top);
+    ranked
+}
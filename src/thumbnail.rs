@@ -0,0 +1,48 @@
+use image::{imageops::FilterType, DynamicImage, RgbaImage};
+
+/// 表示用に縮小するときの形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageShape {
+    /// 縦横比を保ったまま、長い辺を指定の長さに収める（サムネイルやアイコン）
+    Fit,
+    /// 中央を正方形に切り抜き、円の外側を透明にする（アバター）
+    Circle,
+}
+
+/// 画像を表示サイズに合わせて縮小・切り抜きする
+///
+/// 元画像の解像度に関係なくテクスチャの大きさが`size`四方に収まるので、VRAMの使用量を抑えられる。
+/// `size`より小さい画像は拡大しない（`Circle`の正方形への切り抜きは行う）。
+pub fn resize_for_display(image: &DynamicImage, size: u32, shape: ImageShape) -> RgbaImage {
+    let size = size.max(1);
+    match shape {
+        ImageShape::Fit => {
+            if image.width() <= size && image.height() <= size {
+                image.to_rgba8()
+            } else {
+                image.resize(size, size, FilterType::Triangle).to_rgba8()
+            }
+        }
+        ImageShape::Circle => {
+            let edge = image.width().min(image.height());
+            let cropped = image.crop_imm((image.width() - edge) / 2, (image.height() - edge) / 2, edge, edge);
+            let edge = edge.min(size).max(1);
+            let mut rgba = cropped.resize_exact(edge, edge, FilterType::Triangle).to_rgba8();
+            apply_circle_mask(&mut rgba);
+            rgba
+        }
+    }
+}
+
+// 内接円の外側を透明にする（境界は1ピクセル分ぼかす）
+fn apply_circle_mask(image: &mut RgbaImage) {
+    let radius = image.width().min(image.height()) as f32 / 2.0;
+    let (cx, cy) = (image.width() as f32 / 2.0, image.height() as f32 / 2.0);
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - cx;
+        let dy = y as f32 + 0.5 - cy;
+        let distance = (dx * dx + dy * dy).sqrt();
+        let coverage = (radius - distance + 0.5).clamp(0.0, 1.0);
+        pixel[3] = (pixel[3] as f32 * coverage).round() as u8;
+    }
+}
@@ -0,0 +1,61 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// トーストを表示しておく時間
+pub const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+/// 消える前に薄くなっていく時間（表示時間の最後の部分）
+const TOAST_FADE: Duration = Duration::from_millis(500);
+
+/// 同時に出しておく最大の数（超えたら古いものから消す）
+const MAX_TOASTS: usize = 5;
+
+/// 画面の隅に少しの間だけ出す通知
+#[derive(Debug, Clone, PartialEq)]
+pub struct Toast {
+    pub message: String,
+    pub color: [u8; 3],
+    pub expires_at: Instant,
+}
+
+impl Toast {
+    /// 現在の不透明度（0.0〜1.0、消える直前に薄くなる）
+    pub fn opacity_at(&self, now: Instant) -> f32 {
+        let left = self.expires_at.saturating_duration_since(now);
+        (left.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.0)
+    }
+}
+
+/// トーストの待ち行列（新しいものが後ろ）
+#[derive(Debug, Default)]
+pub struct Toasts {
+    queue: VecDeque<Toast>,
+}
+
+impl Toasts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, message: impl Into<String>, color: [u8; 3]) {
+        self.push_at(message, color, Instant::now());
+    }
+
+    /// `push`の現在時刻を指定できる版（テスト用）
+    pub fn push_at(&mut self, message: impl Into<String>, color: [u8; 3], now: Instant) {
+        if self.queue.len() >= MAX_TOASTS {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Toast { message: message.into(), color, expires_at: now + TOAST_DURATION });
+    }
+
+    /// 期限が切れたものを捨て、残りを古い順に返す
+    pub fn visible_at(&mut self, now: Instant) -> impl Iterator<Item = &Toast> {
+        self.queue.retain(|toast| toast.expires_at > now);
+        self.queue.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
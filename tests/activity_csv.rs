@@ -0,0 +1,39 @@
+use misskey_post_viewer::activity::{ActivityLog, ActivityRow};
+
+fn row(username: &str, account: &str) -> ActivityRow {
+    ActivityRow {
+        timestamp: "2025-02-01T09:00:00.000Z".to_string(),
+        account: account.to_string(),
+        username: username.to_string(),
+        host: "misskey.io".to_string(),
+        note_type: "note".to_string(),
+        text_length: 12,
+    }
+}
+
+#[test]
+fn writes_header_and_escaped_rows() {
+    let mut log = ActivityLog::new(10);
+    log.record(row("alice", "main"));
+    log.record(row("bob", "work, \"sub\""));
+
+    assert_eq!(
+        log.to_csv(),
+        "timestamp,account,username,host,type,text_length\n\
+         2025-02-01T09:00:00.000Z,main,alice,misskey.io,note,12\n\
+         2025-02-01T09:00:00.000Z,\"work, \"\"sub\"\"\",bob,misskey.io,note,12\n"
+    );
+}
+
+#[test]
+fn keeps_only_latest_rows() {
+    let mut log = ActivityLog::new(2);
+    log.record(row("alice", "main"));
+    log.record(row("bob", "main"));
+    log.record(row("carol", "main"));
+
+    assert_eq!(log.len(), 2);
+    let csv = log.to_csv();
+    assert!(!csv.contains("alice"));
+    assert!(csv.contains("carol"));
+}
@@ -0,0 +1,18 @@
+use std::borrow::Cow;
+
+use misskey_post_viewer::bidi::visual_order;
+
+#[test]
+fn ltr_text_is_borrowed_unchanged() {
+    assert!(matches!(visual_order("hello 世界"), Cow::Borrowed("hello 世界")));
+}
+
+#[test]
+fn rtl_runs_are_reversed_for_display() {
+    // ヘブライ文字だけの行は全体が反転する
+    assert_eq!(visual_order("שלום"), "םולש");
+    // 左から右の文中に混ざった部分だけ反転する
+    assert_eq!(visual_order("hi שלום!"), "hi םולש!");
+    // 右から左の文中の数字は左から右のまま
+    assert_eq!(visual_order("שלום 123"), "123 םולש");
+}
@@ -0,0 +1,34 @@
+use misskey_post_viewer::bluesky::parse_event;
+use misskey_post_viewer::{NoteType, ParseOptions, TimelineEvent};
+
+#[test]
+fn post_is_normalized_into_a_note() {
+    let event = parse_event(include_str!("fixtures/bluesky_post.json"), &ParseOptions::default()).unwrap();
+    let TimelineEvent::Note(note) = event else { panic!("expected a note, got {:?}", event) };
+
+    assert_eq!(note.id, "did:plc:abc123/3lgpost000001");
+    assert_eq!(note.created_at.as_deref(), Some("2025-02-01T09:00:00.000Z"));
+    assert_eq!(note.user_id.as_deref(), Some("did:plc:abc123"));
+    // ハンドルを調べるまではDIDを表示する
+    assert_eq!(note.username, "did:plc:abc123");
+    assert_eq!(note.text.as_deref(), Some("こんにちは @bob.bsky.social"));
+    assert_eq!(note.mentions, vec!["did:plc:bob456".to_string()]);
+    assert_eq!(note.reply_id.as_deref(), Some("at://did:plc:bob456/app.bsky.feed.post/3lgparent"));
+    assert_eq!(note.note_types, vec![NoteType::Reply]);
+}
+
+#[test]
+fn deletes_and_other_events() {
+    let options = ParseOptions::default();
+    let deleted = r#"{"did":"did:plc:abc123","kind":"commit","commit":{"operation":"delete","collection":"app.bsky.feed.post","rkey":"3lgpost000001"}}"#;
+    assert!(matches!(
+        parse_event(deleted, &options).unwrap(),
+        TimelineEvent::Deleted { note_id } if note_id == "did:plc:abc123/3lgpost000001"
+    ));
+
+    let like = r#"{"did":"did:plc:abc123","kind":"commit","commit":{"operation":"create","collection":"app.bsky.feed.like","rkey":"x"}}"#;
+    assert!(matches!(parse_event(like, &options).unwrap(), TimelineEvent::Other { .. }));
+    let identity = r#"{"did":"did:plc:abc123","kind":"identity","identity":{"handle":"alice.bsky.social"}}"#;
+    assert!(matches!(parse_event(identity, &options).unwrap(), TimelineEvent::Other { .. }));
+    assert!(parse_event(r#"{"kind":"commit"}"#, &options).is_err());
+}
@@ -0,0 +1,35 @@
+use misskey_post_viewer::format::{render_comment, CommentFields, DEFAULT_COMMENT_FORMAT};
+
+fn fields<'a>() -> CommentFields<'a> {
+    CommentFields {
+        account: "main",
+        name: "Alice",
+        user: "alice",
+        host: None,
+        text: "hello",
+        renote: None,
+    }
+}
+
+#[test]
+fn default_format_matches_legacy_output() {
+    assert_eq!(render_comment(DEFAULT_COMMENT_FORMAT, &fields()), "[main] Alice(@alice): hello");
+
+    let remote = CommentFields { host: Some("remote.example"), ..fields() };
+    assert_eq!(render_comment(DEFAULT_COMMENT_FORMAT, &remote), "[main] Alice(@alice@remote.example): hello");
+
+    let renote = CommentFields { renote: Some(("Bob", "bob", "")), ..fields() };
+    assert_eq!(render_comment(DEFAULT_COMMENT_FORMAT, &renote), "[main] Alice(@alice): RT: Bob(@bob): hello");
+}
+
+#[test]
+fn custom_format_and_unknown_placeholders() {
+    assert_eq!(render_comment("{name}: {text}", &fields()), "Alice: hello");
+    assert_eq!(render_comment("{unknown} {text", &fields()), "{unknown} {text");
+}
+
+#[test]
+fn values_are_not_expanded_again() {
+    let tricky = CommentFields { text: "{name}", ..fields() };
+    assert_eq!(render_comment("{text}", &tricky), "{name}");
+}
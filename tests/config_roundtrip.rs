@@ -0,0 +1,270 @@
+use misskey_post_viewer::{Account, AppConfig, CwMode, EmojiMode, FIREHOSE_SAMPLE_RATE_LIMIT, HighlightRule, MfmMode, NoteType, RendererChoice, SoundTrigger, SourceType, StreamLayout, Theme, TimelineType, TimestampPosition, UsernameMode, ZOrderMode};
+
+fn temp_config_path(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("misskey_post_viewer_{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir.join("config.toml")
+}
+
+fn load(path: &std::path::Path) -> AppConfig {
+    let settings = config::Config::builder()
+        .add_source(config::File::from(path.to_path_buf()))
+        .build()
+        .expect("saved config should be valid TOML");
+    let mut config: AppConfig = settings.try_deserialize().expect("saved config should deserialize");
+    for account in &mut config.accounts {
+        account.restore_token();
+    }
+    config
+}
+
+#[test]
+fn save_and_reload_round_trip() {
+    let mut config = AppConfig {
+        accounts: vec![
+            Account::new("main".to_string(), "misskey.io".to_string(), Some("token-abc123".to_string()), TimelineType::Home, true, [255, 200, 0]),
+            Account::new("sub \"quoted\"".to_string(), "misskey.example".to_string(), None, TimelineType::Global, false, [0, 128, 255]),
+            Account::new("local".to_string(), "local.example".to_string(), Some("日本語トークン".to_string()), TimelineType::Local, true, [255, 255, 255]),
+        ],
+        active_account_index: 2,
+        debug: true,
+        fallback_font: Some("C:\\Windows\\Fonts\\meiryo.ttc".to_string()),
+        highlight_rules: vec![HighlightRule {
+            pattern: "rust".to_string(),
+            color: Some([255, 0, 0]),
+            scale: 1.5,
+            speed_multiplier: 0.5,
+        }],
+        highlight_mentions: true,
+        comment_format: "{name}: {text}".to_string(),
+        show_username: UsernameMode::Above,
+        color_jitter: 16,
+        length_speed_factor: 0.25,
+        max_lines: 3,
+        normalize_whitespace: false,
+        render_mfm: MfmMode::Render,
+        remove_deleted: true,
+        apply_edits: true,
+        min_gap_px: 64.0,
+        max_concurrent_downloads: 8,
+        download_timeout_secs: 30,
+        emoji_mode: EmojiMode::Shortcode,
+        high_contrast: true,
+        high_contrast_color: [255, 255, 0],
+        max_lifetime_secs: Some(12.5),
+        user_agent: Some("MyViewer/1.0 (+https://example.com)".to_string()),
+        extra_root_cert: Some("C:\\certs\\homelab-ca.pem".to_string()),
+        show_instance_icon: true,
+        show_avatars: true,
+        hidden_note_types: vec![NoteType::Poll, NoteType::EmojiOnly],
+        stats_window_mins: 15,
+        startup_delay_secs: 10,
+        renderer: RendererChoice::Wgpu,
+        target_fps: 30,
+        max_reconnect_attempts: Some(5),
+        layout: StreamLayout::SplitByAccount,
+        width_based_speed: true,
+        show_counts: true,
+        sound_triggers: vec![SoundTrigger { pattern: "!ding".to_string(), sound: "C:\\sounds\\ding.wav".into() }],
+        sound_cooldown_secs: 1.5,
+        sync_server_blocks: true,
+        emoji_anim_speed: 0.5,
+        persist_state: true,
+        show_timestamp: true,
+        timestamp_format: "%m/%d %H:%M".to_string(),
+        timestamp_position: TimestampPosition::Suffix,
+        max_emoji_width: Some(120.0),
+        enable_http_source: Some(8931),
+        http_source_secret: Some("s3cret \"quoted\"".to_string()),
+        max_emoji_per_comment: 12,
+        focus_host: Some("misskey.io".to_string()),
+        clear_hotkey: Some("ctrl+shift+KeyX".to_string()),
+        shadow_color: [255, 255, 255, 128],
+        shadow_offset: [-1.5, 3.0],
+        text_background: Some([0, 0, 0, 96]),
+        emoji_scale: 1.5,
+        resolve_renote_emojis: false,
+        quote_chain_depth: 2,
+        cw_mode: CwMode::Both,
+        hide_idle_overlay: true,
+        anonymize: true,
+        anonymize_salt: "0123456789abcdef".to_string(),
+        active_profile: Some("配信用".to_string()),
+        z_order_mode: ZOrderMode::OldestOnTop,
+    };
+
+    config.accounts[1].show_replies = false;
+    config.accounts[2].mark_replies = true;
+    config.accounts[0].hide_pure_renotes = true;
+    config.accounts[1].server_hide_renotes = true;
+    config.accounts[1].skip_backfill = true;
+    config.accounts[1].source = SourceType::Mastodon;
+    config.accounts[1].sample_rate = 0.25;
+    config.accounts[1].secure = false;
+    config.accounts[1].danger_accept_invalid_certs = true;
+    config.accounts[2].channel_params = serde_json::json!({"withReplies": true, "withFiles": false, "limit": 10});
+
+    let path = temp_config_path("roundtrip");
+    config.save_to(&path).unwrap();
+    let loaded = load(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+    assert_eq!(loaded.accounts.len(), config.accounts.len());
+    for (loaded, original) in loaded.accounts.iter().zip(&config.accounts) {
+        assert_eq!(loaded.name, original.name);
+        assert_eq!(loaded.host, original.host);
+        assert_eq!(loaded.token, original.token, "token should survive obfuscation");
+        assert_eq!(loaded.timeline, original.timeline);
+        assert_eq!(loaded.enabled, original.enabled);
+        assert_eq!(loaded.text_color, original.text_color);
+        assert_eq!(loaded.show_replies, original.show_replies);
+        assert_eq!(loaded.mark_replies, original.mark_replies);
+        assert_eq!(loaded.hide_pure_renotes, original.hide_pure_renotes);
+        assert_eq!(loaded.server_hide_renotes, original.server_hide_renotes);
+        assert_eq!(loaded.skip_backfill, original.skip_backfill);
+        assert_eq!(loaded.source, original.source);
+        assert_eq!(loaded.sample_rate, original.sample_rate);
+        assert_eq!(loaded.secure, original.secure);
+        assert_eq!(loaded.danger_accept_invalid_certs, original.danger_accept_invalid_certs);
+        assert_eq!(loaded.subscribe_params(), original.subscribe_params());
+    }
+
+    assert_eq!(loaded.active_account_index, config.active_account_index);
+    assert_eq!(loaded.debug, config.debug);
+    assert_eq!(loaded.fallback_font, config.fallback_font);
+    assert_eq!(loaded.highlight_rules, config.highlight_rules);
+    assert_eq!(loaded.highlight_mentions, config.highlight_mentions);
+    assert_eq!(loaded.comment_format, config.comment_format);
+    assert_eq!(loaded.show_username, config.show_username);
+    assert_eq!(loaded.color_jitter, config.color_jitter);
+    assert_eq!(loaded.length_speed_factor, config.length_speed_factor);
+    assert_eq!(loaded.max_lines, config.max_lines);
+    assert_eq!(loaded.normalize_whitespace, config.normalize_whitespace);
+    assert_eq!(loaded.render_mfm, config.render_mfm);
+    assert_eq!(loaded.remove_deleted, config.remove_deleted);
+    assert_eq!(loaded.apply_edits, config.apply_edits);
+    assert_eq!(loaded.min_gap_px, config.min_gap_px);
+    assert_eq!(loaded.max_concurrent_downloads, config.max_concurrent_downloads);
+    assert_eq!(loaded.download_timeout_secs, config.download_timeout_secs);
+    assert_eq!(loaded.emoji_mode, config.emoji_mode);
+    assert_eq!(loaded.high_contrast, config.high_contrast);
+    assert_eq!(loaded.high_contrast_color, config.high_contrast_color);
+    assert_eq!(loaded.max_lifetime_secs, config.max_lifetime_secs);
+    assert_eq!(loaded.user_agent, config.user_agent);
+    assert_eq!(loaded.extra_root_cert, config.extra_root_cert);
+    assert_eq!(loaded.show_instance_icon, config.show_instance_icon);
+    assert_eq!(loaded.show_avatars, config.show_avatars);
+    assert_eq!(loaded.hidden_note_types, config.hidden_note_types);
+    assert_eq!(loaded.stats_window_mins, config.stats_window_mins);
+    assert_eq!(loaded.startup_delay_secs, config.startup_delay_secs);
+    assert_eq!(loaded.renderer, config.renderer);
+    assert_eq!(loaded.target_fps, config.target_fps);
+    assert_eq!(loaded.max_reconnect_attempts, config.max_reconnect_attempts);
+    assert_eq!(loaded.layout, config.layout);
+    assert_eq!(loaded.width_based_speed, config.width_based_speed);
+    assert_eq!(loaded.show_counts, config.show_counts);
+    assert_eq!(loaded.sound_triggers, config.sound_triggers);
+    assert_eq!(loaded.sound_cooldown_secs, config.sound_cooldown_secs);
+    assert_eq!(loaded.sync_server_blocks, config.sync_server_blocks);
+    assert_eq!(loaded.emoji_anim_speed, config.emoji_anim_speed);
+    assert_eq!(loaded.persist_state, config.persist_state);
+    assert_eq!(loaded.show_timestamp, config.show_timestamp);
+    assert_eq!(loaded.timestamp_format, config.timestamp_format);
+    assert_eq!(loaded.timestamp_position, config.timestamp_position);
+    assert_eq!(loaded.max_emoji_width, config.max_emoji_width);
+    assert_eq!(loaded.enable_http_source, config.enable_http_source);
+    assert_eq!(loaded.http_source_secret, config.http_source_secret);
+    assert_eq!(loaded.max_emoji_per_comment, config.max_emoji_per_comment);
+    assert_eq!(loaded.focus_host, config.focus_host);
+    assert_eq!(loaded.clear_hotkey, config.clear_hotkey);
+    assert_eq!(loaded.shadow_color, config.shadow_color);
+    assert_eq!(loaded.shadow_offset, config.shadow_offset);
+    assert_eq!(loaded.text_background, config.text_background);
+    assert_eq!(loaded.emoji_scale, config.emoji_scale);
+    assert_eq!(loaded.resolve_renote_emojis, config.resolve_renote_emojis);
+    assert_eq!(loaded.quote_chain_depth, config.quote_chain_depth);
+    assert_eq!(loaded.cw_mode, config.cw_mode);
+    assert_eq!(loaded.hide_idle_overlay, config.hide_idle_overlay);
+    assert_eq!(loaded.anonymize, config.anonymize);
+    assert_eq!(loaded.anonymize_salt, config.anonymize_salt);
+    assert_eq!(loaded.active_profile, config.active_profile);
+    assert_eq!(loaded.z_order_mode, config.z_order_mode);
+}
+
+#[test]
+fn defaults_round_trip() {
+    let config = AppConfig::default();
+
+    let path = temp_config_path("defaults");
+    config.save_to(&path).unwrap();
+    let loaded = load(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+    assert!(loaded.accounts.is_empty());
+    assert_eq!(loaded.fallback_font, None);
+    assert_eq!(loaded.active_profile, None);
+    assert_eq!(loaded.comment_format, config.comment_format);
+    assert!(loaded.normalize_whitespace);
+}
+
+#[test]
+fn theme_writes_the_underlying_fields() {
+    let mut config = AppConfig {
+        accounts: vec![
+            Account::new("a".to_string(), "misskey.io".to_string(), None, TimelineType::Local, true, [1, 2, 3]),
+            Account::new("b".to_string(), "misskey.io".to_string(), None, TimelineType::Local, true, [4, 5, 6]),
+        ],
+        high_contrast: true,
+        ..Default::default()
+    };
+    config.apply_theme(Theme::ChatBar);
+    assert!(config.text_background.is_some());
+    assert!(!config.high_contrast);
+    assert!(config.accounts.iter().all(|a| a.text_color == [255, 255, 255]));
+
+    // 別のテーマにすると帯は消え、アカウントごとに色が分かれる
+    config.apply_theme(Theme::Neon);
+    assert_eq!(config.text_background, None);
+    assert_ne!(config.accounts[0].text_color, config.accounts[1].text_color);
+
+    // 適用した後は普通の設定として保存される
+    let path = temp_config_path("theme");
+    config.save_to(&path).unwrap();
+    let loaded = load(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    assert_eq!(loaded.shadow_color, config.shadow_color);
+    assert_eq!(loaded.accounts[1].text_color, config.accounts[1].text_color);
+}
+
+#[test]
+fn server_hide_renotes_overrides_channel_params() {
+    let mut account = Account::new("a".to_string(), "misskey.io".to_string(), None, TimelineType::Local, true, [255, 255, 255]);
+    assert_eq!(account.subscribe_params(), serde_json::json!({}));
+
+    account.set_channel_param("withReplies", Some(true));
+    account.set_channel_param("withRenotes", Some(true));
+    account.server_hide_renotes = true;
+    assert_eq!(account.subscribe_params(), serde_json::json!({"withReplies": true, "withRenotes": false}));
+
+    // 既定に戻したパラメータは送らない
+    account.set_channel_param("withReplies", None);
+    account.server_hide_renotes = false;
+    assert_eq!(account.subscribe_params(), serde_json::json!({"withRenotes": true}));
+}
+
+#[test]
+fn bluesky_firehose_is_sampled_unless_dids_are_wanted() {
+    let mut account = Account::new("bsky".to_string(), "jetstream2.us-east.bsky.network".to_string(), None, TimelineType::Global, true, [255, 255, 255]);
+    account.source = SourceType::Bluesky;
+    assert!(account.wanted_dids().is_empty());
+    assert_eq!(account.effective_sample_rate(), FIREHOSE_SAMPLE_RATE_LIMIT);
+
+    account.channel_params = serde_json::json!({"wantedDids": ["did:plc:abc", "", 1]});
+    assert_eq!(account.wanted_dids(), vec!["did:plc:abc".to_string()]);
+    assert_eq!(account.effective_sample_rate(), 1.0);
+
+    // Misskeyなどは設定した割合のまま
+    account.source = SourceType::Misskey;
+    account.channel_params = serde_json::Value::Null;
+    assert_eq!(account.effective_sample_rate(), 1.0);
+}
@@ -0,0 +1,36 @@
+use misskey_post_viewer::emoji::AnimatedEmoji;
+
+fn animation(frame_durations: &[u32]) -> AnimatedEmoji {
+    AnimatedEmoji {
+        frames: Vec::new(),
+        frame_durations: frame_durations.to_vec(),
+        textures: Vec::new(),
+        current_frame: 0,
+        elapsed_ms: 0,
+    }
+}
+
+#[test]
+fn small_steps_advance_one_frame_at_a_time() {
+    let mut anim = animation(&[100, 100, 100]);
+    anim.advance(60);
+    assert_eq!(anim.current_frame, 0);
+    anim.advance(60);
+    assert_eq!((anim.current_frame, anim.elapsed_ms), (1, 20));
+}
+
+#[test]
+fn large_step_advances_several_frames() {
+    let mut anim = animation(&[100, 50, 200]);
+    // 100 + 50 を越えて3フレーム目の途中まで
+    anim.advance(180);
+    assert_eq!((anim.current_frame, anim.elapsed_ms), (2, 30));
+}
+
+#[test]
+fn step_longer_than_loop_wraps_around() {
+    let mut anim = animation(&[100, 50, 200]);
+    // 1周(350ms) + 120ms
+    anim.advance(470);
+    assert_eq!((anim.current_frame, anim.elapsed_ms), (1, 20));
+}
@@ -0,0 +1,58 @@
+use misskey_post_viewer::{parse_emoji_catalog, parse_emoji_list, EmojiInfo};
+use serde_json::json;
+
+fn emoji(name: &str, url: &str) -> EmojiInfo {
+    EmojiInfo {
+        name: name.to_string(),
+        url: url.to_string(),
+    }
+}
+
+#[test]
+fn emoji_map_form() {
+    let value = json!({
+        "blobcat": "https://example.com/blobcat.png",
+        "misskey": "https://example.com/misskey.png",
+    });
+
+    let mut emojis = parse_emoji_list(&value);
+    emojis.sort_by(|a, b| a.name.cmp(&b.name));
+    assert_eq!(emojis, vec![
+        emoji("blobcat", "https://example.com/blobcat.png"),
+        emoji("misskey", "https://example.com/misskey.png"),
+    ]);
+}
+
+#[test]
+fn emoji_array_form() {
+    let value = json!([
+        { "name": "blobcat", "url": "https://example.com/blobcat.png" },
+        { "name": ":misskey:", "url": "https://example.com/misskey.png" },
+        { "name": "no_url" },
+        "garbage",
+    ]);
+
+    assert_eq!(parse_emoji_list(&value), vec![
+        emoji("blobcat", "https://example.com/blobcat.png"),
+        emoji("misskey", "https://example.com/misskey.png"),
+    ]);
+}
+
+#[test]
+fn emoji_unexpected_form() {
+    assert!(parse_emoji_list(&json!(null)).is_empty());
+    assert!(parse_emoji_list(&json!("blobcat")).is_empty());
+}
+
+#[test]
+fn instance_emoji_catalog_is_not_capped() {
+    let emojis: Vec<_> = (0..1000)
+        .map(|i| json!({ "name": format!("e{}", i), "url": format!("https://example.com/{}.png", i), "aliases": [], "category": null }))
+        .chain([json!({ "name": "", "url": "https://example.com/empty.png" })])
+        .collect();
+
+    let catalog = parse_emoji_catalog(&json!({ "emojis": emojis }));
+    assert_eq!(catalog.len(), 1000);
+    assert_eq!(catalog[0], emoji("e0", "https://example.com/0.png"));
+    assert!(parse_emoji_catalog(&json!({})).is_empty());
+}
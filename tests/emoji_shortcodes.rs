@@ -0,0 +1,81 @@
+use misskey_post_viewer::segment::{first_emojis, limit_emoji, segment_text, shortcode_names, split_emoji_host, strip_emoji_shortcodes, TextSegment};
+use misskey_post_viewer::EmojiInfo;
+
+#[test]
+fn removes_shortcodes() {
+    assert_eq!(strip_emoji_shortcodes("こんにちは:wave:です:blob-cat_2:"), "こんにちはです");
+}
+
+#[test]
+fn keeps_unclosed_colons() {
+    assert_eq!(strip_emoji_shortcodes("時刻 12:30 です: ok"), "時刻 12:30 です: ok");
+    assert_eq!(strip_emoji_shortcodes("::a:"), ":");
+}
+
+fn emoji(name: &str) -> EmojiInfo {
+    EmojiInfo { name: name.to_string(), url: format!("https://misskey.example/emoji/{}.png", name) }
+}
+
+#[test]
+fn excess_emoji_become_shortcode_text() {
+    let emojis = vec![emoji("a"), emoji("b")];
+    let mut remaining = 2;
+    let first = limit_emoji(segment_text("x:a::b:y", &emojis), &mut remaining);
+    assert_eq!(first, vec![
+        TextSegment::Text("x".to_string()),
+        TextSegment::Emoji(emoji("a")),
+        TextSegment::Emoji(emoji("b")),
+        TextSegment::Text("y".to_string()),
+    ]);
+    // 残りの数は次の断片に持ち越す
+    let second = limit_emoji(segment_text(":a: z :b:", &emojis), &mut remaining);
+    assert_eq!(second, vec![TextSegment::Text(":a: z :b:".to_string())]);
+}
+
+#[test]
+fn keeps_only_the_first_emoji_in_text_order() {
+    let emojis = vec![emoji("a"), emoji("b"), emoji("c"), emoji("unused")];
+    assert_eq!(first_emojis(":c::a::c::b:", &emojis, 2), vec![emoji("c"), emoji("a")]);
+    assert_eq!(first_emojis(":c:", &emojis, 0), emojis);
+}
+
+#[test]
+fn remote_emoji_keep_their_host() {
+    let emojis = [emoji("blob@remote.example"), emoji("wave")];
+    assert_eq!(
+        segment_text("hi :blob@remote.example: :wave@.: :blob:", &emojis),
+        vec![
+            TextSegment::Text("hi ".to_string()),
+            TextSegment::Emoji(emoji("blob@remote.example")),
+            TextSegment::Text(" ".to_string()),
+            // `@.`はこのサーバーの絵文字
+            TextSegment::Emoji(emoji("wave")),
+            TextSegment::Text(" :blob:".to_string()),
+        ]
+    );
+    assert_eq!(strip_emoji_shortcodes("a:blob@remote.example:b :x@: c"), "ab :x@: c");
+
+    assert_eq!(split_emoji_host("blob@remote.example"), ("blob", Some("remote.example")));
+    assert_eq!(split_emoji_host("blob@."), ("blob", None));
+    assert_eq!(split_emoji_host("blob"), ("blob", None));
+}
+
+#[test]
+fn non_ascii_emoji_names_do_not_skip_text() {
+    let emojis = [emoji("ねこ")];
+    assert_eq!(
+        segment_text(":ねこ:です", &emojis),
+        vec![TextSegment::Emoji(emoji("ねこ")), TextSegment::Text("です".to_string())]
+    );
+}
+
+#[test]
+fn shortcode_names_follow_the_segment_rules() {
+    assert_eq!(
+        shortcode_names("12:30 :ねこ: :blob@remote.example: :wave@.: :x@: :blob-cat_2:"),
+        ["ねこ", "blob@remote.example", "wave@.", "blob-cat_2"]
+    );
+    // 名前として拾ったものは`segment_text`でも絵文字になる
+    let emojis = [emoji("ねこ")];
+    assert_eq!(segment_text(":ねこ:", &emojis), vec![TextSegment::Emoji(emoji("ねこ"))]);
+}
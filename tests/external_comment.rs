@@ -0,0 +1,43 @@
+use misskey_post_viewer::push::{parse_color, ExternalComment};
+
+#[test]
+fn parses_hex_colors() {
+    assert_eq!(parse_color("#ff8800"), Some([255, 136, 0]));
+    assert_eq!(parse_color("00FF7f"), Some([0, 255, 127]));
+    assert_eq!(parse_color("#fff"), None);
+    assert_eq!(parse_color("#gg0000"), None);
+}
+
+#[test]
+fn deserializes_json_payloads() {
+    let comment: ExternalComment = serde_json::from_str(r##"{"name": "Bot", "text": "hello", "color": "#ff0000"}"##).unwrap();
+    assert_eq!(comment.name.as_deref(), Some("Bot"));
+    assert_eq!(comment.text, "hello");
+    assert_eq!(comment.color, Some([255, 0, 0]));
+
+    let comment: ExternalComment = serde_json::from_str(r#"{"text": "hi", "color": [1, 2, 3]}"#).unwrap();
+    assert_eq!(comment.name, None);
+    assert_eq!(comment.color, Some([1, 2, 3]));
+    assert!(comment.emojis.is_empty());
+
+    assert!(serde_json::from_str::<ExternalComment>(r#"{"text": "hi", "color": "red"}"#).is_err());
+    assert!(serde_json::from_str::<ExternalComment>(r#"{"name": "Bot"}"#).is_err());
+}
+
+#[test]
+fn stdin_lines_are_plain_text_or_json() {
+    let plain = ExternalComment::from_line("  hello world \n").unwrap();
+    assert_eq!(plain.text, "hello world");
+    assert_eq!(plain.name, None);
+
+    let json = ExternalComment::from_line(r##"{"name": "ci", "text": "build passed", "color": "#00ff00"}"##).unwrap();
+    assert_eq!(json.name.as_deref(), Some("ci"));
+    assert_eq!(json.text, "build passed");
+    assert_eq!(json.color, Some([0, 255, 0]));
+
+    // JSONとして読めなければそのまま本文にする
+    let broken = ExternalComment::from_line("{not json}").unwrap();
+    assert_eq!(broken.text, "{not json}");
+
+    assert!(ExternalComment::from_line("   ").is_none());
+}
@@ -0,0 +1,60 @@
+use misskey_post_viewer::{parse_message, EmojiInfo, NoteCounts, ParseOptions, ParsedNote};
+
+fn parse_fixture(json: &str) -> ParsedNote {
+    parse_message(json, &ParseOptions::default())
+        .expect("fixture should parse")
+        .expect("fixture should be a note")
+}
+
+fn emoji(name: &str, url: &str) -> EmojiInfo {
+    EmojiInfo {
+        name: name.to_string(),
+        url: url.to_string(),
+    }
+}
+
+#[test]
+fn misskey_note_with_emoji_map() {
+    let note = parse_fixture(include_str!("fixtures/misskey_note.json"));
+
+    assert_eq!(note.username, "misskey_user");
+    assert_eq!(note.user_host, None);
+    assert_eq!(note.text.as_deref(), Some("こんにちは :misskey:"));
+    assert!(note.emojis.contains(&emoji("misskey", "https://misskey.example/emoji/misskey.png")));
+    assert!(note.emojis.contains(&emoji("blobcat", "https://misskey.example/emoji/blobcat.webp")));
+}
+
+#[test]
+fn firefish_note_with_emoji_array() {
+    let note = parse_fixture(include_str!("fixtures/firefish_note.json"));
+
+    assert_eq!(note.username, "fish");
+    assert_eq!(note.user_host.as_deref(), Some("firefish.example"));
+    assert!(note.emojis.contains(&emoji("neofox", "https://firefish.example/emoji/neofox.png")));
+    assert!(note.emojis.contains(&emoji("ff", "https://firefish.example/emoji/ff.png")));
+}
+
+#[test]
+fn sharkey_note_with_user_emoji_only() {
+    let note = parse_fixture(include_str!("fixtures/sharkey_note.json"));
+
+    assert_eq!(note.text.as_deref(), Some("blåhaj time"));
+    assert_eq!(note.emojis, vec![emoji("shark", "https://sharkey.example/emoji/shark.png")]);
+    assert_eq!(note.mentions, vec!["a1b2c3999".to_string()]);
+    // renoteCountなどがなくても0として扱う
+    assert_eq!(note.counts, NoteCounts { reactions: 3, renotes: 0, replies: 0 });
+}
+
+#[test]
+fn iceshrimp_renote_with_null_fields() {
+    let note = parse_fixture(include_str!("fixtures/iceshrimp_note.json"));
+
+    assert_eq!(note.name, "Unknown");
+    assert_eq!(note.text, None);
+    assert!(note.emojis.is_empty());
+
+    let renote = note.renote.expect("renote should be parsed");
+    assert_eq!(renote.username, "orig");
+    assert_eq!(renote.text.as_deref(), Some("original post"));
+    assert_eq!(renote.emojis, vec![emoji("shrimp", "https://iceshrimp.example/emoji/shrimp.png")]);
+}
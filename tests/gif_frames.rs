@@ -0,0 +1,26 @@
+use image::Rgba;
+use misskey_post_viewer::emoji::decode_gif_frames;
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+const CLEAR: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+// 4x4の赤い全体フレーム → (1,1)に2x2の青（表示後に背景へ戻す） → (0,0)に1x1の緑
+#[test]
+fn partial_frames_are_composited_over_the_previous_frame() {
+    let frames = decode_gif_frames(include_bytes!("fixtures/partial_frames.gif")).unwrap();
+    assert_eq!(frames.len(), 3);
+    assert!(frames.iter().all(|(img, _)| img.dimensions() == (4, 4)));
+    assert_eq!(frames.iter().map(|(_, ms)| *ms).collect::<Vec<_>>(), [100, 100, 200]);
+
+    let (second, _) = &frames[1];
+    assert_eq!(*second.get_pixel(1, 1), BLUE);
+    assert_eq!(*second.get_pixel(0, 0), RED, "pixels outside a partial frame keep the previous frame");
+
+    // 2枚目は背景に戻すので、3枚目ではその部分だけが透明になる
+    let (third, _) = &frames[2];
+    assert_eq!(*third.get_pixel(0, 0), GREEN);
+    assert_eq!(*third.get_pixel(1, 1), CLEAR);
+    assert_eq!(*third.get_pixel(3, 3), RED);
+}
@@ -0,0 +1,58 @@
+use misskey_post_viewer::lanes::{allocate_lane, LaneLayout, LaneOccupant};
+
+fn layout(min_gap_px: f32) -> LaneLayout {
+    LaneLayout {
+        area_top: 50.0,
+        area_bottom: 200.0,
+        lane_height: 30.0,
+        spawn_x: 1000.0,
+        min_gap_px,
+    }
+}
+
+fn occupant(top: f32, tail_x: f32) -> LaneOccupant {
+    LaneOccupant { top, bottom: top + 30.0, tail_x }
+}
+
+#[test]
+fn empty_screen_uses_top_lane() {
+    assert_eq!(allocate_lane(&layout(0.0), &[], 30.0), 50.0);
+}
+
+#[test]
+fn lane_is_reused_only_after_gap() {
+    // 右端が出現位置から50px抜けたところ
+    let occupants = [occupant(50.0, 950.0)];
+    assert_eq!(allocate_lane(&layout(40.0), &occupants, 30.0), 50.0);
+    assert_eq!(allocate_lane(&layout(80.0), &occupants, 30.0), 80.0);
+}
+
+#[test]
+fn tall_comment_needs_every_overlapping_lane_free() {
+    let occupants = [occupant(80.0, 1200.0)];
+    // 2レーン分の高さなので、50と80をまたぐ位置は使えない
+    assert_eq!(allocate_lane(&layout(0.0), &occupants, 60.0), 110.0);
+}
+
+#[test]
+fn full_screen_picks_lane_that_clears_first() {
+    let occupants: Vec<_> = (0..5)
+        .map(|lane| occupant(50.0 + lane as f32 * 30.0, if lane == 3 { 1010.0 } else { 1500.0 }))
+        .collect();
+    assert_eq!(allocate_lane(&layout(0.0), &occupants, 30.0), 140.0);
+}
+
+#[test]
+fn band_splits_area_evenly() {
+    let band = layout(0.0).band(1, 3);
+    assert_eq!((band.area_top, band.area_bottom), (100.0, 150.0));
+    // 帯の中で配置され、ほかの帯のコメントには影響されない
+    let occupants = [occupant(50.0, 1500.0)];
+    assert_eq!(allocate_lane(&band, &occupants, 30.0), 100.0);
+}
+
+#[test]
+fn band_out_of_range_uses_whole_area() {
+    let band = layout(0.0).band(3, 3);
+    assert_eq!((band.area_top, band.area_bottom), (50.0, 200.0));
+}
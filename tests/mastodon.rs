@@ -0,0 +1,78 @@
+use misskey_post_viewer::mastodon::{html_to_text, parse_event};
+use misskey_post_viewer::{EmojiInfo, NoteCounts, NoteType, ParseOptions, ParsedNote, TimelineEvent};
+
+fn parse_fixture(json: &str) -> ParsedNote {
+    match parse_event(json, &ParseOptions::default()).expect("fixture should parse") {
+        TimelineEvent::Note(note) => *note,
+        other => panic!("fixture should be a status, got {:?}", other),
+    }
+}
+
+#[test]
+fn update_is_normalized_into_a_note() {
+    let note = parse_fixture(include_str!("fixtures/mastodon_update.json"));
+
+    assert_eq!(note.id, "113000000000000001");
+    assert_eq!(note.created_at.as_deref(), Some("2025-02-01T09:00:00.000Z"));
+    assert_eq!(note.user_id.as_deref(), Some("1001"));
+    assert_eq!(note.name, "Alice :wave:");
+    assert_eq!(note.username, "alice");
+    assert_eq!(note.user_host.as_deref(), Some("mastodon.example"));
+    assert_eq!(note.text.as_deref(), Some("Hello & welcome :blobcat:\nsecond line\nnext https://example.com"));
+    assert!(note.cw.is_none());
+    assert_eq!(note.mentions, vec!["2002".to_string()]);
+    assert_eq!(note.counts, NoteCounts { reactions: 3, renotes: 2, replies: 1 });
+    assert!(note.note_types.is_empty());
+    assert_eq!(note.emojis, vec![
+        EmojiInfo { name: "blobcat".to_string(), url: "https://mastodon.example/emoji/blobcat.png".to_string() },
+        EmojiInfo { name: "wave".to_string(), url: "https://mastodon.example/emoji/wave.png".to_string() },
+    ]);
+}
+
+#[test]
+fn reblog_becomes_a_pure_renote() {
+    let note = parse_fixture(include_str!("fixtures/mastodon_reblog.json"));
+    let renote = note.renote.as_deref().expect("reblog should be parsed");
+
+    assert!(note.is_pure_renote());
+    assert_eq!(note.note_types, vec![NoteType::Renote]);
+    // 表示名が空ならusernameを使う
+    assert_eq!(note.name, "carol");
+    assert!(note.user_host.is_none());
+    assert_eq!(renote.id, "113000000000000001");
+    assert_eq!(renote.username, "alice");
+}
+
+#[test]
+fn delete_and_edit_events() {
+    let options = ParseOptions::default();
+    let deleted = parse_event(r#"{"stream":["public"],"event":"delete","payload":"113000000000000001"}"#, &options).unwrap();
+    assert!(matches!(deleted, TimelineEvent::Deleted { note_id } if note_id == "113000000000000001"));
+
+    let edited = r#"{"event":"status.update","payload":"{\"id\":\"42\",\"content\":\"<p>edited</p>\",\"spoiler_text\":\"\",\"account\":{\"id\":\"1\",\"username\":\"a\",\"acct\":\"a\"}}"}"#;
+    match parse_event(edited, &options).unwrap() {
+        TimelineEvent::Updated { note_id, text, cw } => {
+            assert_eq!(note_id, "42");
+            assert_eq!(text.as_deref(), Some("edited"));
+            assert!(cw.is_none());
+        }
+        other => panic!("expected an edit, got {:?}", other),
+    }
+
+    assert!(matches!(parse_event(r#"{"event":"notification","payload":"{}"}"#, &options).unwrap(), TimelineEvent::Other { .. }));
+    assert!(parse_event(r#"{"event":"update"}"#, &options).is_err());
+}
+
+#[test]
+fn html_content_becomes_plain_text() {
+    assert_eq!(html_to_text("<p>a<br/>b</p><p>c</p>"), "a\nb\n\nc");
+    assert_eq!(html_to_text("&lt;b&gt; &#x1F600; &#39;q&#39; &unknown; & x"), "<b> 😀 'q' &unknown; & x");
+}
+
+#[test]
+fn stream_error_is_a_channel_error() {
+    match parse_event(r#"{"error":"Missing access token"}"#, &ParseOptions::default()).unwrap() {
+        TimelineEvent::ChannelError { reason } => assert_eq!(reason, "Missing access token"),
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
@@ -0,0 +1,32 @@
+use misskey_post_viewer::mfm::{parse_mfm_spans, MfmEffect, MfmSpan};
+
+fn span(text: &str, effects: &[MfmEffect]) -> MfmSpan {
+    MfmSpan {
+        text: text.to_string(),
+        effects: effects.to_vec(),
+    }
+}
+
+#[test]
+fn splits_effects_into_spans() {
+    assert_eq!(parse_mfm_spans("a $[shake b] c"), vec![
+        span("a ", &[]),
+        span("b", &[MfmEffect::Shake]),
+        span(" c", &[]),
+    ]);
+}
+
+#[test]
+fn nested_effects_accumulate() {
+    assert_eq!(parse_mfm_spans("$[spin.speed=2s x$[jelly y]]"), vec![
+        span("x", &[MfmEffect::Spin]),
+        span("y", &[MfmEffect::Spin, MfmEffect::Jelly]),
+    ]);
+}
+
+#[test]
+fn unsupported_markup_is_stripped() {
+    assert_eq!(parse_mfm_spans("$[x2 **big**] <plain>$[shake no]</plain>"), vec![
+        span("big $[shake no]", &[]),
+    ]);
+}
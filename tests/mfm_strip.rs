@@ -0,0 +1,40 @@
+use misskey_post_viewer::mfm::strip_mfm;
+
+#[test]
+fn strips_functions() {
+    assert_eq!(strip_mfm("$[shake ぷるぷる]"), "ぷるぷる");
+    assert_eq!(strip_mfm("$[fg.color=f00 赤い]文字"), "赤い文字");
+}
+
+#[test]
+fn strips_nested_functions() {
+    assert_eq!(strip_mfm("$[x2 $[spin.speed=1s くるくる] です]"), "くるくる です");
+    assert_eq!(strip_mfm("$[tada $[jelly $[bounce 3段]]]!"), "3段!");
+}
+
+#[test]
+fn strips_inline_styles_and_tags() {
+    assert_eq!(strip_mfm("**太字** と ~~打消し~~ と ***強調***"), "太字 と 打消し と 強調");
+    assert_eq!(strip_mfm("<center><b>中央</b></center><small>小</small>"), "中央小");
+}
+
+#[test]
+fn strips_links_and_quotes() {
+    assert_eq!(strip_mfm("[Misskey](https://misskey-hub.net) を見て"), "Misskey を見て");
+    assert_eq!(strip_mfm("?[silent](https://example.com)"), "silent");
+    assert_eq!(strip_mfm("> 引用\n返信"), "引用\n返信");
+}
+
+#[test]
+fn keeps_escaped_content() {
+    assert_eq!(strip_mfm("<plain>**そのまま** $[shake x]</plain>"), "**そのまま** $[shake x]");
+    assert_eq!(strip_mfm("`$[spin code]`"), "$[spin code]");
+    assert_eq!(strip_mfm("```rust\nlet x = **y**;\n```"), "let x = **y**;");
+}
+
+#[test]
+fn leaves_malformed_markup_alone() {
+    assert_eq!(strip_mfm("$[shake 閉じてない"), "$[shake 閉じてない");
+    assert_eq!(strip_mfm("$[] と [ただの括弧]"), "$[] と [ただの括弧]");
+    assert_eq!(strip_mfm("2 ** 3 = 8"), "2 ** 3 = 8");
+}
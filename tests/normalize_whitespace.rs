@@ -0,0 +1,30 @@
+use misskey_post_viewer::{normalize_whitespace, parse_message, ParseOptions};
+
+#[test]
+fn collapses_blank_lines_and_crlf() {
+    assert_eq!(normalize_whitespace("a\r\n\r\n\r\nb\r\nc"), "a\nb\nc");
+    assert_eq!(normalize_whitespace("a\r\rb"), "a\nb");
+}
+
+#[test]
+fn trims_tabs_and_trailing_spaces() {
+    assert_eq!(normalize_whitespace("\tindented  \n  b\t"), "indented\nb");
+}
+
+#[test]
+fn drops_leading_and_trailing_empty_lines() {
+    assert_eq!(normalize_whitespace("\n\n  \nhello\n \n"), "hello");
+    assert_eq!(normalize_whitespace(" \n\t\n"), "");
+}
+
+#[test]
+fn parse_option_can_be_disabled() {
+    let json = r#"{"type":"channel","body":{"id":"x","type":"note","body":{"id":"n1","user":{"username":"a"},"text":"  hi \r\n\r\n there ","cw":"\tcw\t"}}}"#;
+
+    let note = parse_message(json, &ParseOptions::default()).unwrap().unwrap();
+    assert_eq!(note.text.as_deref(), Some("hi\nthere"));
+    assert_eq!(note.cw.as_deref(), Some("cw"));
+
+    let raw = parse_message(json, &ParseOptions { normalize_whitespace: false, ..ParseOptions::default() }).unwrap().unwrap();
+    assert_eq!(raw.text.as_deref(), Some("  hi \r\n\r\n there "));
+}
@@ -0,0 +1,269 @@
+use misskey_post_viewer::format::{comment_body, detect_url, display_text, note_text, preview_text, pseudonym, quote_chain, renote_info, truncate_text, MAX_TEXT_CHARS, REPLY_MARKER};
+use misskey_post_viewer::emoji::MAX_NOTE_EMOJIS;
+use misskey_post_viewer::note::{MAX_NOTE_TEXT_CHARS, MAX_RENOTE_DEPTH};
+use misskey_post_viewer::segment::{segment_text, TextSegment, MAX_SEGMENT_CHARS};
+use misskey_post_viewer::{parse_message, parse_note, CwMode, EmojiInfo, EmojiMode, NoteType, ParseOptions, ParsedNote};
+
+fn parse_fixture(json: &str) -> ParsedNote {
+    parse_message(json, &ParseOptions::default())
+        .expect("fixture should parse")
+        .expect("fixture should be a note")
+}
+
+// 既定の設定（CWだけ表示・絵文字は画像・リプライの印なし）でコメントの本文を組み立てる
+fn comment_text(note: &ParsedNote) -> String {
+    comment_body(&note_text(note, CwMode::CwOnly), false, EmojiMode::Image)
+}
+
+#[test]
+fn plain_note() {
+    let note = parse_fixture(include_str!("fixtures/plain_note.json"));
+
+    assert_eq!(note.id, "9yplain001");
+    assert_eq!(note.created_at.as_deref(), Some("2025-02-01T09:00:00.000Z"));
+    assert_eq!(note.user_id.as_deref(), Some("9user0001"));
+    assert_eq!(note.name, "Alice");
+    assert_eq!(note.username, "alice");
+    assert_eq!(note.avatar_url.as_deref(), Some("https://misskey.example/avatar/alice.webp"));
+    assert!(note.renote.is_none());
+    assert!(note.reply_id.is_none());
+    assert!(note.emojis.is_empty());
+    assert!(note.note_types.is_empty());
+    assert_eq!(comment_text(&note), "おはようございます");
+}
+
+#[test]
+fn renote_uses_original_text_truncated() {
+    let note = parse_fixture(include_str!("fixtures/renote.json"));
+    let renote = note.renote.as_deref().expect("renote should be parsed");
+
+    assert!(note.is_pure_renote());
+    assert!(!note.is_quote());
+    assert_eq!(note.note_types, vec![NoteType::Renote]);
+
+    let (name, username, host, text) = renote_info(renote, CwMode::CwOnly);
+    assert_eq!(name, "Carol :star:");
+    assert_eq!(username, "carol");
+    assert_eq!(host, "remote.example");
+    assert_eq!(text.chars().count(), 80 + 3);
+    assert!(text.ends_with("..."));
+    assert_eq!(comment_text(&note), text);
+    assert!(renote.emojis.contains(&EmojiInfo {
+        name: "star".to_string(),
+        url: "https://remote.example/emoji/star.png".to_string(),
+    }));
+}
+
+#[test]
+fn cw_note_shows_cw_only() {
+    let note = parse_fixture(include_str!("fixtures/cw_note.json"));
+
+    assert_eq!(note.cw.as_deref(), Some("映画の感想"));
+    assert_eq!(comment_text(&note), "CW: 映画の感想");
+}
+
+#[test]
+fn emoji_heavy_note_merges_note_and_user_emojis() {
+    let note = parse_fixture(include_str!("fixtures/emoji_heavy_note.json"));
+
+    let mut names: Vec<&str> = note.emojis.iter().map(|e| e.name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, ["blobcat", "blobcat_dance", "neko_wave", "party_parrot", "verified"]);
+
+    // 同名ならノート側の絵文字を優先
+    let blobcat = note.emojis.iter().find(|e| e.name == "blobcat").unwrap();
+    assert_eq!(blobcat.url, "https://emoji.example/emoji/blobcat.png");
+    assert_eq!(comment_text(&note), ":blobcat::blobcat_dance: :party_parrot: :neko_wave:");
+    assert_eq!(note.note_types, vec![NoteType::EmojiOnly]);
+}
+
+#[test]
+fn reply_is_shown_as_plain_note() {
+    let note = parse_fixture(include_str!("fixtures/reply_note.json"));
+
+    assert_eq!(note.username, "frank");
+    assert!(note.renote.is_none());
+    assert_eq!(note.mentions, vec!["9user0001".to_string()]);
+    assert_eq!(note.reply_id.as_deref(), Some("9yplain001"));
+    assert_eq!(note.note_types, vec![NoteType::Reply]);
+    assert_eq!(note.reply.as_ref().map(|r| r.username.as_str()), Some("alice"));
+    assert_eq!(comment_text(&note), "@alice おはよう！");
+    assert_eq!(
+        comment_body(&note_text(&note, CwMode::CwOnly), true, EmojiMode::Image),
+        format!("{}@alice おはよう！", REPLY_MARKER),
+    );
+}
+
+#[test]
+fn hidden_emoji_mode_strips_shortcodes_from_the_body() {
+    let note = parse_fixture(include_str!("fixtures/emoji_heavy_note.json"));
+    let body = comment_body(&note_text(&note, CwMode::CwOnly), false, EmojiMode::Hidden);
+    assert!(!body.contains(':'));
+}
+
+#[test]
+fn long_text_is_truncated() {
+    let text = "あ".repeat(150);
+    let truncated = truncate_text(&text, MAX_TEXT_CHARS);
+    assert_eq!(truncated, format!("{}...", "あ".repeat(100)));
+}
+
+#[test]
+fn renote_with_text_is_quote() {
+    let mut note = parse_fixture(include_str!("fixtures/renote.json"));
+    note.text = Some("これ好き".to_string());
+
+    assert!(note.is_quote());
+    assert!(!note.is_pure_renote());
+    assert_eq!(display_text(note.text.as_deref(), note.cw.as_deref(), CwMode::CwOnly), "これ好き");
+}
+
+#[test]
+fn cw_mode_chooses_what_is_shown() {
+    let (text, cw) = (Some("本文"), Some("ネタバレ"));
+    assert_eq!(display_text(text, cw, CwMode::CwOnly), "CW: ネタバレ");
+    assert_eq!(display_text(text, cw, CwMode::Both), "CW: ネタバレ — 本文");
+    assert_eq!(display_text(text, cw, CwMode::BodyOnly), "本文");
+    // 本文がなければどのモードでもCWを出す
+    assert_eq!(display_text(None, cw, CwMode::BodyOnly), "CW: ネタバレ");
+    assert_eq!(display_text(None, cw, CwMode::Both), "CW: ネタバレ");
+    assert_eq!(display_text(text, None, CwMode::CwOnly), "本文");
+}
+
+#[test]
+fn poll_and_media_only_notes_are_classified() {
+    let json = include_str!("fixtures/plain_note.json")
+        .replace(r#""text":"おはようございます""#, r#""text":null"#)
+        .replace(r#""fileIds":[]"#, r#""fileIds":["9yfile0001"],"poll":{"choices":[],"multiple":false}"#);
+    let note = parse_fixture(&json);
+
+    assert_eq!(note.note_types, vec![NoteType::Poll, NoteType::MediaOnly]);
+}
+
+#[test]
+fn oversized_note_is_bounded() {
+    let emojis: serde_json::Map<String, serde_json::Value> = (0..MAX_NOTE_EMOJIS * 4)
+        .map(|i| (format!("e{}", i), serde_json::Value::from(format!("https://misskey.example/emoji/{}.png", i))))
+        .collect();
+    let note = serde_json::json!({
+        "id": "9yhuge0001",
+        "text": "あ".repeat(MAX_NOTE_TEXT_CHARS * 10),
+        "user": { "name": "Alice", "username": "alice" },
+        "emojis": emojis,
+    });
+    let note = parse_note(&note, &ParseOptions::default()).unwrap();
+
+    assert_eq!(note.text.unwrap().chars().count(), MAX_NOTE_TEXT_CHARS);
+    assert_eq!(note.emojis.len(), MAX_NOTE_EMOJIS);
+}
+
+#[test]
+fn segmentation_stops_at_the_limit() {
+    let emojis = vec![EmojiInfo { name: "blobcat".to_string(), url: "https://misskey.example/emoji/blobcat.png".to_string() }];
+    let text = ":blobcat:".repeat(MAX_SEGMENT_CHARS);
+    let segments = segment_text(&text, &emojis);
+    let emoji_count = segments.iter().filter(|s| matches!(s, TextSegment::Emoji(_))).count();
+    assert_eq!(emoji_count, MAX_SEGMENT_CHARS / ":blobcat:".len());
+
+    // 閉じていない長いショートコードは文字列のまま
+    let text = format!(":{}", "a".repeat(MAX_SEGMENT_CHARS * 10));
+    let segments = segment_text(&text, &emojis);
+    assert_eq!(segments, vec![TextSegment::Text(text.chars().take(MAX_SEGMENT_CHARS).collect())]);
+}
+
+// `depth`段の引用の連鎖（一番奥がquote0、外側に向かって番号が増える）
+fn quote_chain_json(depth: usize) -> serde_json::Value {
+    (0..=depth).fold(serde_json::Value::Null, |inner, i| {
+        serde_json::json!({
+            "id": format!("q{}", i),
+            "user": { "id": format!("u{}", i), "name": format!("User{}", i), "username": format!("user{}", i), "host": null },
+            "text": format!("quote{}", i),
+            "renote": inner,
+        })
+    })
+}
+
+#[test]
+fn quote_chain_follows_nested_renotes() {
+    let note = parse_note(&quote_chain_json(3), &ParseOptions::default()).unwrap();
+    let renote = note.renote.as_deref().unwrap();
+    assert_eq!(renote.text.as_deref(), Some("quote2"));
+
+    let chain = quote_chain(renote, 5, CwMode::CwOnly);
+    let texts: Vec<&str> = chain.iter().map(|(_, _, _, text)| text.as_str()).collect();
+    assert_eq!(texts, ["quote1", "quote0"]);
+    assert_eq!(quote_chain(renote, 1, CwMode::CwOnly).len(), 1);
+    assert!(quote_chain(renote, 0, CwMode::CwOnly).is_empty());
+}
+
+#[test]
+fn deep_quote_chains_are_cut_off() {
+    let note = parse_note(&quote_chain_json(50), &ParseOptions::default()).unwrap();
+    let depth = std::iter::successors(note.renote.as_deref(), |n| n.renote.as_deref()).count();
+    assert_eq!(depth, MAX_RENOTE_DEPTH);
+}
+
+#[test]
+fn deep_reply_chains_are_cut_off() {
+    let json = (0..50).fold(serde_json::Value::Null, |inner, i| {
+        serde_json::json!({
+            "id": format!("r{}", i),
+            "user": { "id": format!("u{}", i), "name": format!("User{}", i), "username": format!("user{}", i), "host": null },
+            "text": format!("reply{}", i),
+            "replyId": if inner.is_null() { serde_json::Value::Null } else { inner["id"].clone() },
+            "reply": inner,
+        })
+    });
+    let note = parse_note(&json, &ParseOptions::default()).unwrap();
+    let depth = std::iter::successors(note.reply.as_deref(), |n| n.reply.as_deref()).count();
+    assert_eq!(depth, MAX_RENOTE_DEPTH);
+}
+
+#[test]
+fn pseudonyms_are_stable_per_user() {
+    let alice = pseudonym("alice", "misskey.io", "salt");
+    assert!(alice.starts_with("User#") && alice.len() == "User#00000000".len());
+    assert_eq!(alice, pseudonym("alice", "Misskey.IO", "salt"));
+    assert_ne!(alice, pseudonym("bob", "misskey.io", "salt"));
+    // 塩が違えば同じユーザーでも別の仮名になる
+    assert_ne!(alice, pseudonym("alice", "misskey.io", "other"));
+}
+
+#[test]
+fn preview_url_comes_from_the_untruncated_text() {
+    let url = "https://example.com/articles/a-rather-long-slug";
+    let text = format!("{} {}", "あ".repeat(90), url);
+    let truncated = truncate_text(&text, MAX_TEXT_CHARS);
+    assert!(!truncated.contains(url), "the URL should straddle the truncation boundary");
+    assert_ne!(detect_url(&truncated).as_deref(), Some(url));
+    assert_eq!(detect_url(&text).as_deref(), Some(url));
+
+    let mut note = parse_fixture(include_str!("fixtures/plain_note.json"));
+    note.text = Some(text.clone());
+    assert!(!comment_text(&note).contains(url));
+    assert_eq!(detect_url(&preview_text(&note, CwMode::CwOnly)).as_deref(), Some(url));
+
+    // 純粋なリノートの本文は先に切り詰められるので、リノート元の本文から探す
+    let mut note = parse_fixture(include_str!("fixtures/renote.json"));
+    note.renote.as_mut().unwrap().text = Some(format!("{} {}", "い".repeat(70), url));
+    assert!(!comment_text(&note).contains(url));
+    assert_eq!(detect_url(&preview_text(&note, CwMode::CwOnly)).as_deref(), Some(url));
+
+    // 引用ならリノート元ではなく本人のコメントから探す
+    note.text = Some("引用です".to_string());
+    assert!(note.is_quote());
+    assert_eq!(preview_text(&note, CwMode::CwOnly), "引用です");
+}
+
+#[test]
+fn preview_text_follows_the_cw_mode() {
+    let url = "https://example.com/spoiler";
+    let mut note = parse_fixture(include_str!("fixtures/cw_note.json"));
+    note.text = Some(format!("ネタバレ {}", url));
+
+    // CWだけ表示するときは隠した本文のURLをプレビューしない
+    assert_eq!(preview_text(&note, CwMode::CwOnly), "CW: 映画の感想");
+    assert_eq!(detect_url(&preview_text(&note, CwMode::CwOnly)), None);
+    assert_eq!(detect_url(&preview_text(&note, CwMode::Both)).as_deref(), Some(url));
+    assert_eq!(detect_url(&preview_text(&note, CwMode::BodyOnly)).as_deref(), Some(url));
+}
@@ -0,0 +1,46 @@
+use std::time::{Duration, Instant};
+
+use misskey_post_viewer::ratelimit::{parse_retry_after, unavailable_backoff, HostBackoff, UNAVAILABLE_AFTER};
+
+#[test]
+fn retry_after_header_sets_the_wait() {
+    let backoff = HostBackoff::new();
+    let now = Instant::now();
+    assert_eq!(backoff.remaining_at("misskey.io", now), None);
+
+    backoff.rate_limited_at("misskey.io", Some(Duration::from_secs(30)), now);
+    assert_eq!(backoff.remaining_at("misskey.io", now + Duration::from_secs(10)), Some(Duration::from_secs(20)));
+    assert_eq!(backoff.remaining_at("misskey.io", now + Duration::from_secs(30)), None);
+    // 他のサーバーには影響しない
+    assert_eq!(backoff.remaining_at("example.com", now), None);
+}
+
+#[test]
+fn backoff_doubles_until_a_request_succeeds() {
+    let backoff = HostBackoff::new();
+    let now = Instant::now();
+    assert_eq!(backoff.rate_limited_at("misskey.io", None, now), Duration::from_secs(5));
+    assert_eq!(backoff.rate_limited_at("misskey.io", None, now), Duration::from_secs(10));
+    assert_eq!(backoff.rate_limited_at("misskey.io", None, now), Duration::from_secs(20));
+
+    backoff.succeeded("misskey.io");
+    assert_eq!(backoff.remaining_at("misskey.io", now), None);
+    assert_eq!(backoff.rate_limited_at("misskey.io", None, now), Duration::from_secs(5));
+}
+
+#[test]
+fn parses_retry_after_seconds() {
+    assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    assert_eq!(parse_retry_after(" 3 "), Some(Duration::from_secs(3)));
+    assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), None);
+}
+
+#[test]
+fn repeated_short_connections_back_off_longer() {
+    assert_eq!(unavailable_backoff(0), None);
+    assert_eq!(unavailable_backoff(UNAVAILABLE_AFTER - 1), None);
+    assert_eq!(unavailable_backoff(UNAVAILABLE_AFTER), Some(Duration::from_secs(30)));
+    assert_eq!(unavailable_backoff(UNAVAILABLE_AFTER + 1), Some(Duration::from_secs(60)));
+    // 上限で止まる
+    assert_eq!(unavailable_backoff(UNAVAILABLE_AFTER + 50), Some(Duration::from_secs(600)));
+}
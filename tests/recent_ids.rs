@@ -0,0 +1,22 @@
+use misskey_post_viewer::RecentIds;
+
+#[test]
+fn rejects_duplicates() {
+    let mut seen = RecentIds::new(4);
+    assert!(seen.insert("misskey.io/a"));
+    assert!(!seen.insert("misskey.io/a"));
+    assert!(seen.insert("misskey.io/b"));
+    assert_eq!(seen.len(), 2);
+}
+
+#[test]
+fn forgets_oldest_when_full() {
+    let mut seen = RecentIds::new(2);
+    assert!(seen.insert("a"));
+    assert!(seen.insert("b"));
+    assert!(seen.insert("c"));
+    assert_eq!(seen.len(), 2);
+    // 一番古い"a"は忘れているので再び通る
+    assert!(seen.insert("a"));
+    assert!(!seen.insert("c"));
+}
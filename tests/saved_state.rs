@@ -0,0 +1,48 @@
+use misskey_post_viewer::{EmojiInfo, SavedComment, SavedState};
+
+fn temp_state_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir()
+        .join(format!("mpv_state_{}_{}", name, std::process::id()))
+        .join("state.json")
+}
+
+#[test]
+fn state_survives_save_and_load() {
+    let state = SavedState {
+        comments: vec![SavedComment {
+            note_id: "9yplain001".to_string(),
+            text: "おはよう :wave:".to_string(),
+            name: "Alice".to_string(),
+            username: "alice".to_string(),
+            user_host: Some("remote.example".to_string()),
+            host: "remote.example".to_string(),
+            account_name: "main".to_string(),
+            account_color: [255, 200, 0],
+            emojis: vec![EmojiInfo {
+                name: "wave".to_string(),
+                url: "https://remote.example/emoji/wave.png".to_string(),
+            }],
+            speed: 5.5,
+            scale: 1.2,
+        }],
+        seen_ids: vec!["misskey.io/a".to_string(), "misskey.io/b".to_string()],
+    };
+
+    let path = temp_state_path("roundtrip");
+    state.save(&path).unwrap();
+    let loaded = SavedState::load(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+    assert_eq!(loaded, Some(state));
+}
+
+#[test]
+fn broken_state_file_is_ignored() {
+    let path = temp_state_path("broken");
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{ not json").unwrap();
+    let loaded = SavedState::load(&path);
+    std::fs::remove_dir_all(path.parent().unwrap()).ok();
+
+    assert_eq!(loaded, None);
+}
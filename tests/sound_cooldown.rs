@@ -0,0 +1,33 @@
+use misskey_post_viewer::{SoundCooldown, SoundTrigger};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+fn trigger(pattern: &str, sound: &str) -> SoundTrigger {
+    SoundTrigger {
+        pattern: pattern.to_string(),
+        sound: sound.into(),
+    }
+}
+
+#[test]
+fn matching_keyword_plays_mapped_sound() {
+    let triggers = [trigger("!ding", "ding.wav"), trigger("!boo", "boo.wav")];
+    let mut cooldown = SoundCooldown::new(Duration::from_secs(3));
+    let now = Instant::now();
+
+    assert_eq!(cooldown.trigger_at(&triggers, "こんにちは !BOO", now), Some(Path::new("boo.wav")));
+    assert_eq!(cooldown.trigger_at(&triggers, "こんにちは", now), None);
+}
+
+#[test]
+fn same_sound_waits_for_cooldown() {
+    let triggers = [trigger("!ding", "ding.wav"), trigger("!boo", "boo.wav")];
+    let mut cooldown = SoundCooldown::new(Duration::from_secs(3));
+    let start = Instant::now();
+
+    assert!(cooldown.trigger_at(&triggers, "!ding", start).is_some());
+    assert!(cooldown.trigger_at(&triggers, "!ding", start + Duration::from_secs(1)).is_none());
+    // 別の効果音は待たずに鳴る
+    assert!(cooldown.trigger_at(&triggers, "!boo", start + Duration::from_secs(1)).is_some());
+    assert!(cooldown.trigger_at(&triggers, "!ding", start + Duration::from_secs(3)).is_some());
+}
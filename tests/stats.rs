@@ -0,0 +1,35 @@
+use misskey_post_viewer::stats::StatsRecorder;
+use std::time::{Duration, Instant};
+
+fn emojis(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+#[test]
+fn aggregates_recent_posts() {
+    let start = Instant::now();
+    let mut stats = StatsRecorder::new(Duration::from_secs(120));
+    stats.record_at(start, "alice", "misskey.io", emojis(&["blobcat", "blobcat"]));
+    stats.record_at(start, "bob", "misskey.io", emojis(&["party"]));
+    stats.record_at(start, "alice", "misskey.io", emojis(&["blobcat"]));
+    stats.record_at(start, "carol", "remote.example", Vec::new());
+
+    let summary = stats.summary_at(start, 2);
+    assert_eq!(summary.total_posts, 4);
+    assert_eq!(summary.posts_per_minute, 2.0);
+    assert_eq!(summary.top_emojis, vec![("blobcat".to_string(), 3), ("party".to_string(), 1)]);
+    assert_eq!(summary.top_users[0], ("alice@misskey.io".to_string(), 2));
+    assert_eq!(summary.top_hosts, vec![("misskey.io".to_string(), 3), ("remote.example".to_string(), 1)]);
+}
+
+#[test]
+fn drops_posts_outside_window() {
+    let start = Instant::now();
+    let mut stats = StatsRecorder::new(Duration::from_secs(60));
+    stats.record_at(start, "alice", "misskey.io", emojis(&["old"]));
+    stats.record_at(start + Duration::from_secs(50), "bob", "misskey.io", emojis(&["new"]));
+
+    let summary = stats.summary_at(start + Duration::from_secs(90), 5);
+    assert_eq!(summary.total_posts, 1);
+    assert_eq!(summary.top_emojis, vec![("new".to_string(), 1)]);
+}
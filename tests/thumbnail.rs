@@ -0,0 +1,25 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use misskey_post_viewer::thumbnail::{resize_for_display, ImageShape};
+
+fn solid(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([200, 100, 50, 255])))
+}
+
+#[test]
+fn fit_keeps_aspect_ratio() {
+    let resized = resize_for_display(&solid(400, 200), 64, ImageShape::Fit);
+    assert_eq!(resized.dimensions(), (64, 32));
+
+    // 小さい画像は拡大しない
+    let small = resize_for_display(&solid(16, 8), 64, ImageShape::Fit);
+    assert_eq!(small.dimensions(), (16, 8));
+}
+
+#[test]
+fn circle_crops_square_and_masks_corners() {
+    let avatar = resize_for_display(&solid(300, 200), 48, ImageShape::Circle);
+    assert_eq!(avatar.dimensions(), (48, 48));
+    assert_eq!(avatar.get_pixel(0, 0)[3], 0, "corner should be transparent");
+    assert_eq!(avatar.get_pixel(47, 47)[3], 0, "corner should be transparent");
+    assert_eq!(avatar.get_pixel(24, 24)[3], 255, "center should be opaque");
+}
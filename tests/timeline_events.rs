@@ -0,0 +1,60 @@
+use misskey_post_viewer::{parse_event, ParseOptions, TimelineEvent};
+
+#[test]
+fn reaction_event() {
+    let json = r#"{"type":"noteUpdated","body":{"id":"9abc","type":"reacted","body":{"reaction":":blobcat:","userId":"u1"}}}"#;
+    match parse_event(json, &ParseOptions::default()).unwrap() {
+        TimelineEvent::Reacted { note_id, reaction, user_id } => {
+            assert_eq!(note_id, "9abc");
+            assert_eq!(reaction, ":blobcat:");
+            assert_eq!(user_id.as_deref(), Some("u1"));
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn deleted_event() {
+    let json = r#"{"type":"noteUpdated","body":{"id":"9abc","type":"deleted","body":{"deletedAt":"2024-01-01T00:00:00.000Z"}}}"#;
+    assert!(matches!(
+        parse_event(json, &ParseOptions::default()).unwrap(),
+        TimelineEvent::Deleted { note_id } if note_id == "9abc"
+    ));
+}
+
+#[test]
+fn unknown_event_is_other() {
+    let json = r#"{"type":"channel","body":{"id":"1","type":"unreadNotification","body":{}}}"#;
+    assert!(matches!(
+        parse_event(json, &ParseOptions::default()).unwrap(),
+        TimelineEvent::Other { event_type } if event_type == "channel/unreadNotification"
+    ));
+}
+
+#[test]
+fn updated_event_applies_options() {
+    let json = r#"{"type":"noteUpdated","body":{"id":"9abc","type":"updated","body":{"text":"  **edited**  ","cw":null}}}"#;
+    match parse_event(json, &ParseOptions::default()).unwrap() {
+        TimelineEvent::Updated { note_id, text, cw } => {
+            assert_eq!(note_id, "9abc");
+            assert_eq!(text.as_deref(), Some("edited"));
+            assert_eq!(cw, None);
+        }
+        other => panic!("unexpected event: {:?}", other),
+    }
+}
+
+#[test]
+fn channel_errors_carry_the_reason() {
+    let cases = [
+        (r#"{"type":"channelError","body":{"id":"main","message":"Credential required."}}"#, "Credential required."),
+        (r#"{"type":"channel","body":{"id":"main","type":"error","body":{"error":{"message":"NO_SUCH_CHANNEL"}}}}"#, "NO_SUCH_CHANNEL"),
+        (r#"{"type":"error","body":"rate limited"}"#, "rate limited"),
+    ];
+    for (json, expected) in cases {
+        match parse_event(json, &ParseOptions::default()).unwrap() {
+            TimelineEvent::ChannelError { reason } => assert_eq!(reason, expected),
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use chrono::{Local, TimeZone};
+use misskey_post_viewer::format::{format_timestamp, is_backfill, parse_created_at};
+
+#[test]
+fn parses_iso_timestamp_into_local_time() {
+    let time = parse_created_at("2024-05-01T12:34:56.789Z").unwrap();
+    assert_eq!(time.timestamp(), 1714566896);
+    assert!(parse_created_at("not a timestamp").is_none());
+    assert!(parse_created_at("").is_none());
+}
+
+#[test]
+fn formats_with_custom_pattern() {
+    let time = Local.with_ymd_and_hms(2024, 5, 1, 9, 5, 0).unwrap();
+    assert_eq!(format_timestamp(&time, "%H:%M").as_deref(), Some("09:05"));
+    assert_eq!(format_timestamp(&time, "%m/%d %H:%M:%S").as_deref(), Some("05/01 09:05:00"));
+    // 不正な書式でもパニックしない
+    assert_eq!(format_timestamp(&time, "%Q"), None);
+}
+
+#[test]
+fn only_notes_older_than_the_subscription_are_backfill() {
+    let subscribed_at = parse_created_at("2024-05-01T12:00:00Z").unwrap();
+    assert!(is_backfill(Some("2024-05-01T11:59:30.000Z"), &subscribed_at));
+    assert!(!is_backfill(Some("2024-05-01T12:00:01.000Z"), &subscribed_at));
+    // 時刻が分からないものは捨てない
+    assert!(!is_backfill(None, &subscribed_at));
+    assert!(!is_backfill(Some("yesterday"), &subscribed_at));
+}
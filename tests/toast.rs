@@ -0,0 +1,31 @@
+use std::time::{Duration, Instant};
+
+use misskey_post_viewer::toast::{Toasts, TOAST_DURATION};
+
+#[test]
+fn toasts_expire_and_fade_out() {
+    let mut toasts = Toasts::new();
+    let now = Instant::now();
+    toasts.push_at("接続しました", [255, 255, 255], now);
+
+    let toast = toasts.visible_at(now).next().unwrap().clone();
+    assert_eq!(toast.message, "接続しました");
+    assert_eq!(toast.opacity_at(now), 1.0);
+    // 最後の方で薄くなる
+    let fading = toast.opacity_at(now + TOAST_DURATION - Duration::from_millis(250));
+    assert!(fading > 0.0 && fading < 1.0);
+
+    assert_eq!(toasts.visible_at(now + TOAST_DURATION).count(), 0);
+    assert!(toasts.is_empty());
+}
+
+#[test]
+fn oldest_toasts_are_dropped_when_full() {
+    let mut toasts = Toasts::new();
+    let now = Instant::now();
+    for i in 0..8 {
+        toasts.push_at(format!("{}", i), [255, 255, 255], now);
+    }
+    let messages: Vec<String> = toasts.visible_at(now).map(|t| t.message.clone()).collect();
+    assert_eq!(messages, ["3", "4", "5", "6", "7"]);
+}